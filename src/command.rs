@@ -1,5 +1,10 @@
+use crate::storage::column::{ColumnType, ColumnValue};
+use crate::storage::schema::{ColumnSchema, DefaultValue, TableSchema};
 use crate::{database, errors, session, sql, storage};
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// Result of executing an SQL statement.
 ///
@@ -7,11 +12,16 @@ use std::sync::{Arc, Mutex};
 #[derive(Debug)]
 pub enum SqlResult {
     /// Success response for `INSERT`, `UPDATE`, `DELETE`, or other commands with affected row count.
-    Ok { affected_rows: u64 },
+    Ok {
+        affected_rows: u64,
+        /// Primary key of the row added by an `INSERT`.
+        last_insert_id: Option<u32>,
+    },
     /// Result set from `SELECT`-like queries, containing column names and rows of data.
     ResultSet {
         columns: Vec<String>,
-        rows: Vec<Vec<String>>,
+        /// One cell per column, `None` for NULL.
+        rows: Vec<Vec<Option<String>>>,
     },
 }
 
@@ -31,15 +41,26 @@ pub enum SqlResult {
 /// let result = execute(&mut session, command).unwrap();
 /// match result {
 ///     SqlResult::ResultSet { columns, rows } => println!("Columns: {:?}", columns),
-///     SqlResult::Ok { affected_rows } => println!("Affected rows: {}", affected_rows),
+///     SqlResult::Ok { affected_rows, .. } => println!("Affected rows: {}", affected_rows),
 /// }
 /// ```
 pub fn execute(
     session: &mut session::Session,
     command: sql::SqlCommand,
 ) -> Result<SqlResult, errors::Error> {
-    match command.statement {
-        sql::Statement::Select(select_stmt) => execute_select_statement(session, select_stmt),
+    session.warnings.clear();
+    if let Some(name) = command
+        .database
+        .clone()
+        .filter(|name| *name != session.database.name)
+    {
+        return execute_in_database(session, &name, command);
+    }
+    if requires_database(&command.statement) && !session.database.exists() {
+        return Err(err!(Command, "No database selected; run USE <db> first"));
+    }
+    let result = match command.statement {
+        sql::Statement::Select(select_stmt) => execute_select_statement(session, *select_stmt),
         sql::Statement::Insert(insert_stmt) => execute_insert_statement(session, insert_stmt),
         sql::Statement::Update(update_stmt) => execute_update_statement(session, update_stmt),
         sql::Statement::Delete(delete_stmt) => execute_delete_statement(session, delete_stmt),
@@ -49,7 +70,74 @@ pub fn execute(
         sql::Statement::Describe(describe_stmt) => {
             execute_describe_statement(session, describe_stmt)
         }
+        sql::Statement::Truncate(truncate_stmt) => {
+            execute_truncate_statement(session, truncate_stmt)
+        }
+        sql::Statement::Optimize(optimize_stmt) => {
+            execute_optimize_statement(session, optimize_stmt)
+        }
+        sql::Statement::Alter(alter_stmt) => execute_alter_statement(session, alter_stmt),
+        sql::Statement::Set(set_stmt) => {
+            session.settings.set(&set_stmt.name, &set_stmt.value)?;
+            Ok(SqlResult::Ok {
+                affected_rows: 0,
+                last_insert_id: None,
+            })
+        }
+    }?;
+
+    if session.settings.autocommit {
+        session.database.flush(session.lock_timeout)?;
     }
+    Ok(result)
+}
+
+/// Executes a command whose tables are qualified with another database than the
+/// current one.
+///
+/// The database is opened for the statement only, then flushed and closed, and the
+/// current database is restored whether the statement succeeds or not.
+///
+/// # Arguments
+/// * `session` - The session context.
+/// * `name` - The database named by the qualified table names.
+/// * `command` - The SQL command, holding the bare table names.
+///
+/// # Returns
+/// A `Result` containing the `SqlResult`, or an `errors::Error::Storage` if the
+/// database doesn't exist.
+fn execute_in_database(
+    session: &mut session::Session,
+    name: &String,
+    command: sql::SqlCommand,
+) -> Result<SqlResult, errors::Error> {
+    let database = database::Database::get_in(&session.data_dir, name)?;
+    let current = std::mem::replace(&mut session.database, database);
+    let result = execute(
+        session,
+        sql::SqlCommand {
+            database: None,
+            ..command
+        },
+    );
+    let database = std::mem::replace(&mut session.database, current);
+    database.flush(session.lock_timeout)?;
+    result
+}
+
+/// Checks whether a statement works on the tables of the current database.
+///
+/// Statements about databases themselves, settings and `SELECT` without `FROM`
+/// run without one.
+fn requires_database(statement: &sql::Statement) -> bool {
+    !matches!(
+        statement,
+        sql::Statement::Create(sql::CreateStatement::CreateDatabaseStatement(_))
+            | sql::Statement::Drop(sql::DropStatement::DropDatabasesStatement(_))
+            | sql::Statement::Show(sql::ShowStatement::ShowDatabasesStatement(_))
+            | sql::Statement::Show(sql::ShowStatement::ShowVariablesStatement)
+            | sql::Statement::Set(_)
+    ) && !matches!(statement, sql::Statement::Select(stmt) if stmt.table.is_none())
 }
 
 /// Executes a `SELECT` statement.
@@ -64,44 +152,502 @@ fn execute_select_statement(
     session: &mut session::Session,
     stmt: sql::SelectStatement,
 ) -> Result<SqlResult, errors::Error> {
-    let table = session.database.find_table(&stmt.table)?;
-    let rows = execute_select(table)?;
+    let Some(table_name) = stmt.table.clone() else {
+        return execute_constant_select(stmt);
+    };
+    let table = session.database.find_table(&table_name)?;
+    // Aggregates need every matching row, so the row cap applies to their result only
+    let aggregated = !stmt.group_by.is_empty()
+        || stmt.having.is_some()
+        || matches!(&stmt.columns, sql::Columns::List(exprs) if exprs.iter().any(|e| e.has_aggregate()));
+    let max_rows = session.settings.max_rows;
+    let capped = max_rows > 0 && !aggregated;
+    // The scan can stop at the cap only when its rows come out unsorted and unjoined.
+    // It reads one row past the cap to tell whether the result was truncated.
+    let scan_limit =
+        (capped && stmt.join.is_none() && stmt.order_by.is_empty()).then_some(max_rows + 1);
+    let matches = |row: &ScopedRow| match &stmt.where_clause {
+        Some(predicate) => is_true(evaluate(predicate, row)?, predicate),
+        None => Ok(true),
+    };
+
+    let rows: Vec<ScopedRow> = match &stmt.join {
+        Some(join) => {
+            let left_rows = execute_select(table, &table_name, session.lock_timeout, None)?;
+            let mut filtered = Vec::new();
+            for row in execute_join(session, &table_name, left_rows, join)? {
+                if matches(&row)? {
+                    filtered.push(row);
+                }
+            }
+            filtered
+        }
+        None => {
+            // Filter while scanning so only matching rows are kept in memory
+            let mut filtered = Vec::new();
+            for_each_selected_row(table, &table_name, session.lock_timeout, |row| {
+                let row = vec![(table_name.clone(), row)];
+                if matches(&row)? {
+                    filtered.push(row);
+                }
+                Ok(scan_limit.is_none_or(|limit| filtered.len() < limit))
+            })?;
+            filtered
+        }
+    };
+    if aggregated {
+        let sql::Columns::List(exprs) = &stmt.columns else {
+            return Err(err!(Semantic, "SELECT * cannot be combined with GROUP BY"));
+        };
+        let mut result = project_groups(
+            exprs,
+            &stmt.group_by,
+            stmt.having.as_ref(),
+            &stmt.order_by,
+            rows,
+        )?;
+        if let SqlResult::ResultSet { rows, .. } = &mut result {
+            cap_rows(session, rows);
+        }
+        return Ok(result);
+    }
+
+    // The btree yields rows in primary key order, so sorting by the key is redundant
+    let scan_order = rows
+        .first()
+        .and_then(|row| primary_key_order(&stmt, &table_name, row[0].1.schema()));
+    let mut rows = match scan_order {
+        Some(true) => rows,
+        Some(false) => rows.into_iter().rev().collect(),
+        None => sort_rows(rows, &stmt.order_by, Vec::as_slice)?,
+    };
+    cap_rows(session, &mut rows);
 
     if rows.is_empty() {
-        return Ok(SqlResult::Ok { affected_rows: 0 });
+        return Ok(SqlResult::Ok {
+            affected_rows: 0,
+            last_insert_id: None,
+        });
     }
 
-    let columns = match stmt.columns {
-        sql::Columns::All => {
-            let locked_table = table.lock().map_err(|_| {
-                errors::Error::LockTable("Failed to lock table for schema access".to_string())
-            })?;
-            locked_table
-                .schema
-                .columns
-                .iter()
-                .map(|c| c.name.clone())
-                .collect()
-        }
-        sql::Columns::List(cols) => cols,
+    if matches!(stmt.columns, sql::Columns::All) && stmt.join.is_none() {
+        let columns: Vec<String> = rows[0][0]
+            .1
+            .schema()
+            .columns()
+            .iter()
+            .map(|c| c.name.clone())
+            .collect();
+        let rows = rows
+            .iter()
+            .map(|row| row[0].1.to_values(&columns))
+            .collect();
+        return Ok(SqlResult::ResultSet { columns, rows });
+    }
+
+    let exprs: Vec<sql::Expression> = match stmt.columns {
+        // Without a JOIN, `SELECT *` was projected above. With one, it expands to the
+        // columns of every table in order, qualified so same-named columns stay apart.
+        sql::Columns::All => rows[0]
+            .iter()
+            .flat_map(|(table, row)| {
+                row.schema().columns().iter().map(|c| {
+                    sql::Expression::Column(sql::ColumnRef {
+                        table: Some(table.clone()),
+                        name: c.name.clone(),
+                    })
+                })
+            })
+            .collect(),
+        sql::Columns::List(exprs) => exprs,
     };
 
     let formatted_rows = rows
         .into_iter()
         .map(|row| {
-            columns
+            exprs
                 .iter()
-                .map(|col| row.get_column(col).unwrap_or_else(|| "-".to_string()))
-                .collect()
+                .map(|expr| Ok(evaluate(expr, &row)?.map(|v| v.to_string())))
+                .collect::<Result<Vec<_>, errors::Error>>()
         })
-        .collect();
+        .collect::<Result<Vec<_>, errors::Error>>()?;
 
+    // Headers are the expressions as written, like MySQL: `SELECT id, id` yields two
+    // `id` columns rather than deduplicating or renaming them.
     Ok(SqlResult::ResultSet {
-        columns,
+        columns: exprs.iter().map(|e| e.to_string()).collect(),
         rows: formatted_rows,
     })
 }
 
+/// Truncates a result to the `max_rows` cap of the session, warning when rows are
+/// dropped.
+fn cap_rows<T>(session: &mut session::Session, rows: &mut Vec<T>) {
+    let max_rows = session.settings.max_rows;
+    if max_rows > 0 && rows.len() > max_rows {
+        rows.truncate(max_rows);
+        session.warnings.push(format!(
+            "Result truncated to {} rows, raise the cap with SET max_rows",
+            max_rows
+        ));
+    }
+}
+
+/// Groups the rows of an aggregate query and projects one result row per group.
+///
+/// Rows are grouped by the values of the `GROUP BY` columns, keeping groups in the
+/// order their first row appears; without `GROUP BY` all rows form a single group,
+/// which yields a row even when there are no rows. `HAVING` then drops the groups
+/// its predicate doesn't hold for, and `ORDER BY` sorts the remaining ones.
+///
+/// # Arguments
+/// * `exprs` - The projected expressions.
+/// * `group_by` - The grouping columns, possibly empty.
+/// * `having` - The `HAVING` predicate, if any.
+/// * `order_by` - The sort keys, which must be grouping columns.
+/// * `rows` - All rows matching the query.
+///
+/// # Returns
+/// A `Result` containing the `SqlResult`, or an `errors::Error::Semantic` if an
+/// expression references a column that is neither grouped nor aggregated.
+fn project_groups(
+    exprs: &[sql::Expression],
+    group_by: &[sql::ColumnRef],
+    having: Option<&sql::Expression>,
+    order_by: &[sql::OrderByKey],
+    rows: Vec<ScopedRow>,
+) -> Result<SqlResult, errors::Error> {
+    for expr in exprs.iter().chain(having) {
+        if let Some(column) = ungrouped_column(expr, group_by) {
+            return Err(match group_by {
+                [] => err!(
+                    Semantic,
+                    "In aggregated query without GROUP BY, expression '{}' contains nonaggregated column '{}'",
+                    expr,
+                    column
+                ),
+                _ => err!(
+                    Semantic,
+                    "Expression '{}' of SELECT list is not in GROUP BY clause and contains nonaggregated column '{}'",
+                    expr,
+                    column
+                ),
+            });
+        }
+    }
+
+    let mut groups: Vec<Vec<ScopedRow>> = Vec::new();
+    if group_by.is_empty() {
+        groups.push(rows);
+    } else {
+        let mut index: HashMap<Vec<Option<String>>, usize> = HashMap::new();
+        for row in rows {
+            let key = group_by
+                .iter()
+                .map(|column| Ok(resolve_column(column, &row)?.map(|v| v.to_string())))
+                .collect::<Result<Vec<_>, errors::Error>>()?;
+            match index.get(&key) {
+                Some(&i) => groups[i].push(row),
+                None => {
+                    index.insert(key, groups.len());
+                    groups.push(vec![row]);
+                }
+            }
+        }
+    }
+    if let Some(predicate) = having {
+        let mut kept = Vec::new();
+        for group in groups {
+            if is_true(evaluate_group(predicate, &group)?, predicate)? {
+                kept.push(group);
+            }
+        }
+        groups = kept;
+    }
+    if !group_by.is_empty() {
+        for key in order_by {
+            let expr = sql::Expression::Column(key.column.clone());
+            if let Some(column) = ungrouped_column(&expr, group_by) {
+                return Err(err!(
+                    Semantic,
+                    "Expression '{}' of ORDER BY clause is not in GROUP BY clause",
+                    column
+                ));
+            }
+        }
+    }
+    // Every row of a group has the same grouping columns, so its first row sorts it
+    let groups = sort_rows(groups, order_by, |group| group[0].as_slice())?;
+    if groups.is_empty() {
+        return Ok(SqlResult::Ok {
+            affected_rows: 0,
+            last_insert_id: None,
+        });
+    }
+
+    let rows = groups
+        .iter()
+        .map(|group| {
+            exprs
+                .iter()
+                .map(|expr| Ok(evaluate_group(expr, group)?.map(|v| v.to_string())))
+                .collect::<Result<Vec<_>, errors::Error>>()
+        })
+        .collect::<Result<Vec<_>, errors::Error>>()?;
+    Ok(SqlResult::ResultSet {
+        columns: exprs.iter().map(|e| e.to_string()).collect(),
+        rows,
+    })
+}
+
+/// Returns the first column an expression references outside of an aggregate that is
+/// not one of the `GROUP BY` columns.
+fn ungrouped_column<'a>(
+    expr: &'a sql::Expression,
+    group_by: &[sql::ColumnRef],
+) -> Option<&'a sql::ColumnRef> {
+    match expr {
+        sql::Expression::Column(column) => {
+            let grouped = group_by.iter().any(|g| {
+                g.name == column.name
+                    && (g.table.is_none() || column.table.is_none() || g.table == column.table)
+            });
+            (!grouped).then_some(column)
+        }
+        sql::Expression::Literal(_) | sql::Expression::Aggregate { .. } => None,
+        sql::Expression::Binary { left, right, .. } => {
+            ungrouped_column(left, group_by).or_else(|| ungrouped_column(right, group_by))
+        }
+    }
+}
+
+/// Executes a `SELECT` without `FROM`, evaluating its expressions once.
+///
+/// # Arguments
+/// * `stmt` - The `SelectStatement` to execute.
+///
+/// # Returns
+/// A `Result` containing a single-row `SqlResult::ResultSet` or an `errors::Error`.
+fn execute_constant_select(stmt: sql::SelectStatement) -> Result<SqlResult, errors::Error> {
+    let sql::Columns::List(exprs) = stmt.columns else {
+        return Err(err!(Semantic, "SELECT * requires a FROM clause"));
+    };
+    if let Some(column) = exprs.iter().flat_map(|e| e.column_refs()).next() {
+        return Err(err!(Semantic, "Unknown column '{}' in field list", column));
+    }
+
+    let row = exprs
+        .iter()
+        .map(|expr| Ok(evaluate(expr, &[])?.map(|v| v.to_string())))
+        .collect::<Result<Vec<_>, errors::Error>>()?;
+    Ok(SqlResult::ResultSet {
+        columns: exprs.iter().map(|e| e.to_string()).collect(),
+        rows: vec![row],
+    })
+}
+
+/// A row of a `SELECT`, made of one row per table in scope tagged with the table name.
+type ScopedRow = Vec<(String, storage::Row)>;
+
+/// Executes an `INNER JOIN` as a nested loop over the rows of the left table.
+///
+/// For each left row the joined table is probed by key when the join column is its
+/// primary key; otherwise its rows are scanned once and matched by value.
+///
+/// # Arguments
+/// * `session` - The session context.
+/// * `left` - The name of the `FROM` table.
+/// * `left_rows` - The rows of the `FROM` table.
+/// * `join` - The `JoinClause` to execute.
+///
+/// # Returns
+/// A `Result` containing the combined rows or an `errors::Error`.
+fn execute_join(
+    session: &session::Session,
+    left: &str,
+    left_rows: Vec<storage::Row>,
+    join: &sql::JoinClause,
+) -> Result<Vec<ScopedRow>, errors::Error> {
+    if join.table == left {
+        return Err(err!(Semantic, "Not unique table: '{}'", left));
+    }
+
+    // Orient the condition so that `probe` is the column of the joined table.
+    let qualifier = |column: &sql::ColumnRef| {
+        column.table.clone().ok_or_else(|| {
+            err!(
+                Semantic,
+                "Column '{}' in 'JOIN ... ON' must be qualified with its table",
+                column
+            )
+        })
+    };
+    let (outer, probe) = match (qualifier(&join.left)?, qualifier(&join.right)?) {
+        (l, r) if l == left && r == join.table => (&join.left, &join.right),
+        (l, r) if l == join.table && r == left => (&join.right, &join.left),
+        _ => {
+            return Err(err!(
+                Semantic,
+                "'JOIN ... ON' must compare a column of '{}' with a column of '{}'",
+                left,
+                join.table
+            ))
+        }
+    };
+
+    let table = session.database.find_table(&join.table)?;
+    let mut locked_table = storage::lock_with_timeout(table, &join.table, session.lock_timeout)?;
+    let by_key = locked_table
+        .schema
+        .columns()
+        .iter()
+        .find(|c| c.is_primary)
+        .is_some_and(|c| c.name == probe.name);
+    let scanned = if by_key {
+        Vec::new()
+    } else {
+        storage::select_rows(&mut locked_table, None)?
+    };
+
+    let mut rows = Vec::new();
+    for left_row in left_rows {
+        // NULL matches nothing, not even another NULL
+        let Some(value) = left_row.get(&outer.name).filter(|v| !v.is_null()) else {
+            continue;
+        };
+        let matches = if by_key {
+            match key_of(value) {
+                Some(key) => storage::find_row(&mut locked_table, key)?
+                    .into_iter()
+                    .collect(),
+                None => Vec::new(),
+            }
+        } else {
+            scanned
+                .iter()
+                .filter(|row| {
+                    row.get(&probe.name)
+                        .is_some_and(|v| !v.is_null() && values_equal(value, v))
+                })
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        for right_row in matches {
+            rows.push(vec![
+                (left.to_string(), left_row.clone()),
+                (join.table.clone(), right_row),
+            ]);
+        }
+    }
+    Ok(rows)
+}
+
+/// Checks whether a `SELECT` is ordered by nothing but the primary key of its table.
+///
+/// # Arguments
+/// * `stmt` - The `SelectStatement` being executed.
+/// * `table_name` - The table scanned by the statement.
+/// * `schema` - The schema of the scanned table.
+///
+/// # Returns
+/// `Some(ascending)` when the scan order already matches the `ORDER BY`, up to
+/// reversing it, and `None` when the rows must be sorted.
+fn primary_key_order(
+    stmt: &sql::SelectStatement,
+    table_name: &str,
+    schema: &TableSchema,
+) -> Option<bool> {
+    let [key] = stmt.order_by.as_slice() else {
+        return None;
+    };
+    if stmt.join.is_some() || key.column.table.as_deref().is_some_and(|t| t != table_name) {
+        return None;
+    }
+    schema
+        .columns()
+        .iter()
+        .any(|c| c.is_primary && c.name == key.column.name)
+        .then_some(key.ascending)
+}
+
+/// Sorts rows by the keys of an `ORDER BY` clause.
+///
+/// The sort is stable: rows that tie on every key keep their scan order, and a tie on
+/// one key falls through to the next.
+///
+/// # Arguments
+/// * `rows` - The rows to sort, or groups of rows.
+/// * `order_by` - The sort keys, most significant first.
+/// * `scope` - Returns the row the keys of an item are resolved against.
+///
+/// # Returns
+/// A `Result` containing the sorted rows or an `errors::Error` for invalid column references.
+fn sort_rows<T>(
+    rows: Vec<T>,
+    order_by: &[sql::OrderByKey],
+    scope: fn(&T) -> &[(String, storage::Row)],
+) -> Result<Vec<T>, errors::Error> {
+    if order_by.is_empty() {
+        return Ok(rows);
+    }
+    let mut keyed = rows
+        .into_iter()
+        .map(|row| {
+            let keys = order_by
+                .iter()
+                .map(|key| Ok(resolve_column(&key.column, scope(&row))?.cloned()))
+                .collect::<Result<Vec<_>, errors::Error>>()?;
+            Ok((keys, row))
+        })
+        .collect::<Result<Vec<_>, errors::Error>>()?;
+
+    keyed.sort_by(|(a, _), (b, _)| {
+        order_by
+            .iter()
+            .zip(a.iter().zip(b))
+            .map(|(key, (a, b))| match (a, b) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) if key.nulls == sql::NullsOrder::First => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) if key.nulls == sql::NullsOrder::First => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => {
+                    let ordering = compare_values(a, b).unwrap_or(Ordering::Equal);
+                    if key.ascending {
+                        ordering
+                    } else {
+                        ordering.reverse()
+                    }
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+    Ok(keyed.into_iter().map(|(_, row)| row).collect())
+}
+
+/// Converts a column value to a primary key, if it is a valid one.
+fn key_of(value: &ColumnValue) -> Option<u32> {
+    match value {
+        ColumnValue::Int(v) => u32::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+/// Compares two column values, numerically when both are numbers.
+fn compare_values(a: &ColumnValue, b: &ColumnValue) -> Option<Ordering> {
+    match (Number::from_value(a), Number::from_value(b)) {
+        (Some(Number::Integer(a)), Some(Number::Integer(b))) => Some(a.cmp(&b)),
+        (Some(a), Some(b)) => a.to_real().partial_cmp(&b.to_real()),
+        _ => Some(a.to_string().cmp(&b.to_string())),
+    }
+}
+
+/// Compares two column values for equality, numerically when both are numbers.
+fn values_equal(a: &ColumnValue, b: &ColumnValue) -> bool {
+    compare_values(a, b) == Some(Ordering::Equal)
+}
+
 /// Executes a `DESCRIBE` statement.
 ///
 /// # Arguments
@@ -114,7 +660,10 @@ fn execute_describe_statement(
     session: &mut session::Session,
     stmt: sql::DescribeStatement,
 ) -> Result<SqlResult, errors::Error> {
-    let _ = session.database.find_table(&stmt.name)?;
+    let table = session.database.find_table(&stmt.name)?;
+    let schema = storage::lock_with_timeout(table, &stmt.name, session.lock_timeout)?
+        .schema
+        .clone();
 
     let columns: Vec<String> = Vec::from([
         "Field".into(),
@@ -125,18 +674,20 @@ fn execute_describe_statement(
         "Extra".into(),
     ]);
 
-    let rows: Vec<Vec<String>> = storage::SCHEMA
-        .columns
-        .clone()
-        .into_iter()
+    let rows = schema
+        .columns()
+        .iter()
         .map(|c| {
             Vec::from([
-                c.name.clone(),
-                c.type_.clone().to_string(),
-                c.is_nullable.then(|| "YES").unwrap_or("NO").to_string(),
-                c.is_primary.then(|| "PRI").unwrap_or("-").to_string(),
-                c.default.clone().unwrap_or("NULL".to_string()),
-                "".into(),
+                Some(c.name.clone()),
+                Some(c.type_.clone().to_string()),
+                Some(if c.is_nullable { "YES" } else { "NO" }.to_string()),
+                Some(describe_key(c).to_string()),
+                c.default
+                    .as_ref()
+                    .filter(|d| **d != DefaultValue::AutoIncrement)
+                    .map(|d| d.to_string()),
+                Some(describe_extra(c).to_string()),
             ])
         })
         .collect();
@@ -144,6 +695,27 @@ fn execute_describe_statement(
     Ok(SqlResult::ResultSet { columns, rows })
 }
 
+/// Returns the `Key` shown by `DESCRIBE` for a column.
+///
+/// Only the primary key is indexed, so columns are either `PRI` or not part of a key;
+/// there are no unique (`UNI`) or secondary (`MUL`) indexes.
+fn describe_key(column: &ColumnSchema) -> &'static str {
+    if column.is_primary {
+        "PRI"
+    } else {
+        "-"
+    }
+}
+
+/// Returns the `Extra` shown by `DESCRIBE` for a column: `auto_increment` for an
+/// `AUTO_INCREMENT` key, empty otherwise.
+fn describe_extra(column: &ColumnSchema) -> &'static str {
+    match column.default {
+        Some(DefaultValue::AutoIncrement) => "auto_increment",
+        _ => "",
+    }
+}
+
 /// Executes an `INSERT` statement.
 ///
 /// # Arguments
@@ -157,9 +729,107 @@ fn execute_insert_statement(
     stmt: sql::InsertStatement,
 ) -> Result<SqlResult, errors::Error> {
     let table = session.database.find_table(&stmt.table)?;
-    let row = storage::build_row(&storage::SCHEMA, &stmt.columns, &stmt.values)?;
-    execute_insert(table, row)?;
-    Ok(SqlResult::Ok { affected_rows: 1 })
+    let mut locked_table = storage::lock_with_timeout(table, &stmt.table, session.lock_timeout)?;
+    let schema = locked_table.schema.clone();
+    let mut columns = Vec::with_capacity(stmt.columns.len());
+    let mut values = Vec::with_capacity(stmt.values.len());
+    // Indexes into the schema of the columns set to NULL
+    let mut nulls = Vec::new();
+    for (name, value) in stmt.columns.into_iter().zip(&stmt.values) {
+        match value {
+            sql::InsertValue::Literal(value) => values.push(value.clone()),
+            sql::InsertValue::Now => values.push(storage::current_datetime()?),
+            sql::InsertValue::Null => {
+                let index = schema
+                    .columns()
+                    .iter()
+                    .position(|c| c.name == name)
+                    .ok_or_else(|| err!(Semantic, "Unknown column '{}' in 'field list'", name))?;
+                let column = &schema.columns()[index];
+                // NULL for an AUTO_INCREMENT key assigns the next key, as if it was omitted
+                if column.default != Some(DefaultValue::AutoIncrement) {
+                    if !column.accepts_null() {
+                        return Err(err!(Schema, "Column '{}' cannot be null", name));
+                    }
+                    nulls.push(index);
+                }
+                continue;
+            }
+        }
+        columns.push(name);
+    }
+    check_data_length(
+        session.settings.strict_mode,
+        &mut session.warnings,
+        &schema,
+        &columns,
+        &values,
+    )?;
+    // The table stays locked, so no other insert can take the key assigned here
+    let auto_increment = schema
+        .columns()
+        .iter()
+        .find(|c| c.default == Some(DefaultValue::AutoIncrement) && !columns.contains(&c.name));
+    if let Some(column) = auto_increment {
+        let next = match locked_table.max_key()? {
+            Some(key) => key
+                .checked_add(1)
+                .ok_or_else(|| err!(Schema, "Failed to read auto-increment value"))?,
+            None => 1,
+        };
+        columns.push(column.name.clone());
+        values.push(next.to_string());
+    }
+    let mut row = storage::build_row(&schema, &columns, &values)?;
+    for index in nulls {
+        row.values[index] = ColumnValue::Null;
+    }
+    let id = storage::insert_row(&mut locked_table, &row)?;
+    session.last_insert_id = Some(id);
+    Ok(SqlResult::Ok {
+        affected_rows: 1,
+        last_insert_id: Some(id),
+    })
+}
+
+/// Checks that string values fit the length of their `VARCHAR` or `CHAR` column.
+///
+/// Longer values are truncated when the row is built; in strict mode they are
+/// rejected instead, otherwise each truncation adds a warning.
+///
+/// # Arguments
+/// * `strict` - Whether strict mode is on.
+/// * `warnings` - The warnings of the statement.
+/// * `schema` - The schema of the table inserted into.
+/// * `columns` - The inserted column names.
+/// * `values` - The inserted values, in the order of `columns`.
+///
+/// # Returns
+/// An `errors::Error::Schema` for the first value too long in strict mode.
+fn check_data_length(
+    strict: bool,
+    warnings: &mut Vec<String>,
+    schema: &TableSchema,
+    columns: &[String],
+    values: &[String],
+) -> Result<(), errors::Error> {
+    for (column, value) in columns.iter().zip(values) {
+        let len = match schema.columns().iter().find(|c| &c.name == column) {
+            Some(ColumnSchema {
+                type_: ColumnType::VARCHAR(len) | ColumnType::CHAR(len),
+                ..
+            }) => *len as usize,
+            _ => continue,
+        };
+        if value.len() <= len {
+            continue;
+        }
+        if strict {
+            return Err(err!(Schema, "Data too long for column '{}'", column));
+        }
+        warnings.push(format!("Data truncated for column '{}'", column));
+    }
+    Ok(())
 }
 
 /// Executes an `UPDATE` statement.
@@ -175,16 +845,20 @@ fn execute_update_statement(
     stmt: sql::UpdateStatement,
 ) -> Result<SqlResult, errors::Error> {
     let table = session.database.find_table(&stmt.table)?;
-    let mut _locked_table = table
-        .lock()
-        .map_err(|_| errors::Error::LockTable("Failed to lock table for update".to_string()))?;
+    let mut _locked_table = storage::lock_with_timeout(table, &stmt.table, session.lock_timeout)?;
     // let affected_rows = storage::update_rows(&mut locked_table, &stmt.sets, &stmt.where_clause)?;
     let affected_rows = 0;
-    Ok(SqlResult::Ok { affected_rows })
+    Ok(SqlResult::Ok {
+        affected_rows,
+        last_insert_id: None,
+    })
 }
 
 /// Executes a `DELETE` statement.
 ///
+/// A `pk = literal` predicate goes straight to its key; any other predicate is
+/// evaluated against every row of the table.
+///
 /// # Arguments
 /// * `session` - The session context.
 /// * `stmt` - The `DeleteStatement` to execute.
@@ -196,12 +870,124 @@ fn execute_delete_statement(
     stmt: sql::DeleteStatement,
 ) -> Result<SqlResult, errors::Error> {
     let table = session.database.find_table(&stmt.table)?;
-    let mut _locked_table = table
-        .lock()
-        .map_err(|_| errors::Error::LockTable("Failed to lock table for delete".to_string()))?;
-    // let affected_rows = storage::delete_rows(&mut locked_table, &stmt.where_clause)?;
-    let affected_rows = 0;
-    Ok(SqlResult::Ok { affected_rows })
+    let mut locked_table = storage::lock_with_timeout(table, &stmt.table, session.lock_timeout)?;
+    if stmt.where_clause.is_none() {
+        // An unqualified delete empties the table, so reset the tree instead of
+        // deleting rows one by one.
+        let affected_rows = locked_table.pager.table_n_recs()? as u64;
+        storage::truncate_table(&mut locked_table)?;
+        return Ok(SqlResult::Ok {
+            affected_rows,
+            last_insert_id: None,
+        });
+    }
+    let predicate = stmt.where_clause.as_ref().unwrap();
+    let keys = match primary_key_lookup(predicate, &stmt.table, &locked_table.schema) {
+        Some(key) => vec![key],
+        None => {
+            let schema = locked_table.schema.clone();
+            let mut keys = Vec::new();
+            for row in storage::select_rows(&mut locked_table, None)? {
+                let key = row.get_id(&schema)?;
+                let scoped = vec![(stmt.table.clone(), row)];
+                if is_true(evaluate(predicate, &scoped)?, predicate)? {
+                    keys.push(key);
+                }
+            }
+            keys
+        }
+    };
+    let affected_rows = storage::delete_rows(&mut locked_table, &keys)?;
+    Ok(SqlResult::Ok {
+        affected_rows,
+        last_insert_id: None,
+    })
+}
+
+/// Checks whether a `WHERE` clause selects a single row by its primary key.
+///
+/// # Arguments
+/// * `predicate` - The `WHERE` clause of the statement.
+/// * `table_name` - The table the statement applies to.
+/// * `schema` - The schema of the table.
+///
+/// # Returns
+/// The key when the predicate is `pk = literal` or `literal = pk`, and `None` when
+/// the rows must be scanned.
+fn primary_key_lookup(
+    predicate: &sql::Expression,
+    table_name: &str,
+    schema: &TableSchema,
+) -> Option<u32> {
+    let sql::Expression::Binary {
+        left,
+        operator: sql::Operator::Equal,
+        right,
+    } = predicate
+    else {
+        return None;
+    };
+    let (column, literal) = match (left.as_ref(), right.as_ref()) {
+        (sql::Expression::Column(c), l @ sql::Expression::Literal(_))
+        | (l @ sql::Expression::Literal(_), sql::Expression::Column(c)) => (c, l),
+        _ => return None,
+    };
+    if column.table.as_deref().is_some_and(|t| t != table_name)
+        || !schema
+            .columns()
+            .iter()
+            .any(|c| c.is_primary && c.name == column.name)
+    {
+        return None;
+    }
+    key_of(&evaluate(literal, &[]).ok()??)
+}
+
+/// Executes a `TRUNCATE TABLE` statement.
+///
+/// # Arguments
+/// * `session` - The session context.
+/// * `stmt` - The `TruncateStatement` to execute.
+///
+/// # Returns
+/// A `Result` containing a `SqlResult::Ok` or an `errors::Error`.
+fn execute_truncate_statement(
+    session: &mut session::Session,
+    stmt: sql::TruncateStatement,
+) -> Result<SqlResult, errors::Error> {
+    let table = session.database.find_table(&stmt.name)?;
+    let mut locked_table = storage::lock_with_timeout(table, &stmt.name, session.lock_timeout)?;
+    storage::truncate_table(&mut locked_table)?;
+    Ok(SqlResult::Ok {
+        affected_rows: 0,
+        last_insert_id: None,
+    })
+}
+
+/// Executes an `OPTIMIZE TABLE` statement.
+///
+/// # Arguments
+/// * `session` - The session context.
+/// * `stmt` - The `OptimizeStatement` to execute.
+///
+/// # Returns
+/// A `Result` containing a `SqlResult::ResultSet` with the page counts before and after
+/// the rewrite, or an `errors::Error`.
+fn execute_optimize_statement(
+    session: &mut session::Session,
+    stmt: sql::OptimizeStatement,
+) -> Result<SqlResult, errors::Error> {
+    let table = session.database.find_table(&stmt.name)?;
+    let mut locked_table = storage::lock_with_timeout(table, &stmt.name, session.lock_timeout)?;
+    let (pages_before, pages_after) = storage::optimize_table(&mut locked_table)?;
+    Ok(SqlResult::ResultSet {
+        columns: vec!["Table".into(), "Pages_before".into(), "Pages_after".into()],
+        rows: vec![vec![
+            Some(stmt.name),
+            Some(pages_before.to_string()),
+            Some(pages_after.to_string()),
+        ]],
+    })
 }
 
 /// Executes a `CREATE` statement.
@@ -218,12 +1004,19 @@ fn execute_create_statement(
 ) -> Result<SqlResult, errors::Error> {
     match stmt {
         sql::CreateStatement::CreateDatabaseStatement(db_stmt) => {
-            database::Database::create(&db_stmt.name)?;
-            Ok(SqlResult::Ok { affected_rows: 0 })
+            database::Database::create_in(&session.data_dir, &db_stmt.name)?;
+            Ok(SqlResult::Ok {
+                affected_rows: 0,
+                last_insert_id: None,
+            })
         }
         sql::CreateStatement::CreateTableStatement(table_stmt) => {
-            session.database.create_table(&table_stmt.name)?;
-            Ok(SqlResult::Ok { affected_rows: 0 })
+            let schema = TableSchema::new(table_stmt.columns_schemas);
+            session.database.create_table(&table_stmt.name, schema)?;
+            Ok(SqlResult::Ok {
+                affected_rows: 0,
+                last_insert_id: None,
+            })
         }
     }
 }
@@ -241,19 +1034,87 @@ fn execute_show_statement(
     stmt: sql::ShowStatement,
 ) -> Result<SqlResult, errors::Error> {
     match stmt {
-        sql::ShowStatement::ShowDatabasesStatement => {
+        sql::ShowStatement::ShowDatabasesStatement(pattern) => {
             let columns = vec!["Database".to_string()];
-            let rows = database::show_databases()?
+            let rows = database::show_databases(&session.data_dir)?
                 .into_iter()
-                .map(|db| vec![db])
+                .filter(|db| pattern.as_ref().is_none_or(|p| like_matches(p, db)))
+                .map(|db| vec![Some(db)])
                 .collect();
             Ok(SqlResult::ResultSet { columns, rows })
         }
-        sql::ShowStatement::ShowTablesStatement => {
+        sql::ShowStatement::ShowTablesStatement(pattern) => {
             let columns = vec![format!("Tables_in_{}", &session.database.name)];
-            let rows = storage::table::show_tables(&session.database.name)?
+            let rows = storage::table::show_tables(session.database.path())?
+                .into_iter()
+                .filter(|table| pattern.as_ref().is_none_or(|p| like_matches(p, table)))
+                .map(|table| vec![Some(table)])
+                .collect();
+            Ok(SqlResult::ResultSet { columns, rows })
+        }
+        sql::ShowStatement::ShowTableStatusStatement(pattern) => {
+            let columns = ["Name", "Rows", "Data_length", "Row_format"]
+                .map(String::from)
+                .to_vec();
+            let mut rows = Vec::new();
+            for name in storage::table::show_tables(session.database.path())? {
+                if pattern.as_ref().is_some_and(|p| !like_matches(p, &name)) {
+                    continue;
+                }
+                let table = session.database.find_table(&name)?;
+                let table = storage::lock_with_timeout(table, &name, session.lock_timeout)?;
+                // Rows are stored with a fixed size, padded to their schema row size
+                rows.push(vec![
+                    Some(name.clone()),
+                    Some(table.row_count()?.to_string()),
+                    Some((table.page_count() * storage::table::PAGE_SIZE).to_string()),
+                    Some("Fixed".to_string()),
+                ]);
+            }
+            Ok(SqlResult::ResultSet { columns, rows })
+        }
+        sql::ShowStatement::ShowVariablesStatement => {
+            let columns = vec!["Variable_name".to_string(), "Value".to_string()];
+            let rows = session
+                .settings
+                .variables()
                 .into_iter()
-                .map(|table| vec![table])
+                .map(|(name, value)| vec![Some(name.to_string()), Some(value)])
+                .collect();
+            Ok(SqlResult::ResultSet { columns, rows })
+        }
+        sql::ShowStatement::ShowColumnsStatement(name) => {
+            execute_describe_statement(session, sql::DescribeStatement { name })
+        }
+        sql::ShowStatement::ShowIndexStatement(name) => {
+            let table = session.database.find_table(&name)?;
+            let schema = storage::lock_with_timeout(table, &name, session.lock_timeout)?
+                .schema
+                .clone();
+            let columns = [
+                "Table",
+                "Non_unique",
+                "Key_name",
+                "Seq_in_index",
+                "Column_name",
+            ]
+            .map(String::from)
+            .to_vec();
+            // Only the primary key is indexed, by the table btree itself
+            let rows = schema
+                .columns()
+                .iter()
+                .filter(|c| c.is_primary)
+                .enumerate()
+                .map(|(i, c)| {
+                    vec![
+                        Some(name.clone()),
+                        Some("0".to_string()),
+                        Some("PRIMARY".to_string()),
+                        Some((i + 1).to_string()),
+                        Some(c.name.clone()),
+                    ]
+                })
                 .collect();
             Ok(SqlResult::ResultSet { columns, rows })
         }
@@ -279,50 +1140,496 @@ fn execute_drop_statement(
                     "Cannot drop the currently used database".to_string(),
                 ));
             }
-            database::drop_database(&name)?;
-            Ok(SqlResult::Ok { affected_rows: 0 })
+            database::drop_database(&session.data_dir, &name)?;
+            Ok(SqlResult::Ok {
+                affected_rows: 0,
+                last_insert_id: None,
+            })
         }
         sql::DropStatement::DropTablesStatement(name) => {
             session.database.drop_table(&name)?;
-            Ok(SqlResult::Ok { affected_rows: 0 })
+            Ok(SqlResult::Ok {
+                affected_rows: 0,
+                last_insert_id: None,
+            })
         }
     }
 }
 
-/// Inserts a row into a table.
+/// Executes an `ALTER TABLE` or `RENAME TABLE` statement.
 ///
 /// # Arguments
-/// * `table` - The table to insert into, wrapped in an `Arc<Mutex<storage::Table>>`.
-/// * `row` - The row to insert.
+/// * `session` - The session context.
+/// * `stmt` - The `AlterStatement` to execute.
 ///
 /// # Returns
-/// A `Result` indicating success or an `errors::Error` if the operation fails.
-pub fn execute_insert(
-    table: &Arc<Mutex<storage::Table>>,
-    row: storage::Row,
-) -> Result<(), errors::Error> {
-    let mut locked_table = table
-        .lock()
-        .map_err(|_| errors::Error::LockTable("Failed to lock table for insert".to_string()))?;
+/// A `Result` containing a `SqlResult::Ok` or an `errors::Error`.
+fn execute_alter_statement(
+    session: &mut session::Session,
+    stmt: sql::AlterStatement,
+) -> Result<SqlResult, errors::Error> {
+    match stmt {
+        sql::AlterStatement::RenameTableStatement { name, new_name } => {
+            session
+                .database
+                .rename_table(&name, &new_name, session.lock_timeout)?;
+            Ok(SqlResult::Ok {
+                affected_rows: 0,
+                last_insert_id: None,
+            })
+        }
+        sql::AlterStatement::RenameColumnStatement {
+            name,
+            column,
+            new_column,
+            type_,
+        } => {
+            let table = session.database.find_table(&name)?;
+            let mut locked_table = storage::lock_with_timeout(table, &name, session.lock_timeout)?;
+            let current = locked_table
+                .schema
+                .columns()
+                .iter()
+                .find(|c| c.name == column);
+            if let (Some(current), Some(type_)) = (current, type_) {
+                if current.type_.to_string() != type_.to_string() {
+                    return Err(err!(
+                        Schema,
+                        "Changing the type of column '{}' is not supported",
+                        column
+                    ));
+                }
+            }
+            storage::table::rename_column(&mut locked_table, &column, &new_column)?;
+            Ok(SqlResult::Ok {
+                affected_rows: 0,
+                last_insert_id: None,
+            })
+        }
+    }
+}
 
-    storage::insert_row(&mut locked_table, &row)?;
-    Ok(())
+/// Numeric operand of an arithmetic expression.
+enum Number {
+    Integer(i128),
+    Real(f64),
 }
 
-/// Selects rows from a table.
-///
-/// # Arguments
-/// * `table` - The table to select from, wrapped in an `Arc<Mutex<storage::Table>>`.
-///
-/// # Returns
-/// A `Result` containing a vector of `schema::Row`s or an `errors::Error`.
-pub fn execute_select(
-    table: &Arc<Mutex<storage::Table>>,
+impl Number {
+    fn from_value(value: &ColumnValue) -> Option<Self> {
+        match value {
+            // Booleans aren't numbers here, even though `as_i64` maps them to 0 and 1
+            ColumnValue::Boolean(_) => None,
+            // A BIGINT beyond the i64 range stays exact
+            ColumnValue::BigInt(v) => Some(Number::Integer(*v)),
+            _ => value
+                .as_i64()
+                .map(|v| Number::Integer(v as i128))
+                .or_else(|| value.as_f64().map(Number::Real)),
+        }
+    }
+
+    fn to_real(&self) -> f64 {
+        match self {
+            Number::Integer(v) => *v as f64,
+            Number::Real(v) => *v,
+        }
+    }
+
+    fn into_value(self) -> ColumnValue {
+        match self {
+            Number::Integer(v) => match i64::try_from(v) {
+                Ok(v) => ColumnValue::Int(v),
+                Err(_) => ColumnValue::BigInt(v),
+            },
+            Number::Real(v) => ColumnValue::Double(v),
+        }
+    }
+}
+
+/// Matches text against an SQL `LIKE` pattern, ignoring ASCII case.
+///
+/// `%` matches any run of characters, `_` matches exactly one, and a backslash makes
+/// the next character literal.
+///
+/// # Arguments
+/// * `pattern` - The `LIKE` pattern.
+/// * `text` - The text to match.
+fn like_matches(pattern: &str, text: &str) -> bool {
+    fn matches(pattern: &[char], text: &[char]) -> bool {
+        match pattern {
+            [] => text.is_empty(),
+            ['%', rest @ ..] => (0..=text.len()).any(|i| matches(rest, &text[i..])),
+            ['_', rest @ ..] => !text.is_empty() && matches(rest, &text[1..]),
+            ['\\', c, rest @ ..] | [c, rest @ ..] => match text {
+                [t, text @ ..] if t.eq_ignore_ascii_case(c) => matches(rest, text),
+                _ => false,
+            },
+        }
+    }
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    matches(&pattern, &text)
+}
+
+/// Evaluates an expression against a row.
+///
+/// # Arguments
+/// * `expr` - The expression to evaluate.
+/// * `row` - The rows of all tables in scope providing column values.
+///
+/// # Returns
+/// The computed value, `None` if a referenced column is absent from the row,
+/// or an `errors::Error::Semantic` for invalid arithmetic or column references.
+fn evaluate(
+    expr: &sql::Expression,
+    row: &[(String, storage::Row)],
+) -> Result<Option<ColumnValue>, errors::Error> {
+    match expr {
+        sql::Expression::Column(column) => Ok(resolve_column(column, row)?.cloned()),
+        sql::Expression::Literal(sql::Literal::Text(s)) => {
+            Ok(Some(ColumnValue::Text(s.clone().into_bytes())))
+        }
+        sql::Expression::Literal(sql::Literal::Number(n)) => {
+            let number = if n.contains('.') {
+                n.parse().map(Number::Real).ok()
+            } else {
+                n.parse().map(Number::Integer).ok()
+            };
+            number
+                .map(|n| Some(n.into_value()))
+                .ok_or_else(|| err!(Syntax, "Invalid number: {}", n))
+        }
+        sql::Expression::Binary { left, right, .. } => {
+            evaluate_binary(expr, evaluate(left, row)?, evaluate(right, row)?)
+        }
+        sql::Expression::Aggregate { .. } => Err(err!(Semantic, "Invalid use of group function")),
+    }
+}
+
+/// Applies the operator of a binary expression to its evaluated operands.
+///
+/// # Arguments
+/// * `expr` - The `Expression::Binary`, used for its operator and in error messages.
+/// * `lhs` - The value of the left operand.
+/// * `rhs` - The value of the right operand.
+///
+/// # Returns
+/// The computed value, `None` if an operand is missing, or an `errors::Error::Semantic`
+/// for invalid arithmetic.
+fn evaluate_binary(
+    expr: &sql::Expression,
+    lhs: Option<ColumnValue>,
+    rhs: Option<ColumnValue>,
+) -> Result<Option<ColumnValue>, errors::Error> {
+    let sql::Expression::Binary {
+        left,
+        operator,
+        right,
+    } = expr
+    else {
+        unreachable!("not a binary expression");
+    };
+    if matches!(operator, sql::Operator::And | sql::Operator::Or) {
+        let (lhs, rhs) = (truth(lhs, left)?, truth(rhs, right)?);
+        let result = match operator {
+            sql::Operator::And => match (lhs, rhs) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            },
+            _ => match (lhs, rhs) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            },
+        };
+        return Ok(result.map(ColumnValue::Boolean));
+    }
+
+    let (Some(lhs), Some(rhs)) = (lhs, rhs) else {
+        return Ok(None);
+    };
+    if operator.is_comparison() {
+        let ordering = compare_values(&lhs, &rhs);
+        let result = match operator {
+            sql::Operator::Equal => ordering == Some(Ordering::Equal),
+            sql::Operator::NotEqual => ordering != Some(Ordering::Equal),
+            sql::Operator::Less => ordering == Some(Ordering::Less),
+            sql::Operator::LessOrEqual => {
+                matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+            }
+            sql::Operator::Greater => ordering == Some(Ordering::Greater),
+            _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        };
+        return Ok(Some(ColumnValue::Boolean(result)));
+    }
+
+    let operand = |value: &ColumnValue, operand: &sql::Expression| {
+        Number::from_value(value).ok_or_else(|| {
+            err!(
+                Semantic,
+                "Arithmetic on non-numeric value '{}' in '{}'",
+                operand,
+                expr
+            )
+        })
+    };
+    let (lhs, rhs) = (operand(&lhs, left)?, operand(&rhs, right)?);
+    apply_operator(*operator, lhs, rhs).map(|n| Some(n.into_value()))
+}
+
+/// Evaluates an expression over a group of rows, computing its aggregates.
+///
+/// Parts outside of aggregates are evaluated against the first row of the group.
+///
+/// # Arguments
+/// * `expr` - The expression to evaluate.
+/// * `group` - The rows the aggregates run over.
+///
+/// # Returns
+/// The computed value, `None` for a missing value, or an `errors::Error::Semantic`.
+fn evaluate_group(
+    expr: &sql::Expression,
+    group: &[ScopedRow],
+) -> Result<Option<ColumnValue>, errors::Error> {
+    match expr {
+        sql::Expression::Aggregate { function, argument } => {
+            aggregate(expr, *function, argument.as_deref(), group)
+        }
+        sql::Expression::Binary { left, right, .. } if expr.has_aggregate() => evaluate_binary(
+            expr,
+            evaluate_group(left, group)?,
+            evaluate_group(right, group)?,
+        ),
+        _ => evaluate(expr, group.first().map_or(&[], |row| row.as_slice())),
+    }
+}
+
+/// Computes an aggregate function over a group of rows.
+///
+/// `COUNT(*)` counts every row; the other forms skip rows where the argument is
+/// missing (`NULL`), and all but `COUNT` yield `NULL` when no value remains.
+fn aggregate(
+    expr: &sql::Expression,
+    function: sql::Aggregate,
+    argument: Option<&sql::Expression>,
+    group: &[ScopedRow],
+) -> Result<Option<ColumnValue>, errors::Error> {
+    let Some(argument) = argument else {
+        return Ok(Some(Number::Integer(group.len() as i128).into_value()));
+    };
+    let mut values = Vec::new();
+    for row in group {
+        if let Some(value) = evaluate(argument, row)? {
+            values.push(value);
+        }
+    }
+
+    match function {
+        sql::Aggregate::Count => Ok(Some(Number::Integer(values.len() as i128).into_value())),
+        sql::Aggregate::Sum | sql::Aggregate::Avg => {
+            let mut total: Option<Number> = None;
+            for value in &values {
+                let number = Number::from_value(value).ok_or_else(|| {
+                    err!(
+                        Semantic,
+                        "Non-numeric value '{}' in '{}'",
+                        value.to_string(),
+                        expr
+                    )
+                })?;
+                total = Some(match total {
+                    Some(total) => apply_operator(sql::Operator::Add, total, number)?,
+                    None => number,
+                });
+            }
+            Ok(total.map(|total| match function {
+                sql::Aggregate::Sum => total.into_value(),
+                _ => Number::Real(total.to_real() / values.len() as f64).into_value(),
+            }))
+        }
+        sql::Aggregate::Min | sql::Aggregate::Max => {
+            let wanted = match function {
+                sql::Aggregate::Min => Ordering::Less,
+                _ => Ordering::Greater,
+            };
+            Ok(values.into_iter().reduce(|best, value| {
+                if compare_values(&value, &best) == Some(wanted) {
+                    value
+                } else {
+                    best
+                }
+            }))
+        }
+    }
+}
+
+/// Interprets a value as a truth value of a condition.
+///
+/// # Returns
+/// `None` for a missing value, or an `errors::Error::Semantic` if the value is not
+/// a boolean or a number.
+fn truth(
+    value: Option<ColumnValue>,
+    expr: &sql::Expression,
+) -> Result<Option<bool>, errors::Error> {
+    match value {
+        None => Ok(None),
+        Some(value) => value.as_f64().map(|v| Some(v != 0.0)).ok_or_else(|| {
+            err!(
+                Semantic,
+                "Non-boolean value '{}' in condition '{}'",
+                value.to_string(),
+                expr
+            )
+        }),
+    }
+}
+
+/// Returns whether a condition holds, treating a missing value as false.
+fn is_true(value: Option<ColumnValue>, expr: &sql::Expression) -> Result<bool, errors::Error> {
+    Ok(truth(value, expr)?.unwrap_or(false))
+}
+
+/// Looks up the value of a column among the rows of all tables in scope.
+///
+/// # Returns
+/// The column value, `None` if it is NULL, or an `errors::Error::Semantic` if no
+/// table has the column, the qualifier is unknown or an unqualified name is ambiguous.
+fn resolve_column<'a>(
+    column: &sql::ColumnRef,
+    row: &'a [(String, storage::Row)],
+) -> Result<Option<&'a ColumnValue>, errors::Error> {
+    match &column.table {
+        Some(table) => {
+            let (_, row) = row
+                .iter()
+                .find(|(name, _)| name == table)
+                .ok_or_else(|| err!(Semantic, "Unknown table '{}' in field list", table))?;
+            let value = row.get(&column.name).ok_or_else(|| {
+                err!(
+                    Semantic,
+                    "Unknown column '{}.{}' in 'field list'",
+                    table,
+                    column.name
+                )
+            })?;
+            Ok(Some(value).filter(|v| !v.is_null()))
+        }
+        None => {
+            let mut values = row.iter().filter_map(|(_, r)| r.get(&column.name));
+            let value = values.next().ok_or_else(|| {
+                err!(Semantic, "Unknown column '{}' in 'field list'", column.name)
+            })?;
+            if values.next().is_some() {
+                return Err(err!(
+                    Semantic,
+                    "Column '{}' in field list is ambiguous",
+                    column.name
+                ));
+            }
+            Ok(Some(value).filter(|v| !v.is_null()))
+        }
+    }
+}
+
+fn apply_operator(
+    operator: sql::Operator,
+    lhs: Number,
+    rhs: Number,
+) -> Result<Number, errors::Error> {
+    if let sql::Operator::Divide = operator {
+        let divisor = rhs.to_real();
+        if divisor == 0.0 {
+            return Err(err!(Semantic, "Division by zero"));
+        }
+        return Ok(Number::Real(lhs.to_real() / divisor));
+    }
+    match (lhs, rhs) {
+        (Number::Integer(a), Number::Integer(b)) => {
+            let result = match operator {
+                sql::Operator::Add => a.checked_add(b),
+                sql::Operator::Subtract => a.checked_sub(b),
+                sql::Operator::Multiply => a.checked_mul(b),
+                _ => unreachable!("not an arithmetic operator"),
+            };
+            result
+                .map(Number::Integer)
+                .ok_or_else(|| err!(Semantic, "Arithmetic overflow"))
+        }
+        (a, b) => {
+            let (a, b) = (a.to_real(), b.to_real());
+            Ok(Number::Real(match operator {
+                sql::Operator::Add => a + b,
+                sql::Operator::Subtract => a - b,
+                sql::Operator::Multiply => a * b,
+                _ => unreachable!("not an arithmetic operator"),
+            }))
+        }
+    }
+}
+
+/// Inserts a row into a table.
+///
+/// # Arguments
+/// * `table` - The table to insert into, wrapped in an `Arc<Mutex<storage::Table>>`.
+/// * `name` - The table name, used in error messages.
+/// * `row` - The row to insert.
+/// * `timeout` - The maximum time to wait for the table lock.
+///
+/// # Returns
+/// A `Result` containing the primary key of the inserted row or an `errors::Error`.
+pub fn execute_insert(
+    table: &Arc<Mutex<storage::Table>>,
+    name: &str,
+    row: storage::Row,
+    timeout: Duration,
+) -> Result<u32, errors::Error> {
+    let mut locked_table = storage::lock_with_timeout(table, name, timeout)?;
+    storage::insert_row(&mut locked_table, &row)
+}
+
+/// Selects rows from a table.
+///
+/// # Arguments
+/// * `table` - The table to select from, wrapped in an `Arc<Mutex<storage::Table>>`.
+/// * `name` - The table name, used in error messages.
+/// * `timeout` - The maximum time to wait for the table lock.
+///
+/// # Returns
+/// A `Result` containing a vector of `schema::Row`s or an `errors::Error`.
+pub fn execute_select(
+    table: &Arc<Mutex<storage::Table>>,
+    name: &str,
+    timeout: Duration,
+    max_rows: Option<usize>,
 ) -> Result<Vec<storage::Row>, errors::Error> {
-    let mut locked_table = table
-        .lock()
-        .map_err(|_| errors::Error::LockTable("Failed to lock table for select".to_string()))?;
-    storage::select_rows(&mut locked_table)
+    let mut locked_table = storage::lock_with_timeout(table, name, timeout)?;
+    storage::select_rows(&mut locked_table, max_rows)
+}
+
+/// Streams the rows of a table to a callback, holding the table lock during the scan.
+///
+/// # Arguments
+/// * `table` - The table to scan.
+/// * `name` - The table name, used in lock errors.
+/// * `timeout` - Maximum time to wait for the table lock.
+/// * `f` - Called with each row in key order; returning `false` stops the scan.
+///
+/// # Returns
+/// A `Result` indicating success or the first `errors::Error` from the scan or callback.
+pub fn for_each_selected_row<F>(
+    table: &Arc<Mutex<storage::Table>>,
+    name: &str,
+    timeout: Duration,
+    f: F,
+) -> Result<(), errors::Error>
+where
+    F: FnMut(storage::Row) -> Result<bool, errors::Error>,
+{
+    let mut locked_table = storage::lock_with_timeout(table, name, timeout)?;
+    storage::for_each_row(&mut locked_table, f)
 }
 
 #[cfg(test)]
@@ -330,6 +1637,7 @@ mod tests {
     use super::*;
     use crate::repl;
     use crate::sql;
+    use std::thread;
 
     // Mock implementations for testing
     fn mock_session() -> session::Session {
@@ -340,6 +1648,7 @@ mod tests {
         sql::SqlCommand {
             statement: stmt,
             sql: String::new(),
+            database: None,
         }
     }
 
@@ -349,7 +1658,7 @@ mod tests {
 
         let create_stmt = sql::CreateTableStatement {
             name: "users".to_string(),
-            columns_schemas: Vec::new(),
+            columns_schemas: storage::table::SCHEMA.columns().to_vec(),
         };
         let command = mock_sql_command(sql::Statement::Create(
             sql::CreateStatement::CreateTableStatement(create_stmt),
@@ -359,18 +1668,18 @@ mod tests {
 
         let insert_stmt = sql::InsertStatement {
             table: "users".to_string(),
-            columns: vec!["id".to_string(), "name".to_string(), "email".to_string()],
+            columns: vec!["id".to_string(), "username".to_string(), "email".to_string()],
             values: vec![
-                "1".to_string(),
-                "John".to_string(),
-                "john@mail.com".to_string(),
+                sql::InsertValue::Literal("1".to_string()),
+                sql::InsertValue::Literal("John".to_string()),
+                sql::InsertValue::Literal("john@mail.com".to_string()),
             ],
         };
         let command = mock_sql_command(sql::Statement::Insert(insert_stmt));
         let result = execute(&mut session, command);
 
         assert!(result.is_ok());
-        if let Ok(SqlResult::Ok { affected_rows }) = result {
+        if let Ok(SqlResult::Ok { affected_rows, .. }) = result {
             assert_eq!(affected_rows, 1);
         }
     }
@@ -381,8 +1690,10 @@ mod tests {
 
         assert!(execute(
             &mut session,
-            sql::parser::parse("create table users (ID INT)".into())
-                .expect("Failed to build SQL to create users table")
+            sql::parser::parse(
+                "create table users (id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255))".into()
+            )
+            .expect("Failed to build SQL to create users table")
         )
         .is_ok());
 
@@ -441,4 +1752,2098 @@ mod tests {
         println!("{}", repl::console::build_table(&colums, &rows));
         println!("Total nodes: {}", total);
     }
+
+    /// Column definitions matching the rows inserted by `insert_users`.
+    const USERS_COLUMNS: &str =
+        "(id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255))";
+
+    fn create_table(session: &mut session::Session, name: &str) {
+        run(
+            session,
+            &format!("create table {} {};", name, USERS_COLUMNS),
+        )
+        .unwrap();
+    }
+
+    fn run(session: &mut session::Session, sql: &str) -> Result<SqlResult, errors::Error> {
+        execute(session, sql::parser::parse(sql.into())?)
+    }
+
+    /// Unwraps the cells of a result set that has no NULLs.
+    fn strings(rows: &[Vec<Option<String>>]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.clone().expect("NULL cell"))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_select_arithmetic() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table users (id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255));",
+        )
+        .unwrap();
+        run(
+            &mut session,
+            "insert into users (id, username, email) values (41, 'user41', 'person41@example.com');",
+        )
+        .unwrap();
+
+        match run(&mut session, "select id + 1, id * 2 from users;").unwrap() {
+            SqlResult::ResultSet { columns, rows } => {
+                assert_eq!(columns, vec!["id + 1", "id * 2"]);
+                assert_eq!(strings(&rows), vec![vec!["42", "82"]]);
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_select_arithmetic_errors() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table users (id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255));",
+        )
+        .unwrap();
+        run(
+            &mut session,
+            "insert into users (id, username, email) values (1, 'user1', 'person1@example.com');",
+        )
+        .unwrap();
+
+        let err = run(&mut session, "select id / 0 from users;").unwrap_err();
+        assert!(matches!(err, errors::Error::Semantic(_)), "{}", err);
+        let err = run(&mut session, "select username + 1 from users;").unwrap_err();
+        assert!(matches!(err, errors::Error::Semantic(_)), "{}", err);
+        for sql in [
+            "select id + 1.2.3 from users;",
+            "select id from users where id > 1..2;",
+        ] {
+            let err = run(&mut session, sql).unwrap_err();
+            assert!(matches!(err, errors::Error::Syntax(_)), "{}: {}", sql, err);
+        }
+    }
+
+    #[test]
+    fn test_select_unknown_column() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        insert_users(&mut session, [1, 2, 3]);
+
+        for (sql, column) in [
+            ("select id, missing from users;", "missing"),
+            ("select id from users where missing = 1;", "missing"),
+            ("select id from users order by missing;", "missing"),
+            ("select count(missing) from users;", "missing"),
+            ("select users.missing from users;", "users.missing"),
+        ] {
+            match run(&mut session, sql) {
+                Err(errors::Error::Semantic(msg)) => assert_eq!(
+                    msg,
+                    format!("Unknown column '{}' in 'field list'", column),
+                    "{}",
+                    sql
+                ),
+                r => panic!("Unexpected result for {}: {:?}", sql, r),
+            }
+        }
+    }
+
+    fn insert_users(session: &mut session::Session, ids: impl IntoIterator<Item = u32>) {
+        for id in ids {
+            run(
+                session,
+                &format!(
+                    "insert into users (id, username, email) values ({}, 'user{}', 'person{}@example.com');",
+                    id, id, id
+                ),
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_btree_outline_after_split() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table users (id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255));",
+        )
+        .unwrap();
+        insert_users(&mut session, 1..=14);
+
+        let outline = session
+            .database
+            .find_table(&"users".into())
+            .unwrap()
+            .try_lock()
+            .unwrap()
+            .build_btree_outline()
+            .unwrap();
+        let internal: Vec<_> = outline
+            .iter()
+            .filter(|l| l.starts_with("Internal"))
+            .collect();
+        let leaves: Vec<_> = outline.iter().filter(|l| l.starts_with("  Leaf")).collect();
+        assert_eq!(internal.len(), 1, "{:?}", outline);
+        assert_eq!(leaves.len(), 2, "{:?}", outline);
+        assert_eq!(outline.len(), 3, "{:?}", outline);
+    }
+
+    #[test]
+    fn test_btree_internal_key_count() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table users (id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255));",
+        )
+        .unwrap();
+        insert_users(&mut session, 1..=30);
+
+        let table = session
+            .database
+            .find_table(&"users".into())
+            .unwrap()
+            .try_lock()
+            .unwrap();
+        let num_keys = {
+            let root = table.pager.get(table.root_page_num).unwrap();
+            assert_eq!(
+                root.get_node_type().unwrap(),
+                storage::btree::NodeType::NodeInternal
+            );
+            root.internal_node_num_keys().unwrap()
+        };
+        let (_, _, rows) = table.build_btree().unwrap();
+
+        let internal: Vec<_> = rows.iter().filter(|r| r[0] == "Internal").collect();
+        assert_eq!(internal.len(), num_keys as usize + 1, "{:?}", internal);
+        let capacity = format!("{}/{}", num_keys, storage::btree::INTERNAL_NODE_MAX_CELLS);
+        for row in &internal {
+            assert_eq!(row[6], capacity);
+        }
+        assert_eq!(
+            internal.iter().filter(|r| r[3] != "-").count(),
+            num_keys as usize
+        );
+
+        let leaf_cells = rows.iter().filter(|r| r[0] == "Leaf").count();
+        assert_eq!(leaf_cells, 30);
+        assert!(rows.iter().filter(|r| r[0] == "Leaf").all(|r| r[7] == "-"));
+
+        let first_leaf = rows.iter().find(|r| r[0] == "Leaf").unwrap();
+        let page = table.pager.get(first_leaf[1].parse().unwrap()).unwrap();
+        let expected = format!(
+            "{}/{}",
+            page.leaf_node_num_cells().unwrap(),
+            page.max_cells()
+        );
+        assert_eq!(first_leaf[6], expected);
+    }
+
+    #[test]
+    fn test_lock_with_timeout() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table users (id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255));",
+        )
+        .unwrap();
+        let table = Arc::clone(session.database.find_table(&"users".into()).unwrap());
+
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let holder = {
+            let table = Arc::clone(&table);
+            thread::spawn(move || {
+                let _guard = table.lock().unwrap();
+                locked_tx.send(()).unwrap();
+                release_rx.recv().unwrap();
+            })
+        };
+        locked_rx.recv().unwrap();
+
+        let err = storage::lock_with_timeout(&table, "users", Duration::from_millis(20))
+            .err()
+            .expect("Lock should time out while held by another thread");
+        assert_eq!(
+            err.to_string(),
+            errors::Error::LockTable("Timed out acquiring lock on 'users'".to_string()).to_string()
+        );
+
+        session.lock_timeout = Duration::from_millis(20);
+        let err = run(&mut session, "select * from users;").unwrap_err();
+        assert!(matches!(err, errors::Error::LockTable(_)), "{}", err);
+
+        release_tx.send(()).unwrap();
+        holder.join().unwrap();
+        assert!(storage::lock_with_timeout(&table, "users", Duration::from_millis(20)).is_ok());
+    }
+
+    #[test]
+    fn test_lock_poisoned_table() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        insert_users(&mut session, 1..=3);
+        let table = Arc::clone(session.database.find_table(&"users".into()).unwrap());
+        let poison = |table: &Arc<Mutex<storage::Table>>| {
+            let table = Arc::clone(table);
+            let result = thread::spawn(move || {
+                let _guard = table.lock().unwrap();
+                panic!("Panic while holding the table lock");
+            })
+            .join();
+            assert!(result.is_err());
+        };
+
+        // Without unsaved changes, the table is recovered
+        session.database.flush(session.lock_timeout).unwrap();
+        poison(&table);
+        assert!(table.is_poisoned());
+        match run(&mut session, "select * from users;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => assert_eq!(rows.len(), 3),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        assert!(!table.is_poisoned());
+
+        // With unsaved changes, the panic may have interrupted one of them
+        table.lock().unwrap().mark_dirty();
+        poison(&table);
+        let err = run(&mut session, "select * from users;").unwrap_err();
+        assert!(matches!(err, errors::Error::LockTable(_)), "{}", err);
+        assert!(err.to_string().contains("poisoned"), "{}", err);
+        assert!(table.is_poisoned());
+    }
+
+    #[test]
+    fn test_select_star_join() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        run(
+            &mut session,
+            "create table orders (id INT PRIMARY KEY, user_id INT, total INT);",
+        )
+        .unwrap();
+        insert_users(&mut session, 1..=2);
+        run(
+            &mut session,
+            "insert into orders (id, user_id, total) values (10, 2, 99);",
+        )
+        .unwrap();
+
+        match run(
+            &mut session,
+            "select * from orders join users on orders.user_id = users.id;",
+        )
+        .unwrap()
+        {
+            SqlResult::ResultSet { columns, rows } => {
+                assert_eq!(
+                    columns,
+                    [
+                        "orders.id",
+                        "orders.user_id",
+                        "orders.total",
+                        "users.id",
+                        "users.username",
+                        "users.email"
+                    ]
+                );
+                assert_eq!(
+                    strings(&rows),
+                    [["10", "2", "99", "2", "user2", "person2@example.com"]]
+                );
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        // A single table keeps unqualified headers
+        match run(&mut session, "select * from orders;").unwrap() {
+            SqlResult::ResultSet { columns, .. } => {
+                assert_eq!(columns, ["id", "user_id", "total"])
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_select_inner_join() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table users (id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255));",
+        )
+        .unwrap();
+        run(
+            &mut session,
+            "create table orders (id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255));",
+        )
+        .unwrap();
+        insert_users(&mut session, 1..=3);
+        for id in [2, 3, 4] {
+            run(
+                &mut session,
+                &format!(
+                    "insert into orders (id, username, email) values ({}, 'user{}', 'order{}@shop.com');",
+                    5 - id, id, id
+                ),
+            )
+            .unwrap();
+        }
+
+        // Probe by primary key.
+        match run(
+            &mut session,
+            "select users.username, orders.email from users join orders on users.id = orders.id;",
+        )
+        .unwrap()
+        {
+            SqlResult::ResultSet { columns, rows } => {
+                assert_eq!(columns, vec!["users.username", "orders.email"]);
+                assert_eq!(
+                    strings(&rows),
+                    vec![
+                        vec!["user1", "order4@shop.com"],
+                        vec!["user2", "order3@shop.com"],
+                        vec!["user3", "order2@shop.com"],
+                    ]
+                );
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        // Scan by value.
+        match run(
+            &mut session,
+            "select users.id, orders.id from users inner join orders on orders.username = users.username;",
+        )
+        .unwrap()
+        {
+            SqlResult::ResultSet { rows, .. } => {
+                assert_eq!(strings(&rows), vec![vec!["2", "3"], vec!["3", "2"]]);
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        let err = run(
+            &mut session,
+            "select id from users join orders on users.id = orders.id;",
+        )
+        .unwrap_err();
+        assert!(matches!(err, errors::Error::Semantic(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_select_qualified_columns() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table users (id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255));",
+        )
+        .unwrap();
+        insert_users(&mut session, 1..=3);
+
+        match run(&mut session, "select users.id from users;").unwrap() {
+            SqlResult::ResultSet { columns, rows } => {
+                assert_eq!(columns, vec!["users.id"]);
+                assert_eq!(strings(&rows), vec![vec!["1"], vec!["2"], vec!["3"]]);
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        match run(
+            &mut session,
+            "select id from users where users.id >= 2 and username != 'user3';",
+        )
+        .unwrap()
+        {
+            SqlResult::ResultSet { rows, .. } => assert_eq!(strings(&rows), vec![vec!["2"]]),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        let err = run(&mut session, "select orders.id from users;").unwrap_err();
+        assert!(matches!(err, errors::Error::Semantic(_)), "{}", err);
+        let err = run(&mut session, "select id from users where orders.id = 1;").unwrap_err();
+        assert!(matches!(err, errors::Error::Semantic(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_truncate_table() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table users (id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255));",
+        )
+        .unwrap();
+        insert_users(&mut session, 1..=20);
+
+        run(&mut session, "truncate table users;").unwrap();
+        assert!(matches!(
+            run(&mut session, "select * from users;").unwrap(),
+            SqlResult::Ok {
+                affected_rows: 0,
+                ..
+            }
+        ));
+        {
+            let table = session
+                .database
+                .find_table(&"users".into())
+                .unwrap()
+                .try_lock()
+                .unwrap();
+            assert_eq!(table.pager.table_n_recs().unwrap(), 0);
+            assert_eq!(table.walk_btree().unwrap(), vec![(0, 0)]);
+        }
+
+        insert_users(&mut session, [7, 3]);
+        match run(&mut session, "select id from users;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => {
+                assert_eq!(strings(&rows), vec![vec!["3"], vec!["7"]])
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        // The file shrinks back to the single root page on flush.
+        session.database.flush(session.lock_timeout).unwrap();
+        let db = database::Database::get_in(&session.data_dir, &session.database.name).unwrap();
+        assert_eq!(
+            db.table_stats(storage::LOCK_TIMEOUT).unwrap(),
+            vec![("users".to_string(), 2, 1)]
+        );
+    }
+
+    #[test]
+    fn test_parse_duplicate_columns() {
+        let err =
+            sql::parser::parse("insert into users (id, id) values (1, 2);".into()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            errors::Error::Syntax("Duplicate column 'id' in INSERT".to_owned()).to_string()
+        );
+
+        let err = sql::parser::parse("create table t (id INT, ID INT);".into()).unwrap_err();
+        assert!(matches!(err, errors::Error::Syntax(_)), "{}", err);
+
+        let err =
+            sql::parser::parse("create table t (id INT PRIMARY KEY, code INT PRIMARY KEY);".into())
+                .unwrap_err();
+        assert!(matches!(err, errors::Error::Syntax(_)), "{}", err);
+
+        assert!(
+            sql::parser::parse("create table t (id INT PRIMARY KEY, code INT);".into()).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_select_without_from() {
+        let mut session = mock_session();
+        match run(&mut session, "select 1, 'a', 2+3;").unwrap() {
+            SqlResult::ResultSet { columns, rows } => {
+                assert_eq!(columns, vec!["1", "'a'", "2 + 3"]);
+                assert_eq!(strings(&rows), vec![vec!["1", "a", "5"]]);
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        let err = run(&mut session, "select id;").unwrap_err();
+        assert!(matches!(err, errors::Error::Semantic(_)), "{}", err);
+        for sql in ["select *;", "select * where id = 1;"] {
+            match sql::parser::parse(sql.into()) {
+                Err(errors::Error::Semantic(msg)) => {
+                    assert_eq!(msg, "SELECT * requires a FROM clause")
+                }
+                r => panic!("Unexpected result for {}: {:?}", sql, r),
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema =
+            |name: &str| (name == "users").then(|| storage::table::SCHEMA.as_ref().clone());
+
+        assert!(sql::validate(
+            "select id, username from users where id > 1;",
+            Some(&schema)
+        )
+        .is_ok());
+        assert!(sql::validate(
+            "insert into users (id, email) values (1, 'a@b.c');",
+            Some(&schema)
+        )
+        .is_ok());
+
+        let err = sql::validate("select nope from users;", Some(&schema)).unwrap_err();
+        assert!(matches!(err, errors::Error::Semantic(_)), "{}", err);
+        let err = sql::validate("delete from users where nope = 1;", Some(&schema)).unwrap_err();
+        assert!(matches!(err, errors::Error::Semantic(_)), "{}", err);
+        let err = sql::validate("describe orders;", Some(&schema)).unwrap_err();
+        assert!(matches!(err, errors::Error::Semantic(_)), "{}", err);
+
+        // Without a schema only the syntax is checked.
+        assert!(sql::validate("select nope from users;", None).is_ok());
+        let err = sql::validate("insert into users (id) values (1, 2);", None);
+        assert!(matches!(err, Err(errors::Error::Syntax(_))), "{:?}", err);
+        let err = sql::validate("select from users;", None).unwrap_err();
+        assert!(matches!(err, errors::Error::Syntax(_)), "{}", err);
+        let err = sql::validate(
+            "create table t (a INT PRIMARY KEY, b INT PRIMARY KEY);",
+            None,
+        );
+        assert!(matches!(err, Err(errors::Error::Syntax(_))), "{:?}", err);
+    }
+
+    #[test]
+    fn test_execute_multiple_statements() {
+        let mut session = mock_session();
+        let buffer =
+            "create table users (id INT PRIMARY KEY, username VARCHAR(32), email VARCHAR(255)); \
+            insert into users (id, username, email) values (1, 'semi;colon', 'a@b.c');";
+        let commands = sql::parser::parse_many(buffer).unwrap();
+        assert_eq!(commands.len(), 2);
+        for cmd in commands {
+            execute(&mut session, cmd).unwrap();
+        }
+
+        match run(&mut session, "select username from users;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => {
+                assert_eq!(strings(&rows), vec![vec!["semi;colon"]])
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(sql::split_statements("select 1; ;\n select 2;").len(), 2);
+    }
+
+    #[test]
+    fn test_rename_table() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table users (id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255));",
+        )
+        .unwrap();
+        run(
+            &mut session,
+            "create table orders (id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255));",
+        )
+        .unwrap();
+        insert_users(&mut session, [1, 2]);
+
+        run(&mut session, "alter table users rename to customers;").unwrap();
+        let err = run(&mut session, "select id from users;").unwrap_err();
+        assert!(matches!(err, errors::Error::Storage(_)), "{}", err);
+        match run(&mut session, "select id from customers;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => {
+                assert_eq!(strings(&rows), vec![vec!["1"], vec!["2"]])
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let err = run(&mut session, "rename table customers to orders;").unwrap_err();
+        assert!(matches!(err, errors::Error::Schema(_)), "{}", err);
+        run(&mut session, "rename table customers to users;").unwrap();
+
+        // The renamed table is found again after reopening the database.
+        session.reopen().unwrap();
+        match run(&mut session, "select id from users;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => assert_eq!(rows.len(), 2),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rename_column() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table users (id INT PRIMARY KEY, username VARCHAR(32) DEFAULT guest, email VARCHAR(255));",
+        )
+        .unwrap();
+        insert_users(&mut session, [1, 2]);
+
+        run(
+            &mut session,
+            "alter table users rename column username to login;",
+        )
+        .unwrap();
+        match run(&mut session, "select * from users;").unwrap() {
+            SqlResult::ResultSet { columns, .. } => assert_eq!(columns, ["id", "login", "email"]),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let err = run(&mut session, "alter table users rename column id to email;").unwrap_err();
+        assert!(matches!(err, errors::Error::Schema(_)), "{}", err);
+
+        // The schema is persisted, the stored rows are read back under the new name.
+        session.reopen().unwrap();
+        match run(&mut session, "select login from users where id = 2;").unwrap() {
+            SqlResult::ResultSet { columns, rows } => {
+                assert_eq!(columns, vec!["login"]);
+                assert_eq!(strings(&rows), vec![vec!["user2"]]);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        run(
+            &mut session,
+            "alter table users change login username VARCHAR(32);",
+        )
+        .unwrap();
+        insert_users(&mut session, [3]);
+        let err = run(&mut session, "alter table users change username name INT;").unwrap_err();
+        assert!(matches!(err, errors::Error::Schema(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_create_table_schema() {
+        let mut session = mock_session();
+        let err = run(&mut session, "create table t (a INT);").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "[5000] Schema Error: Table must have a primary key"
+        );
+
+        run(
+            &mut session,
+            "create table t (a INT PRIMARY KEY, b TINYINT DEFAULT 7);",
+        )
+        .unwrap();
+        run(&mut session, "insert into t (a) values (1);").unwrap();
+        match run(&mut session, "describe t;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => {
+                let fields: Vec<_> = rows
+                    .iter()
+                    .map(|r| (r[0].as_deref().unwrap(), r[1].as_deref().unwrap()))
+                    .collect();
+                assert_eq!(fields, [("a", "INT"), ("b", "TINYINT")]);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match run(&mut session, "select * from t;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => assert_eq!(strings(&rows), vec![vec!["1", "7"]]),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_describe_key_and_extra() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table events (id INT PRIMARY KEY AUTO_INCREMENT, created TIMESTAMP DEFAULT CURRENT_TIMESTAMP, note VARCHAR(8) DEFAULT none);",
+        )
+        .unwrap();
+        match run(&mut session, "describe events;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => {
+                let flags: Vec<_> = rows
+                    .iter()
+                    .map(|r| {
+                        let cell = |i: usize| r[i].as_deref();
+                        (cell(0), cell(3), cell(4), cell(5))
+                    })
+                    .collect();
+                assert_eq!(
+                    flags,
+                    [
+                        (Some("id"), Some("PRI"), None, Some("auto_increment")),
+                        (
+                            Some("created"),
+                            Some("-"),
+                            Some("CURRENT_TIMESTAMP"),
+                            Some("")
+                        ),
+                        (Some("note"), Some("-"), Some("none"), Some("")),
+                    ]
+                );
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_select_column_order() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        run(
+            &mut session,
+            "insert into users (email, id) values ('a@example.com', 1);",
+        )
+        .unwrap();
+        let result = |session: &mut session::Session, sql: &str| match run(session, sql).unwrap() {
+            SqlResult::ResultSet { columns, rows } => (columns, strings(&rows).remove(0)),
+            other => panic!("unexpected result: {:?}", other),
+        };
+
+        // An explicit list controls the order, whatever the schema order is
+        assert_eq!(
+            result(&mut session, "select email, id from users;"),
+            (
+                vec!["email".to_string(), "id".to_string()],
+                vec!["a@example.com".to_string(), "1".to_string()]
+            )
+        );
+        let (columns, row) = result(
+            &mut session,
+            "select username, count(id), email from users group by username, email;",
+        );
+        assert_eq!(columns, ["username", "COUNT(id)", "email"]);
+        assert_eq!(row, ["guest", "1", "a@example.com"]);
+
+        // `*` uses the declaration order, also after renaming a column
+        run(
+            &mut session,
+            "alter table users rename column username to login;",
+        )
+        .unwrap();
+        let (columns, row) = result(&mut session, "select * from users;");
+        assert_eq!(columns, ["id", "login", "email"]);
+        assert_eq!(row, ["1", "guest", "a@example.com"]);
+    }
+
+    #[test]
+    fn test_select_order_by() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        for (id, username) in [(1, "bob"), (2, "alice"), (3, "bob"), (4, "carol")] {
+            run(
+                &mut session,
+                &format!(
+                    "insert into users (id, username, email) values ({}, '{}', 'u{}@example.com');",
+                    id, username, id
+                ),
+            )
+            .unwrap();
+        }
+        let ids = |session: &mut session::Session, sql: &str| match run(session, sql).unwrap() {
+            SqlResult::ResultSet { rows, .. } => {
+                strings(&rows).into_iter().map(|r| r[0].clone()).collect()
+            }
+            other => panic!("unexpected result: {:?}", other),
+        };
+
+        let sorted: Vec<String> = ids(
+            &mut session,
+            "select id from users order by username asc, id desc;",
+        );
+        assert_eq!(sorted, ["2", "3", "1", "4"]);
+        let sorted: Vec<String> = ids(
+            &mut session,
+            "select id from users where id > 1 order by username desc;",
+        );
+        assert_eq!(sorted, ["4", "3", "2"]);
+
+        // Keywords inside text literals don't end the WHERE or HAVING clause
+        for (id, username) in [(5, "order by"), (6, "ORDER"), (7, "order by")] {
+            run(
+                &mut session,
+                &format!(
+                    "insert into users (id, username, email) values ({}, '{}', 'u{}@example.com');",
+                    id, username, id
+                ),
+            )
+            .unwrap();
+        }
+        let sorted: Vec<String> = ids(
+            &mut session,
+            "select id from users where username = 'order by' or username = 'ORDER' order by id desc;",
+        );
+        assert_eq!(sorted, ["7", "6", "5"]);
+        let sorted: Vec<String> = ids(
+            &mut session,
+            "select username from users group by username having username = 'order by' order by username;",
+        );
+        assert_eq!(sorted, ["order by"]);
+
+        let err = run(&mut session, "select id from users order by id sideways;").unwrap_err();
+        assert!(matches!(err, errors::Error::Syntax(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_sort_rows_nulls() {
+        use crate::storage::schema::ColumnSchema;
+
+        let column = |name: &str| ColumnSchema {
+            name: name.into(),
+            type_: storage::column::ColumnType::INT,
+            default: None,
+            is_primary: name == "id",
+            is_nullable: true,
+        };
+        let schema = Arc::new(TableSchema::new(vec![column("id"), column("score")]));
+        let rows = || {
+            [
+                (1, ColumnValue::Int(5)),
+                (2, ColumnValue::Null),
+                (3, ColumnValue::Int(4)),
+            ]
+            .into_iter()
+            .map(|(id, score)| {
+                vec![(
+                    "t".to_string(),
+                    storage::Row::new(schema.clone(), vec![ColumnValue::Int(id), score]),
+                )]
+            })
+            .collect::<Vec<_>>()
+        };
+        let order = |direction: &str| {
+            let sql = format!("select id from t order by score {};", direction);
+            let sql::Statement::Select(stmt) = sql::parser::parse(sql).unwrap().statement else {
+                unreachable!()
+            };
+            sort_rows(rows(), &stmt.order_by, Vec::as_slice)
+                .unwrap()
+                .iter()
+                .map(|row| row[0].1.get_column("id").unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(order("asc"), ["2", "3", "1"]);
+        assert_eq!(order("desc"), ["1", "3", "2"]);
+        assert_eq!(order("asc nulls last"), ["3", "1", "2"]);
+        assert_eq!(order("desc nulls first"), ["2", "1", "3"]);
+    }
+
+    #[test]
+    fn test_order_by_primary_key() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        insert_users(&mut session, [3, 1, 2]);
+        let schema = session
+            .database
+            .find_table(&"users".into())
+            .unwrap()
+            .try_lock()
+            .unwrap()
+            .schema
+            .clone();
+        let order = |sql: &str| {
+            let sql::Statement::Select(stmt) = sql::parser::parse(sql.into()).unwrap().statement
+            else {
+                unreachable!()
+            };
+            primary_key_order(&stmt, "users", &schema)
+        };
+
+        assert_eq!(order("select id from users order by id;"), Some(true));
+        assert_eq!(
+            order("select id from users order by users.id desc;"),
+            Some(false)
+        );
+        assert_eq!(order("select id from users order by username;"), None);
+        assert_eq!(order("select id from users order by id, username;"), None);
+
+        let ids = |session: &mut session::Session, sql: &str| -> Vec<String> {
+            match run(session, sql).unwrap() {
+                SqlResult::ResultSet { rows, .. } => {
+                    strings(&rows).into_iter().map(|r| r[0].clone()).collect()
+                }
+                r => panic!("Unexpected result: {:?}", r),
+            }
+        };
+        assert_eq!(
+            ids(&mut session, "select id from users order by id asc;"),
+            vec!["1", "2", "3"]
+        );
+        assert_eq!(
+            ids(&mut session, "select id from users order by id desc;"),
+            vec!["3", "2", "1"]
+        );
+    }
+
+    #[test]
+    fn test_parse_statements() {
+        let parse = |sql: &str| sql::parser::parse(sql.into()).unwrap().statement;
+        let column = |name: &str| sql::ColumnRef {
+            table: None,
+            name: name.to_string(),
+        };
+
+        assert_eq!(
+            parse("insert into users (id, username) values (1, 'alice');"),
+            sql::Statement::Insert(sql::InsertStatement {
+                table: "users".to_string(),
+                columns: vec!["id".to_string(), "username".to_string()],
+                values: vec![
+                    sql::InsertValue::Literal("1".to_string()),
+                    sql::InsertValue::Literal("alice".to_string()),
+                ],
+            })
+        );
+        assert_eq!(
+            parse("select id from users where id > 1 order by username desc;"),
+            sql::Statement::Select(Box::new(sql::SelectStatement {
+                table: Some("users".to_string()),
+                columns: sql::Columns::List(vec![sql::Expression::Column(column("id"))]),
+                join: None,
+                where_clause: Some(sql::Expression::Binary {
+                    left: Box::new(sql::Expression::Column(column("id"))),
+                    operator: sql::Operator::Greater,
+                    right: Box::new(sql::Expression::Literal(sql::Literal::Number(
+                        "1".to_string()
+                    ))),
+                }),
+                order_by: vec![sql::OrderByKey {
+                    column: column("username"),
+                    ascending: false,
+                    nulls: sql::NullsOrder::Last,
+                }],
+                group_by: Vec::new(),
+                having: None,
+                lock: None,
+            }))
+        );
+        assert_eq!(
+            parse("set output = json;"),
+            sql::Statement::Set(sql::SetStatement {
+                name: "output".to_string(),
+                value: "json".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_multiline() {
+        let parse = |sql: &str| sql::parser::parse(sql.into()).unwrap().statement;
+        assert_eq!(
+            parse("create table users (\n\tid INT PRIMARY KEY,\n\tname VARCHAR(32)\r\n\tDEFAULT guest\n)\n;"),
+            parse("create table users (id INT PRIMARY KEY, name VARCHAR(32) DEFAULT guest);")
+        );
+        assert_eq!(
+            parse("select id\nfrom users\twhere id = 1\n;"),
+            parse("select id from users where id = 1;")
+        );
+    }
+
+    #[test]
+    fn test_parse_terminators() {
+        let parse = |sql: &str| sql::parser::parse(sql.into()).unwrap().statement;
+        let expected = parse("select id from users where id = 1;");
+        assert_eq!(parse("select id from users where id = 1\\g"), expected);
+        assert_eq!(parse("select id from users where id = 1\\G"), expected);
+        assert_eq!(parse("select id from users where id = 1"), expected);
+
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        insert_users(&mut session, [1]);
+        match run(&mut session, "select * from users\\g").unwrap() {
+            SqlResult::ResultSet { rows, .. } => assert_eq!(rows.len(), 1),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_set_statement() {
+        let mut session = mock_session();
+        assert_eq!(session.settings.output, session::OutputFormat::Table);
+
+        run(&mut session, "set output = json;").unwrap();
+        run(&mut session, "SET timing_precision=4;").unwrap();
+        assert_eq!(session.settings.output, session::OutputFormat::Json);
+        assert_eq!(session.settings.timing_precision, 4);
+
+        match run(&mut session, "show variables;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => {
+                let rows = strings(&rows);
+                assert!(rows.contains(&vec!["output".to_string(), "json".to_string()]));
+                assert!(rows.contains(&vec!["timing_precision".to_string(), "4".to_string()]));
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        run(&mut session, "set max_input_len = 4096;").unwrap();
+        assert_eq!(session.settings.max_input_len, 4096);
+
+        let err = run(&mut session, "set output = xml;").unwrap_err();
+        assert!(matches!(err, errors::Error::Command(_)), "{}", err);
+        let err = run(&mut session, "set max_input_len = -1;").unwrap_err();
+        assert!(matches!(err, errors::Error::Command(_)), "{}", err);
+        let err = run(&mut session, "set missing = 1;").unwrap_err();
+        assert!(matches!(err, errors::Error::Command(_)), "{}", err);
+        assert!(run(&mut session, "set output;").is_err());
+    }
+
+    #[test]
+    fn test_char_and_decimal_columns() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table prices (id INT PRIMARY KEY, code CHAR(4), amount DECIMAL(10,2));",
+        )
+        .unwrap();
+        run(
+            &mut session,
+            "insert into prices (id, code, amount) values (1, 'ab', '12.345');",
+        )
+        .unwrap();
+        run(
+            &mut session,
+            "insert into prices (id, code, amount) values (2, 'wxyz', '-0.5');",
+        )
+        .unwrap();
+        assert!(run(
+            &mut session,
+            "insert into prices (id, code, amount) values (3, 'a', 'x');"
+        )
+        .is_err());
+
+        match run(&mut session, "select code, amount from prices;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => assert_eq!(
+                strings(&rows),
+                vec![
+                    vec!["ab  ".to_string(), "12.35".to_string()],
+                    vec!["wxyz".to_string(), "-0.50".to_string()],
+                ]
+            ),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        match run(&mut session, "select id from prices where amount > 1;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => {
+                assert_eq!(strings(&rows), vec![vec!["1".to_string()]])
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_insert_native_row() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        {
+            let table = session.database.find_table(&"users".into()).unwrap();
+            let mut table = table.try_lock().unwrap();
+            let row = storage::Row::builder(&table.schema)
+                .insert("id", 7)
+                .insert("email", "bob@example.com")
+                .build()
+                .unwrap();
+            storage::insert_row(&mut table, &row).unwrap();
+
+            let err = storage::Row::builder(&table.schema)
+                .insert("id", "seven")
+                .insert("email", "x")
+                .build()
+                .unwrap_err();
+            assert!(matches!(err, errors::Error::Schema(_)));
+            let err = storage::Row::builder(&table.schema)
+                .insert("id", 8)
+                .insert("nope", true)
+                .build()
+                .unwrap_err();
+            assert!(matches!(err, errors::Error::Schema(_)));
+        }
+
+        match run(&mut session, "select * from users;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => {
+                assert_eq!(strings(&rows), vec![vec!["7", "guest", "bob@example.com"]])
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_show_columns_and_index() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+
+        let describe = run(&mut session, "describe users;").unwrap();
+        let show_columns = run(&mut session, "show columns from users;").unwrap();
+        assert_eq!(format!("{:?}", show_columns), format!("{:?}", describe));
+
+        match run(&mut session, "show index from users;").unwrap() {
+            SqlResult::ResultSet { columns, rows } => {
+                assert_eq!(columns[2], "Key_name");
+                assert_eq!(
+                    strings(&rows),
+                    vec![vec!["users", "0", "PRIMARY", "1", "id"]]
+                );
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        assert!(run(&mut session, "show index from missing;").is_err());
+        assert!(run(&mut session, "show columns users;").is_err());
+    }
+
+    #[test]
+    fn test_show_table_status() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        create_table(&mut session, "orders");
+        for i in 1..=30 {
+            run(
+                &mut session,
+                &format!(
+                    "insert into users (id, username, email) values ({}, 'user{}', 'person{}@example.com');",
+                    i, i, i
+                ),
+            )
+            .unwrap();
+        }
+        let page_count = {
+            let table = session.database.find_table(&"users".to_string()).unwrap();
+            let table = table.lock().unwrap();
+            assert_eq!(table.row_count().unwrap(), 30);
+            table.page_count()
+        };
+        assert!(page_count > 1);
+
+        match run(&mut session, "show table status;").unwrap() {
+            SqlResult::ResultSet { columns, mut rows } => {
+                assert_eq!(columns, vec!["Name", "Rows", "Data_length", "Row_format"]);
+                rows.sort();
+                assert_eq!(
+                    strings(&rows),
+                    vec![
+                        vec![
+                            "orders",
+                            "0",
+                            &storage::table::PAGE_SIZE.to_string(),
+                            "Fixed"
+                        ],
+                        vec![
+                            "users",
+                            "30",
+                            &(page_count * storage::table::PAGE_SIZE).to_string(),
+                            "Fixed"
+                        ],
+                    ]
+                );
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        match run(&mut session, "show table status like 'ord%';").unwrap() {
+            SqlResult::ResultSet { rows, .. } => assert_eq!(strings(&rows)[0][0], "orders"),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        assert!(run(&mut session, "show table users;").is_err());
+    }
+
+    #[test]
+    fn test_no_database_selected() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        std::fs::remove_dir_all(session.database.path()).unwrap();
+
+        let err = run(&mut session, "select * from users;").unwrap_err();
+        assert!(matches!(err, errors::Error::Command(_)), "{}", err);
+        assert!(err.to_string().contains("No database selected"), "{}", err);
+        assert!(run(&mut session, "insert into users (id) values (1);").is_err());
+        assert!(run(&mut session, "select 1;").is_ok());
+        assert!(run(&mut session, "show databases;").is_ok());
+    }
+
+    #[test]
+    fn test_qualified_table_names() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        run(&mut session, "create database analytics;").unwrap();
+        run(
+            &mut session,
+            "create table analytics.events (id INT PRIMARY KEY, name VARCHAR(32));",
+        )
+        .unwrap();
+        run(
+            &mut session,
+            "insert into analytics.events (id, name) values (1, 'login');",
+        )
+        .unwrap();
+
+        let command =
+            sql::parser::parse("select events.name from analytics.events;".into()).unwrap();
+        assert_eq!(command.database.as_deref(), Some("analytics"));
+        match execute(&mut session, command).unwrap() {
+            SqlResult::ResultSet { columns, rows } => {
+                assert_eq!(columns, ["events.name"]);
+                assert_eq!(strings(&rows), [["login"]]);
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        // The current database is unchanged and doesn't have the table
+        assert!(session.database.find_table(&"users".to_string()).is_ok());
+        assert!(run(&mut session, "select * from events;").is_err());
+        let current = format!("select * from {}.users;", session.database.name);
+        assert!(matches!(
+            run(&mut session, &current).unwrap(),
+            SqlResult::Ok { .. }
+        ));
+
+        let err = run(&mut session, "select * from missing.events;").unwrap_err();
+        assert!(matches!(err, errors::Error::Storage(_)), "{}", err);
+        let err = run(
+            &mut session,
+            "select * from analytics.events join users on events.id = users.id;",
+        )
+        .unwrap_err();
+        assert!(matches!(err, errors::Error::Syntax(_)), "{}", err);
+        assert!(run(&mut session, "select * from analytics.;").is_err());
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table users (id INT PRIMARY KEY, name VARCHAR(4), code CHAR(2));",
+        )
+        .unwrap();
+        let names = |session: &mut session::Session| match run(session, "select name from users;")
+            .unwrap()
+        {
+            SqlResult::ResultSet { rows, .. } => strings(&rows)
+                .into_iter()
+                .map(|r| r[0].clone())
+                .collect::<Vec<_>>(),
+            r => panic!("Unexpected result: {:?}", r),
+        };
+
+        let err = run(
+            &mut session,
+            "insert into users (id, name, code) values (1, 'alice', 'ab');",
+        )
+        .unwrap_err();
+        assert!(matches!(err, errors::Error::Schema(_)), "{}", err);
+        assert_eq!(
+            err.to_string(),
+            errors::Error::Schema("Data too long for column 'name'".into()).to_string()
+        );
+        assert!(run(
+            &mut session,
+            "insert into users (id, name, code) values (1, 'bob', 'abc');"
+        )
+        .is_err());
+        run(
+            &mut session,
+            "insert into users (id, name, code) values (1, 'bob', 'ab');",
+        )
+        .unwrap();
+        assert!(session.warnings.is_empty());
+
+        run(&mut session, "set strict_mode = off;").unwrap();
+        run(
+            &mut session,
+            "insert into users (id, name, code) values (2, 'alice', 'ab');",
+        )
+        .unwrap();
+        assert_eq!(session.warnings, ["Data truncated for column 'name'"]);
+        assert_eq!(names(&mut session), ["bob", "alic"]);
+    }
+
+    #[test]
+    fn test_insert_now() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table events (id INT PRIMARY KEY, created TIMESTAMP, note VARCHAR(32));",
+        )
+        .unwrap();
+        let before = storage::current_datetime().unwrap();
+        run(
+            &mut session,
+            "insert into events (id, created, note) values (1, now(), CURRENT_TIMESTAMP);",
+        )
+        .unwrap();
+        let after = storage::current_datetime().unwrap();
+
+        match run(&mut session, "select created, note from events;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => {
+                // The `YYYY-MM-DD HH:MM:SS` form sorts chronologically
+                let rows = strings(&rows);
+                let created = &rows[0][0];
+                assert!(
+                    before <= *created && *created <= after,
+                    "{} not in {}..={}",
+                    created,
+                    before,
+                    after
+                );
+                assert_eq!(rows[0][1], *created);
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        // Quoted, it is just text that isn't a valid timestamp
+        assert!(run(
+            &mut session,
+            "insert into events (id, created) values (2, 'now()');"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_insert_reports_key() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        match run(
+            &mut session,
+            "insert into users (id, email) values (5, 'a@b.c');",
+        )
+        .unwrap()
+        {
+            SqlResult::Ok {
+                affected_rows,
+                last_insert_id,
+            } => {
+                assert_eq!(affected_rows, 1);
+                assert_eq!(last_insert_id, Some(5));
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        assert_eq!(session.last_insert_id, Some(5));
+
+        assert!(run(
+            &mut session,
+            "insert into users (id, email) values (5, 'a@b.c');"
+        )
+        .is_err());
+        assert_eq!(session.last_insert_id, Some(5));
+        match run(
+            &mut session,
+            "insert into users (id, email, nope) values (6, 'a@b.c', 'x');",
+        ) {
+            Err(errors::Error::Semantic(msg)) => {
+                assert_eq!(msg, "Unknown column 'nope' in 'field list'")
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        // A negative key must not wrap around to a large unsigned one
+        assert!(run(
+            &mut session,
+            "insert into users (id, email) values (-1, 'a@b.c');"
+        )
+        .is_err());
+        assert_eq!(session.last_insert_id, Some(5));
+        match run(&mut session, "delete from users;").unwrap() {
+            SqlResult::Ok { last_insert_id, .. } => assert_eq!(last_insert_id, None),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_insert_auto_increment() {
+        let mut session = mock_session();
+        run(
+            &mut session,
+            "create table events (id INT PRIMARY KEY AUTO_INCREMENT, note VARCHAR(8));",
+        )
+        .unwrap();
+        let insert = |session: &mut session::Session, sql: &str| match run(session, sql).unwrap() {
+            SqlResult::Ok { last_insert_id, .. } => last_insert_id,
+            r => panic!("Unexpected result: {:?}", r),
+        };
+        assert_eq!(
+            insert(&mut session, "insert into events (note) values ('a');"),
+            Some(1)
+        );
+        assert_eq!(
+            insert(&mut session, "insert into events (note) values ('b');"),
+            Some(2)
+        );
+        // An explicit key is kept, and the next assigned one follows the largest key
+        assert_eq!(
+            insert(
+                &mut session,
+                "insert into events (id, note) values (10, 'c');"
+            ),
+            Some(10)
+        );
+        assert_eq!(
+            insert(&mut session, "insert into events (note) values ('d');"),
+            Some(11)
+        );
+        assert_eq!(session.last_insert_id, Some(11));
+
+        let table = session.database.find_table(&"events".into()).unwrap();
+        let schema = table.lock().unwrap().schema.clone();
+        assert!(schema
+            .to_create_sql("events")
+            .starts_with("CREATE TABLE events (id INT PRIMARY KEY AUTO_INCREMENT,"));
+
+        let err =
+            sql::parser::parse("create table t (id INT PRIMARY KEY, n INT AUTO_INCREMENT);".into())
+                .unwrap_err();
+        assert!(matches!(err, errors::Error::Syntax(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_insert_column_count_mismatch() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        for (sql, expected) in [
+            (
+                "insert into users (id, username, email) values (1, 'alice');",
+                "(id, username, email)",
+            ),
+            (
+                "insert into users (id) values (1, 'alice', 'a@b.c');",
+                "(id)",
+            ),
+        ] {
+            match run(&mut session, sql) {
+                Err(errors::Error::Syntax(msg)) => assert_eq!(
+                    msg,
+                    format!(
+                        "Column count doesn't match value count: table 'users' expects {}",
+                        expected
+                    )
+                ),
+                r => panic!("Unexpected result for {}: {:?}", sql, r),
+            }
+        }
+        let table = session.database.find_table(&"users".into()).unwrap();
+        assert_eq!(table.try_lock().unwrap().pager.table_n_recs().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_escaped_quotes() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        session
+            .execute_script(
+                "insert into users (id, username, email) values (1, 'O''Brien', 'ob@example.com');
+                 insert into users (id, username, email) values (2, 'it''s; ok', '''');"
+                    .as_bytes(),
+            )
+            .unwrap();
+
+        match run(
+            &mut session,
+            "select id, username from users where username = 'O''Brien';",
+        )
+        .unwrap()
+        {
+            SqlResult::ResultSet { rows, .. } => {
+                assert_eq!(strings(&rows), vec![vec!["1", "O'Brien"]])
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        match run(
+            &mut session,
+            "select username, email from users where id = 2;",
+        )
+        .unwrap()
+        {
+            SqlResult::ResultSet { rows, .. } => {
+                assert_eq!(strings(&rows), vec![vec!["it's; ok", "'"]])
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_concurrent_inserts() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        let table = session
+            .database
+            .find_table(&"users".into())
+            .unwrap()
+            .clone();
+        let schema = table.try_lock().unwrap().schema.clone();
+
+        let handles: Vec<_> = [1..=15, 16..=30]
+            .into_iter()
+            .map(|ids| {
+                let (table, schema) = (table.clone(), schema.clone());
+                thread::spawn(move || {
+                    for id in ids.rev() {
+                        let row = storage::Row::builder(&schema)
+                            .insert("id", id)
+                            .insert("email", format!("user{}@example.com", id))
+                            .build()?;
+                        execute_insert(&table, "users", row, storage::LOCK_TIMEOUT)?;
+                    }
+                    Ok::<_, errors::Error>(())
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap().unwrap();
+        }
+        assert_eq!(table.try_lock().unwrap().pager.table_n_recs().unwrap(), 30);
+    }
+
+    #[test]
+    fn test_delete_all_rows() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        insert_users(&mut session, 1..=30);
+        {
+            let table = session.database.find_table(&"users".into()).unwrap();
+            assert!(table.try_lock().unwrap().pager.len() > 1);
+        }
+
+        match run(&mut session, "delete from users;").unwrap() {
+            SqlResult::Ok { affected_rows, .. } => assert_eq!(affected_rows, 30),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        let table = session.database.find_table(&"users".into()).unwrap();
+        let table = table.try_lock().unwrap();
+        assert_eq!(table.pager.len(), 1);
+        assert_eq!(table.pager.table_n_recs().unwrap(), 0);
+        let root = table.pager.get(table.root_page_num).unwrap();
+        assert_eq!(
+            root.get_node_type().unwrap(),
+            storage::btree::NodeType::NodeLeaf
+        );
+    }
+
+    #[test]
+    fn test_delete_by_primary_key() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        insert_users(&mut session, 1..=30);
+        {
+            let table = session.database.find_table(&"users".into()).unwrap();
+            assert!(table.try_lock().unwrap().pager.len() > 1);
+        }
+        let mut expected: Vec<u32> = (1..=30).collect();
+        let delete =
+            |session: &mut session::Session, sql: &str, affected: u64| match run(session, sql)
+                .unwrap()
+            {
+                SqlResult::Ok { affected_rows, .. } => {
+                    assert_eq!(affected_rows, affected, "{}", sql)
+                }
+                r => panic!("Unexpected result: {:?}", r),
+            };
+
+        delete(&mut session, "delete from users where id=5;", 1);
+        delete(&mut session, "delete from users where 14 = users.id;", 1);
+        delete(&mut session, "delete from users where id = 5;", 0);
+        delete(
+            &mut session,
+            "delete from users where username = 'user7';",
+            1,
+        );
+        delete(&mut session, "delete from users where id > 25;", 5);
+        expected.retain(|&id| ![5, 7, 14].contains(&id) && id <= 25);
+
+        match run(&mut session, "select id from users;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => {
+                let ids: Vec<u32> = strings(&rows)
+                    .iter()
+                    .map(|r| r[0].parse().unwrap())
+                    .collect();
+                assert_eq!(ids, expected);
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_select_row_cap() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        insert_users(&mut session, 1..=20);
+
+        run(&mut session, "set max_rows = 5;").unwrap();
+        match run(&mut session, "select id from users;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => assert_eq!(rows.len(), 5),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        assert_eq!(session.warnings.len(), 1);
+
+        // The cap keeps the first rows of the sorted, joined and filtered result
+        let ids = |session: &mut session::Session, sql: &str| match run(session, sql).unwrap() {
+            SqlResult::ResultSet { rows, .. } => strings(&rows)
+                .into_iter()
+                .map(|r| r[0].clone())
+                .collect::<Vec<_>>(),
+            r => panic!("Unexpected result: {:?}", r),
+        };
+        run(&mut session, "set max_rows = 3;").unwrap();
+        assert_eq!(
+            ids(&mut session, "select id from users order by id desc;"),
+            ["20", "19", "18"]
+        );
+        assert_eq!(
+            ids(&mut session, "select id from users order by email desc;"),
+            ["9", "8", "7"]
+        );
+        create_table(&mut session, "orders");
+        for id in [15, 17, 19, 21] {
+            run(
+                &mut session,
+                &format!(
+                    "insert into orders (id, email) values ({}, 'o@shop.com');",
+                    id
+                ),
+            )
+            .unwrap();
+        }
+        run(&mut session, "set max_rows = 2;").unwrap();
+        assert_eq!(
+            ids(
+                &mut session,
+                "select users.id from users join orders on users.id = orders.id;"
+            ),
+            ["15", "17"]
+        );
+        assert_eq!(session.warnings.len(), 1);
+
+        run(&mut session, "set max_rows = 0;").unwrap();
+        assert!(session.warnings.is_empty());
+        match run(&mut session, "select id from users;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => assert_eq!(rows.len(), 20),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        assert!(session.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_for_each_selected_row() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        insert_users(&mut session, [5, 3, 9, 1, 7]);
+
+        let table = session.database.find_table(&"users".into()).unwrap();
+        let selected: Vec<String> = execute_select(table, "users", storage::LOCK_TIMEOUT, None)
+            .unwrap()
+            .iter()
+            .map(|row| row.get_column("id").unwrap())
+            .collect();
+
+        let mut streamed = Vec::new();
+        for_each_selected_row(table, "users", storage::LOCK_TIMEOUT, |row| {
+            streamed.push(row.get_column("id").unwrap());
+            Ok(true)
+        })
+        .unwrap();
+        assert_eq!(streamed, selected);
+        assert_eq!(streamed, vec!["1", "3", "5", "7", "9"]);
+
+        let mut first = Vec::new();
+        for_each_selected_row(table, "users", storage::LOCK_TIMEOUT, |row| {
+            first.push(row.get_column("id").unwrap());
+            Ok(first.len() < 2)
+        })
+        .unwrap();
+        assert_eq!(first, vec!["1", "3"]);
+    }
+
+    #[test]
+    fn test_optimize_table() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        // Ascending inserts leave every split leaf half full.
+        insert_users(&mut session, 1..=30);
+        let all_rows =
+            |session: &mut session::Session| match run(session, "select * from users;").unwrap() {
+                SqlResult::ResultSet { rows, .. } => rows,
+                r => panic!("Unexpected result: {:?}", r),
+            };
+        let before = all_rows(&mut session);
+
+        let SqlResult::ResultSet { rows, .. } = run(&mut session, "optimize table users;").unwrap()
+        else {
+            panic!("Expected a result set");
+        };
+        let (pages_before, pages_after): (usize, usize) = (
+            strings(&rows)[0][1].parse().unwrap(),
+            strings(&rows)[0][2].parse().unwrap(),
+        );
+        assert!(
+            pages_after < pages_before,
+            "{} -> {}",
+            pages_before,
+            pages_after
+        );
+        {
+            let table = session.database.find_table(&"users".into()).unwrap();
+            let table = table.try_lock().unwrap();
+            assert_eq!(table.pager.len(), pages_after);
+            assert!(!table.path.with_extension("tbd.tmp").exists());
+        }
+        assert_eq!(all_rows(&mut session), before);
+
+        // The rebuilt tree keeps accepting inserts and lookups.
+        insert_users(&mut session, [31]);
+        assert_eq!(all_rows(&mut session).len(), 31);
+        assert!(run(&mut session, "vacuum users;").is_ok());
+        assert!(run(&mut session, "optimize users;").is_err());
+    }
+
+    #[test]
+    fn test_show_tables_like() {
+        let mut session = mock_session();
+        for name in ["users", "user_roles", "userXroles", "orders"] {
+            create_table(&mut session, name);
+        }
+        let tables = |session: &mut session::Session, sql: &str| -> Vec<String> {
+            match run(session, sql).unwrap() {
+                SqlResult::ResultSet { rows, .. } => {
+                    let mut names: Vec<String> =
+                        strings(&rows).into_iter().map(|r| r[0].clone()).collect();
+                    names.sort();
+                    names
+                }
+                r => panic!("Unexpected result: {:?}", r),
+            }
+        };
+
+        assert_eq!(tables(&mut session, "show tables;").len(), 4);
+        assert_eq!(
+            tables(&mut session, "show tables like 'u%';"),
+            vec!["userXroles", "user_roles", "users"]
+        );
+        assert_eq!(
+            tables(&mut session, r"show tables like 'USER\_%';"),
+            vec!["user_roles"]
+        );
+        assert_eq!(
+            tables(&mut session, "show tables like 'order_';"),
+            vec!["orders"]
+        );
+        assert!(tables(&mut session, "show tables like 'x%';").is_empty());
+        assert!(run(&mut session, "show tables like users;").is_err());
+
+        let databases = tables(&mut session, r"show databases like 'test\_%';");
+        assert!(databases.contains(&session.database.name));
+    }
+
+    #[test]
+    fn test_select_duplicate_columns() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        insert_users(&mut session, [1, 2]);
+
+        match run(&mut session, "select id, id, users.id, id from users;").unwrap() {
+            SqlResult::ResultSet { columns, rows } => {
+                assert_eq!(columns, vec!["id", "id", "users.id", "id"]);
+                assert_eq!(strings(&rows), vec![vec!["1"; 4], vec!["2"; 4]]);
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_select_for_update() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        insert_users(&mut session, [1, 2, 3]);
+
+        let lock = |sql: &str| match sql::parser::parse(sql.into()).unwrap().statement {
+            sql::Statement::Select(stmt) => stmt.lock,
+            s => panic!("Unexpected statement: {:?}", s),
+        };
+        assert_eq!(lock("select * from users;"), None);
+        assert_eq!(
+            lock("select * from users for update;"),
+            Some(sql::LockMode::Exclusive)
+        );
+        assert_eq!(
+            lock("select id from users where id > 1 order by id desc lock in share mode;"),
+            Some(sql::LockMode::Shared)
+        );
+
+        let rows = |session: &mut session::Session, sql: &str| match run(session, sql).unwrap() {
+            SqlResult::ResultSet { rows, .. } => strings(&rows),
+            r => panic!("Unexpected result: {:?}", r),
+        };
+        assert_eq!(
+            rows(&mut session, "select * from users for update;"),
+            rows(&mut session, "select * from users;")
+        );
+        assert_eq!(
+            rows(
+                &mut session,
+                "select id from users where id > 1 order by id desc for update;"
+            ),
+            vec![vec!["3"], vec!["2"]]
+        );
+    }
+
+    #[test]
+    fn test_dash_is_not_null() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        run(
+            &mut session,
+            "insert into users (id, username, email) values (1, '-', '-');",
+        )
+        .unwrap();
+        match run(
+            &mut session,
+            "select sum(id), max(email) from users where id > 1;",
+        )
+        .unwrap()
+        {
+            SqlResult::ResultSet { rows, .. } => assert_eq!(rows, vec![vec![None, None]]),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        match run(&mut session, "select username, email from users;").unwrap() {
+            SqlResult::ResultSet { rows, .. } => assert_eq!(strings(&rows), [["-", "-"]]),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_select_aggregates() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        let single_row = |session: &mut session::Session, sql: &str| match run(session, sql) {
+            Ok(SqlResult::ResultSet { columns, mut rows }) => {
+                assert_eq!(rows.len(), 1, "{:?}", rows);
+                (columns, rows.remove(0))
+            }
+            r => panic!("Unexpected result for {}: {:?}", sql, r),
+        };
+
+        let (columns, row) = single_row(&mut session, "select count(*), sum(id) from users;");
+        assert_eq!(columns, vec!["COUNT(*)", "SUM(id)"]);
+        assert_eq!(row, vec![Some("0".to_string()), None]);
+
+        insert_users(&mut session, [3, 1, 4, 2]);
+        let (_, row) = single_row(
+            &mut session,
+            "select count(*), count(email), sum(id), avg(id), min(id), max(username) from users;",
+        );
+        assert_eq!(
+            strings(&[row])[0],
+            vec!["4", "4", "10", "2.5", "1", "user4"]
+        );
+        let (_, row) = single_row(
+            &mut session,
+            "select count(*) * 2 + 1, max(id) - min(id) from users where id > 1;",
+        );
+        assert_eq!(strings(&[row])[0], vec!["7", "2"]);
+
+        session.settings.set("max_rows", "2").unwrap();
+        let (_, row) = single_row(&mut session, "select count(*) from users;");
+        assert_eq!(strings(&[row])[0], vec!["4"]);
+
+        for sql in [
+            "select id, count(*) from users;",
+            "select id from users where count(*) > 1;",
+            "select count(max(id)) from users;",
+            "select sum(username) from users;",
+        ] {
+            let err = run(&mut session, sql).unwrap_err();
+            assert!(
+                matches!(err, errors::Error::Semantic(_)),
+                "{}: {}",
+                sql,
+                err
+            );
+        }
+        assert!(run(&mut session, "select total(id) from users;").is_err());
+    }
+
+    #[test]
+    fn test_count_column() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        insert_users(&mut session, [1, 2, 3]);
+        // `email` is nullable without a default, so omitting it stores NULL too
+        for sql in [
+            "insert into users (id) values (4);",
+            "insert into users (id, email) values (5, NULL);",
+        ] {
+            run(&mut session, sql).unwrap();
+        }
+        let row = |session: &mut session::Session, sql: &str| match run(session, sql).unwrap() {
+            SqlResult::ResultSet { rows, .. } => rows.into_iter().next().unwrap(),
+            r => panic!("Unexpected result: {:?}", r),
+        };
+        let cell = |s: &str| Some(s.to_string());
+        assert_eq!(
+            row(
+                &mut session,
+                "select count(*), count(email), count(id + 1) from users;"
+            ),
+            [cell("5"), cell("3"), cell("5")]
+        );
+        assert_eq!(
+            row(
+                &mut session,
+                "select count(*), count(email), min(email) from users where id > 3;"
+            ),
+            [cell("2"), cell("0"), None]
+        );
+        assert_eq!(
+            row(&mut session, "select id, email from users where id = 5;"),
+            [cell("5"), None]
+        );
+
+        match run(
+            &mut session,
+            "insert into users (id, email) values (NULL, 'a@b.c');",
+        ) {
+            Err(errors::Error::Schema(msg)) => assert_eq!(msg, "Column 'id' cannot be null"),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_select_group_by() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        for (id, username) in [
+            (1, "bob"),
+            (2, "alice"),
+            (3, "bob"),
+            (4, "carol"),
+            (5, "bob"),
+        ] {
+            run(
+                &mut session,
+                &format!(
+                    "insert into users (id, username, email) values ({}, '{}', '{}{}@example.com');",
+                    id, username, username, id
+                ),
+            )
+            .unwrap();
+        }
+        let rows = |session: &mut session::Session, sql: &str| match run(session, sql).unwrap() {
+            SqlResult::ResultSet { columns, rows } => (columns, strings(&rows)),
+            r => panic!("Unexpected result: {:?}", r),
+        };
+
+        let (columns, groups) = rows(
+            &mut session,
+            "select username, count(*) from users group by username;",
+        );
+        assert_eq!(columns, vec!["username", "COUNT(*)"]);
+        // Groups come out in the order their first row was scanned.
+        assert_eq!(
+            groups,
+            vec![vec!["bob", "3"], vec!["alice", "1"], vec!["carol", "1"]]
+        );
+
+        let (_, groups) = rows(
+            &mut session,
+            "select username, min(id), max(id), count(*) from users where id > 1 group by username order by username;",
+        );
+        assert_eq!(
+            groups,
+            vec![
+                vec!["alice", "2", "2", "1"],
+                vec!["bob", "3", "5", "2"],
+                vec!["carol", "4", "4", "1"],
+            ]
+        );
+
+        let (_, groups) = rows(
+            &mut session,
+            "select users.username, email, count(*) from users group by username, email order by email desc;",
+        );
+        assert_eq!(groups.len(), 5);
+        assert_eq!(groups[0], vec!["carol", "carol4@example.com", "1"]);
+
+        // The sort and the row cap apply to the groups, not to the scanned rows
+        let (_, groups) = rows(
+            &mut session,
+            "select username, count(*) from users group by username order by username desc;",
+        );
+        assert_eq!(
+            groups,
+            vec![vec!["carol", "1"], vec!["bob", "3"], vec!["alice", "1"]]
+        );
+        session.settings.set("max_rows", "2").unwrap();
+        let (_, groups) = rows(
+            &mut session,
+            "select username, count(*) from users group by username;",
+        );
+        assert_eq!(groups, vec![vec!["bob", "3"], vec!["alice", "1"]]);
+        assert_eq!(session.warnings.len(), 1);
+        session.settings.set("max_rows", "0").unwrap();
+        assert!(matches!(
+            run(
+                &mut session,
+                "select username, count(*) from users where id > 9 group by username;"
+            ),
+            Ok(SqlResult::Ok {
+                affected_rows: 0,
+                ..
+            })
+        ));
+
+        for sql in [
+            "select id, count(*) from users group by username;",
+            "select * from users group by username;",
+            "select username from users group by missing;",
+            "select username, count(*) from users group by username order by id;",
+        ] {
+            let err = run(&mut session, sql).unwrap_err();
+            assert!(
+                matches!(err, errors::Error::Semantic(_)),
+                "{}: {}",
+                sql,
+                err
+            );
+        }
+        assert!(matches!(
+            run(&mut session, "select username from users group username;"),
+            Err(errors::Error::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn test_select_having() {
+        let mut session = mock_session();
+        create_table(&mut session, "users");
+        for (id, username) in [
+            (1, "bob"),
+            (2, "alice"),
+            (3, "bob"),
+            (4, "carol"),
+            (5, "alice"),
+        ] {
+            run(
+                &mut session,
+                &format!(
+                    "insert into users (id, username, email) values ({}, '{}', '{}{}@example.com');",
+                    id, username, username, id
+                ),
+            )
+            .unwrap();
+        }
+        let groups = |session: &mut session::Session, sql: &str| match run(session, sql).unwrap() {
+            SqlResult::ResultSet { rows, .. } => strings(&rows),
+            SqlResult::Ok { .. } => Vec::new(),
+        };
+
+        assert_eq!(
+            groups(
+                &mut session,
+                "select username, count(*) from users group by username having count(*) > 1 order by username;",
+            ),
+            vec![vec!["alice", "2"], vec!["bob", "2"]]
+        );
+        // WHERE drops rows before grouping, HAVING drops whole groups afterwards.
+        assert_eq!(
+            groups(
+                &mut session,
+                "select username, count(*) from users where id < 5 group by username having count(*) > 1;",
+            ),
+            vec![vec!["bob", "2"]]
+        );
+        assert_eq!(
+            groups(
+                &mut session,
+                "select username from users group by username having username = 'carol' or max(id) = 5;",
+            ),
+            vec![vec!["alice"], vec!["carol"]]
+        );
+        assert_eq!(
+            groups(
+                &mut session,
+                "select count(*) from users having count(*) > 9;"
+            ),
+            Vec::<Vec<String>>::new()
+        );
+
+        let err = run(
+            &mut session,
+            "select username from users group by username having id > 1;",
+        )
+        .unwrap_err();
+        assert!(matches!(err, errors::Error::Semantic(_)), "{}", err);
+    }
 }
@@ -1,6 +1,11 @@
+use crate::storage::schema::TableSchema;
 use crate::{errors::Error, storage};
 use std::sync::{Arc, Mutex};
-use std::{collections::HashMap, path::PathBuf};
+use std::time::Duration;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 use tracing::{info, warn};
 
 pub struct Database {
@@ -9,9 +14,25 @@ pub struct Database {
     tables: HashMap<String, Arc<Mutex<storage::Table>>>,
 }
 
+/// The directory databases are stored in, one subdirectory per database.
+pub const DATA_DIR: &str = "data";
+
 impl Database {
     pub fn create(name: &String) -> Result<Self, Error> {
-        let path = PathBuf::from(format!("data/{}", name));
+        Self::create_in(Path::new(DATA_DIR), name)
+    }
+
+    pub fn get(name: &String) -> Result<Self, Error> {
+        Self::get_in(Path::new(DATA_DIR), name)
+    }
+
+    pub fn get_or_create(name: &String) -> Result<Self, Error> {
+        Self::get_or_create_in(Path::new(DATA_DIR), name)
+    }
+
+    /// Creates a new database under the `root` data directory.
+    pub fn create_in(root: &Path, name: &String) -> Result<Self, Error> {
+        let path = root.join(name);
         std::fs::create_dir_all(&path)?;
 
         if path.exists() && std::fs::read_dir(&path)?.next().is_some() {
@@ -21,34 +42,58 @@ impl Database {
         Self::load(name.to_string(), path)
     }
 
-    pub fn get(name: &String) -> Result<Self, Error> {
-        let path = PathBuf::from(format!("data/{}", name));
+    /// Opens an existing database under the `root` data directory.
+    pub fn get_in(root: &Path, name: &String) -> Result<Self, Error> {
+        let path = root.join(name);
         if !path.exists() {
             return Err(err!(Storage, "Database '{}' not found", name));
         }
         Self::load(name.to_string(), path)
     }
 
-    pub fn get_or_create(name: &String) -> Result<Self, Error> {
-        let path = PathBuf::from(format!("data/{}", name));
+    /// Opens a database under the `root` data directory, creating it if missing.
+    pub fn get_or_create_in(root: &Path, name: &String) -> Result<Self, Error> {
+        let path = root.join(name);
         std::fs::create_dir_all(&path)?;
         Self::load(name.to_string(), path)
     }
 
+    /// Opens a database for tests in its own temporary data directory.
+    ///
+    /// The directory is removed when the returned `TempDir` is dropped, so it must
+    /// outlive the database.
+    pub fn open_test() -> Result<(tempfile::TempDir, Self), Error> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("mysqlite_test_")
+            .tempdir()?;
+        let db = Self::create_in(temp_dir.path(), &"test".to_string())?;
+        Ok((temp_dir, db))
+    }
+
+    /// Returns the directory holding the tablespace files of the database.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Checks whether the database directory still exists on disk.
+    pub fn exists(&self) -> bool {
+        self.path.is_dir()
+    }
+
     fn load(name: String, path: PathBuf) -> Result<Self, Error> {
         let mut tables = HashMap::new();
 
         if let Ok(entries) = std::fs::read_dir(&path) {
             for entry in entries.filter_map(Result::ok) {
-                let path = entry.path();
+                let file = entry.path();
 
-                if path.extension() != Some("tbd".as_ref()) {
+                if file.extension() != Some("tbd".as_ref()) {
                     continue;
                 }
 
-                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                if let Some(stem) = file.file_stem().and_then(|s| s.to_str()) {
                     let table_name = stem.to_string();
-                    let table = storage::table::load_table(&name, &table_name)?;
+                    let table = storage::table::load_table(&path, &name, &table_name)?;
                     tables.insert(table_name, Arc::new(Mutex::new(table)));
                 }
             }
@@ -57,7 +102,7 @@ impl Database {
         Ok(Self { name, path, tables })
     }
 
-    pub fn create_table(&mut self, name: &String) -> Result<(), Error> {
+    pub fn create_table(&mut self, name: &String, schema: TableSchema) -> Result<(), Error> {
         if self.tables.contains_key(name) {
             return Err(err!(
                 Storage,
@@ -67,7 +112,7 @@ impl Database {
             ));
         }
 
-        let table = storage::table::create_table(&self.name, name)?;
+        let table = storage::table::create_table(&self.path, &self.name, name, Arc::new(schema))?;
         self.tables
             .insert(name.to_string(), Arc::new(Mutex::new(table)));
         Ok(())
@@ -77,30 +122,178 @@ impl Database {
         self.tables
             .remove(name)
             .ok_or_else(|| err!(Storage, "Table '{}.{}' doesn't exist", self.name, name))?;
-        storage::table::drop_table(&self.name, name)?;
+        storage::table::drop_table(&self.path, &self.name, name)?;
+        Ok(())
+    }
+
+    /// Renames a table, moving its tablespace file and registry entry.
+    ///
+    /// Fails with `Error::Schema` if a table named `new_name` already exists.
+    pub fn rename_table(
+        &mut self,
+        name: &String,
+        new_name: &String,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        if self.tables.contains_key(new_name) {
+            return Err(err!(
+                Schema,
+                "Table '{}.{}' already exists",
+                self.name,
+                new_name
+            ));
+        }
+        let table = self.find_table(name)?.clone();
+        let mut guard = storage::lock_with_timeout(&table, name, timeout)?;
+        storage::table::rename_table(&mut guard, new_name)?;
+        drop(guard);
+        self.tables.remove(name);
+        self.tables.insert(new_name.clone(), table);
         Ok(())
     }
 
+    /// Returns the names of all tables in the database, sorted by name.
+    pub fn table_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.tables.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
     pub fn find_table(&self, name: &String) -> Result<&Arc<Mutex<storage::Table>>, Error> {
         self.tables
             .get(name)
             .ok_or_else(|| err!(Storage, "Table '{}.{}' doesn't exist", self.name, name))
     }
 
-    pub fn flush(&self) -> Result<(), Error> {
+    /// Returns the name, row count and page count of every table, sorted by name.
+    ///
+    /// Loaded tables are inspected through their pager, tables only present on disk
+    /// are reported from their tablespace header.
+    ///
+    /// # Arguments
+    /// * `timeout` - The maximum time to wait for each table lock.
+    pub fn table_stats(&self, timeout: Duration) -> Result<Vec<(String, u32, usize)>, Error> {
+        let mut stats = Vec::new();
+
+        for entry in std::fs::read_dir(&self.path)?.filter_map(Result::ok) {
+            let path = entry.path();
+            if path.extension() != Some("tbd".as_ref()) {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let name = name.to_string();
+
+            let (rows, pages) = match self.tables.get(&name) {
+                Some(table) => {
+                    let table = storage::lock_with_timeout(table, &name, timeout)?;
+                    (table.pager.table_n_recs()?, table.pager.len())
+                }
+                None => {
+                    let (header, pages) =
+                        storage::table::peek_table(&self.path, &self.name, &name)?;
+                    (header.table_n_recs, pages)
+                }
+            };
+            stats.push((name, rows, pages));
+        }
+
+        stats.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(stats)
+    }
+
+    /// Copies the tablespace files of all tables into `target`, as a snapshot.
+    ///
+    /// Every table is locked before the first one is copied and flushed while
+    /// locked, so the copies reflect a single point in time.
+    ///
+    /// # Arguments
+    /// * `target` - The directory to copy into, created if missing. It must not be
+    ///   the directory of the database itself.
+    /// * `timeout` - The maximum time to wait for each table lock.
+    ///
+    /// # Returns
+    /// The names of the copied tables, sorted by name.
+    pub fn backup(&self, target: &Path, timeout: Duration) -> Result<Vec<String>, Error> {
+        std::fs::create_dir_all(target)?;
+        if target.canonicalize()? == self.path.canonicalize()? {
+            return Err(err!(
+                Storage,
+                "Cannot back up database '{}' into its own directory",
+                self.name
+            ));
+        }
+        let names = self.table_names();
+        let mut tables = Vec::with_capacity(names.len());
+        // Lock in name order so concurrent backups can't deadlock each other
+        for name in &names {
+            let table = &self.tables[name];
+            tables.push(storage::lock_with_timeout(table, name, timeout)?);
+        }
+        for table in tables.iter_mut() {
+            table.flush()?;
+            let file_name = table
+                .path
+                .file_name()
+                .ok_or_else(|| err!(Storage, "Invalid table path for '{}'", table.name))?;
+            std::fs::copy(&table.path, target.join(file_name))?;
+        }
+        info!(name = %self.name, target = %target.display(), "Backed up database");
+        Ok(names)
+    }
+
+    /// Builds a SQL script of `CREATE TABLE` and `INSERT` statements that
+    /// recreates every table of the database, as written by `\dump`.
+    ///
+    /// # Arguments
+    /// * `timeout` - The maximum time to wait for each table lock.
+    ///
+    /// # Returns
+    /// The script, with tables in name order and rows in primary key order.
+    pub fn dump(&self, timeout: Duration) -> Result<String, Error> {
+        let mut script = format!("-- MySQLite dump of database '{}'\n", self.name);
+        for name in self.table_names() {
+            let mut table = storage::lock_with_timeout(&self.tables[&name], &name, timeout)?;
+            script.push_str(&format!("\n{}\n", table.schema.to_create_sql(&name)));
+            let columns: Vec<&str> = table
+                .schema
+                .columns()
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect();
+            let columns = columns.join(", ");
+            storage::for_each_row(&mut table, |row| {
+                let values: Vec<String> = row.values.iter().map(|v| v.to_sql_literal()).collect();
+                script.push_str(&format!(
+                    "INSERT INTO {} ({}) VALUES ({});\n",
+                    name,
+                    columns,
+                    values.join(", ")
+                ));
+                Ok(true)
+            })?;
+        }
+        Ok(script)
+    }
+
+    /// Checks whether any table has changes not yet written to disk.
+    ///
+    /// Tables locked by another thread are skipped.
+    pub fn is_dirty(&self) -> bool {
+        self.tables
+            .values()
+            .any(|table| table.try_lock().is_ok_and(|t| t.is_dirty()))
+    }
+
+    /// Writes the changes of every table to disk, waiting at most `timeout` for
+    /// each table lock.
+    pub fn flush(&self, timeout: Duration) -> Result<(), Error> {
         info!(name = %self.name, "Flushing database...");
 
         for (name, table) in &self.tables {
             info!(table = %name, "Flushing table...");
-            let mut table = table.lock().map_err(|e| {
-                err!(
-                    LockTable,
-                    "Failed to lock table '{}.{}': {}",
-                    self.name,
-                    name,
-                    e
-                )
-            })?;
+            let mut table = storage::lock_with_timeout(table, name, timeout)?;
 
             if let Err(e) = table.flush() {
                 warn!(table = %name, "Failed to flush table: {}", e);
@@ -114,8 +307,8 @@ impl Database {
     }
 }
 
-pub fn show_databases() -> Result<Vec<String>, Error> {
-    let path = PathBuf::from("data");
+pub fn show_databases(root: &Path) -> Result<Vec<String>, Error> {
+    let path = root.to_path_buf();
     std::fs::create_dir_all(&path)?;
 
     let databases = std::fs::read_dir(&path)?
@@ -129,11 +322,98 @@ pub fn show_databases() -> Result<Vec<String>, Error> {
     Ok(databases)
 }
 
-pub fn drop_database(name: &str) -> Result<(), Error> {
-    let path = PathBuf::from(format!("data/{}", name));
+pub fn drop_database(root: &Path, name: &str) -> Result<(), Error> {
+    let path = root.join(name);
     if !path.exists() {
         return Err(err!(Storage, "Unknown database '{}'", name));
     }
     std::fs::remove_dir_all(&path)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_stats() {
+        let (_dir, mut db) = Database::open_test().unwrap();
+        assert!(db.table_stats(storage::LOCK_TIMEOUT).unwrap().is_empty());
+
+        db.create_table(&"users".into(), storage::table::SCHEMA.as_ref().clone())
+            .unwrap();
+        {
+            let mut table = db.find_table(&"users".into()).unwrap().lock().unwrap();
+            for id in 1..=3 {
+                let row = storage::build_row(
+                    &storage::table::SCHEMA,
+                    &["id".into(), "email".into()],
+                    &[id.to_string(), format!("user{}@example.com", id)],
+                )
+                .unwrap();
+                storage::insert_row(&mut table, &row).unwrap();
+            }
+            // Every table lock waits at most the given timeout
+            let timeout = Duration::from_millis(20);
+            assert!(matches!(db.table_stats(timeout), Err(Error::LockTable(_))));
+            assert!(matches!(db.flush(timeout), Err(Error::LockTable(_))));
+            assert!(matches!(db.dump(timeout), Err(Error::LockTable(_))));
+        }
+        assert_eq!(
+            db.table_stats(storage::LOCK_TIMEOUT).unwrap(),
+            vec![("users".into(), 3, 1)]
+        );
+
+        // Unloaded tables are reported straight from the tablespace header.
+        db.flush(storage::LOCK_TIMEOUT).unwrap();
+        db.tables.clear();
+        assert_eq!(
+            db.table_stats(storage::LOCK_TIMEOUT).unwrap(),
+            vec![("users".into(), 3, 1)]
+        );
+    }
+
+    #[test]
+    fn test_backup() {
+        let (dir, mut db) = Database::open_test().unwrap();
+        db.create_table(&"users".into(), storage::table::SCHEMA.as_ref().clone())
+            .unwrap();
+        {
+            let mut table = db.find_table(&"users".into()).unwrap().lock().unwrap();
+            for id in 1..=20 {
+                let row = storage::build_row(
+                    &storage::table::SCHEMA,
+                    &["id".into(), "email".into()],
+                    &[id.to_string(), format!("user{}@example.com", id)],
+                )
+                .unwrap();
+                storage::insert_row(&mut table, &row).unwrap();
+            }
+        }
+
+        // Back up into a database directory so the snapshot can be opened as one
+        let backup_name = "backup".to_string();
+        let copied = db
+            .backup(&dir.path().join(&backup_name), storage::LOCK_TIMEOUT)
+            .unwrap();
+        assert_eq!(copied, vec!["users".to_string()]);
+
+        // Copying the files onto themselves would truncate them
+        let err = db
+            .backup(&dir.path().join("backup/../test"), storage::LOCK_TIMEOUT)
+            .unwrap_err();
+        assert!(matches!(err, Error::Storage(_)), "{}", err);
+
+        let backup = Database::get_in(dir.path(), &backup_name).unwrap();
+        let rows = |db: &Database| {
+            let mut table = db.find_table(&"users".into()).unwrap().lock().unwrap();
+            storage::select_rows(&mut table, None)
+                .unwrap()
+                .iter()
+                .map(|row| row.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rows(&backup).len(), 20);
+        assert_eq!(rows(&backup), rows(&db));
+    }
+}
@@ -5,11 +5,15 @@ mod command;
 mod database;
 mod repl;
 mod session;
+mod signals;
 mod sql;
 mod storage;
 use clap::Parser;
 use std::fs::OpenOptions;
 use std::io;
+use std::path::PathBuf;
+use std::process;
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriter};
 use tracing_subscriber::EnvFilter;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -31,32 +35,141 @@ struct Cli {
     /// Start the database server as a standalone process.
     #[arg(long, short, env = "MYSQLITE_SERVER", default_value = "false")]
     server: bool,
+    /// Execute the given SQL statements non-interactively and exit.
+    #[arg(long, short)]
+    execute: Option<String>,
+    /// File to write logs to, or `-` for stderr.
+    #[arg(long, default_value = "mysqlite.log")]
+    log_file: String,
+    /// Minimum level of log records to write. `RUST_LOG` takes precedence.
+    #[arg(long, default_value = "info")]
+    log_level: String,
+    /// Print results as tab-separated values. Only applies to --execute.
+    #[arg(long, short = 'B')]
+    batch: bool,
+    /// Execute the SQL statements of a file at startup, before any other input.
+    #[arg(long)]
+    init_file: Option<PathBuf>,
+    /// Exit when a statement of --init-file fails, instead of printing the error and going on.
+    #[arg(long, requires = "init_file")]
+    abort_on_init_error: bool,
+    /// Keep the command history in memory instead of saving it to ~/.mysqlite_history.
+    #[arg(long)]
+    no_history: bool,
 }
 
-fn main() {
-    let file = OpenOptions::new()
-        .append(true)
-        .create(true)
-        .open("mysqlite.log")
-        .expect("Failed to open log file");
-
+/// Builds the log subscriber for the given level and writer.
+///
+/// # Arguments
+/// * `level` - The default filter, used unless `rust_log` is a valid filter.
+/// * `rust_log` - The value of the `RUST_LOG` environment variable, if set.
+/// * `writer` - Where log records are written.
+fn log_subscriber<W>(
+    level: &str,
+    rust_log: Option<&str>,
+    writer: W,
+) -> impl tracing::Subscriber + Send + Sync
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
     tracing_subscriber::fmt()
-        .with_writer(file)
+        .with_writer(writer)
         .with_ansi(false)
         .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("trace")),
+            rust_log
+                .and_then(|filter| EnvFilter::try_new(filter).ok())
+                .unwrap_or_else(|| EnvFilter::new(level)),
         )
-        .init();
+        .finish()
+}
 
+fn main() {
     let cli = Cli::parse();
+
+    let writer = if cli.log_file == "-" {
+        BoxMakeWriter::new(io::stderr)
+    } else {
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&cli.log_file)
+            .expect("Failed to open log file");
+        BoxMakeWriter::new(file)
+    };
+    let rust_log = std::env::var(EnvFilter::DEFAULT_ENV).ok();
+    tracing::subscriber::set_global_default(log_subscriber(
+        &cli.log_level,
+        rust_log.as_deref(),
+        writer,
+    ))
+    .expect("Failed to set up logging");
+
+    if let Err(e) = signals::install() {
+        tracing::warn!("Failed to install signal handlers: {}", e);
+    }
+
     if cli.server {
         println!("Server mode is not supported yet.");
         return;
     }
 
-    match repl::console::start() {
+    let init_file = cli.init_file.map(|path| repl::console::InitFile {
+        path,
+        abort_on_error: cli.abort_on_init_error,
+    });
+
+    if let Some(sql) = cli.execute {
+        if let Err(e) = repl::console::execute(&sql, cli.batch, init_file.as_ref()) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    match repl::console::start(init_file.as_ref(), !cli.no_history) {
         Ok(_) => (),
         Err(errors::Error::Io(e)) if e.kind() == io::ErrorKind::Interrupted => (), // Silence Ctrl+C
         Err(e) => println!("\nError: {}", e),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_log_level() {
+        let logs = |rust_log: Option<&str>| {
+            let capture = Capture::default();
+            let writer = capture.clone();
+            let subscriber = log_subscriber("error", rust_log, move || writer.clone());
+            tracing::subscriber::with_default(subscriber, || {
+                tracing::info!("info record");
+                tracing::error!("error record");
+            });
+            let logs = capture.0.lock().unwrap().clone();
+            String::from_utf8(logs).unwrap()
+        };
+
+        let default = logs(None);
+        assert!(default.contains("error record"));
+        assert!(!default.contains("info record"));
+        // RUST_LOG takes precedence over the level
+        assert!(logs(Some("info")).contains("info record"));
+    }
+}
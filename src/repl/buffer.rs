@@ -64,6 +64,30 @@ impl Buffer {
         last_line.remove(idx);
     }
 
+    /// Removes the characters in `start..end` from the current line.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds for the current line.
+    pub fn remove_range(&mut self, start: usize, end: usize) {
+        let last_line = self
+            .lines
+            .last_mut()
+            .expect("Buffer should always have at least one line");
+        assert!(
+            start <= end && end <= last_line.len(),
+            "Index out of bounds"
+        );
+        last_line.drain(start..end);
+    }
+
+    /// Replaces the contents of the buffer with the lines of `text`.
+    pub fn set_text(&mut self, text: &str) {
+        self.lines = text.lines().map(String::from).collect();
+        if self.lines.is_empty() {
+            self.clear();
+        }
+    }
+
     /// Combines all lines into a single String with spaces between lines.
     pub fn build(&self) -> String {
         self.lines
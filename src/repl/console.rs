@@ -1,11 +1,12 @@
 use super::{buffer, prompt, status};
-use crate::{command, database, errors, session, sql};
+use crate::{command, database, errors, session, sql, storage};
 use crossterm::{
     cursor, execute,
     style::{Color, Print, ResetColor, SetForegroundColor},
     terminal::{self, Clear, ClearType},
 };
 use std::io::{self, Write};
+use std::path::PathBuf;
 use std::sync::Once;
 use std::time::Instant;
 use tracing::{error, info};
@@ -26,19 +27,72 @@ Note that all text commands must be first on line and end with ';'
 
 ?           (\?) Synonym for 'help'.
 help        (\h) Display this help.
+clear       (\c) Clear the screen.
 use         (\u) Use another database. Takes database name as argument.
 version     (\v) Show version information.
-print_btree Print B-Tree node.
+tables      (\dt) List tables with their row and page counts.
+print_btree (\btree) Print B-Tree nodes of a table. Takes table name as argument.
+edit        (\e) Edit the current statement in $EDITOR.
+check       (\check) Validate an SQL statement without executing it.
+backup      (\backup) Copy all tables of the database to a directory. Takes the directory as argument.
+dump        (\dump) Write a database as SQL statements. Takes database name and file as arguments.
+source      (\.) Execute the SQL statements of a file. Takes file name as argument.
 quit        (\q) Quit MySQLite.
 "#;
 
+/// An SQL script executed against a session at startup, given with `--init-file`.
+pub struct InitFile {
+    /// The path of the script.
+    pub path: PathBuf,
+    /// Fail startup on the first failing statement instead of printing the error.
+    pub abort_on_error: bool,
+}
+
+/// Executes the init file against the session.
+///
+/// Statements run in order and stop at the first failure. The error is returned when
+/// `abort_on_error` is set, and otherwise printed to stderr.
+///
+/// # Arguments
+/// * `session` - The session to set up.
+/// * `init_file` - The script to execute.
+pub fn run_init_file(
+    session: &mut session::Session,
+    init_file: &InitFile,
+) -> Result<(), errors::Error> {
+    info!(path = %init_file.path.display(), "Executing init file...");
+    let result = std::fs::File::open(&init_file.path)
+        .map_err(errors::Error::from)
+        .and_then(|file| session.execute_script(file));
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) if init_file.abort_on_error => Err(err!(
+            Command,
+            "Error in init file {}: {}",
+            init_file.path.display(),
+            e
+        )),
+        Err(e) => {
+            eprintln!("Error in init file {}: {}", init_file.path.display(), e);
+            Ok(())
+        }
+    }
+}
+
 /// Starts a REPL session in raw console mode.
 ///
+/// # Arguments
+/// * `init_file` - A script executed before the first prompt.
+/// * `save_history` - Whether to load and save the history file.
+///
 /// # Returns
 /// A `Result` indicating success or an `errors::Error` if initialization or cleanup fails.
-pub fn start() -> Result<(), errors::Error> {
+pub fn start(init_file: Option<&InitFile>, save_history: bool) -> Result<(), errors::Error> {
     let mut session = session::Session::open()?;
     info!(session_id = %session.id, "Starting REPL session...");
+    if let Some(init_file) = init_file {
+        run_init_file(&mut session, init_file)?;
+    }
 
     // Enable raw mode and blinking cursor
     terminal::enable_raw_mode()?;
@@ -54,7 +108,7 @@ pub fn start() -> Result<(), errors::Error> {
         }));
     });
 
-    let mut console = Console::new(&mut session);
+    let mut console = Console::new(&mut session, save_history);
     let result = console.start();
 
     // Ensure raw mode is disabled and session is closed, even on error
@@ -68,21 +122,82 @@ pub fn start() -> Result<(), errors::Error> {
     result
 }
 
+/// Executes SQL statements non-interactively and prints their results to stdout.
+///
+/// No raw terminal mode or REPL is used, which makes this suitable for scripts.
+///
+/// # Arguments
+/// * `sql` - One or more SQL statements separated by `;`.
+/// * `batch` - Print result sets as tab-separated values without summaries.
+/// * `init_file` - A script executed before `sql`.
+///
+/// # Returns
+/// A `Result` indicating success or the `errors::Error` of the first failing statement.
+pub fn execute(sql: &str, batch: bool, init_file: Option<&InitFile>) -> Result<(), errors::Error> {
+    let mut session = session::Session::open()?;
+    info!(session_id = %session.id, "Executing statements non-interactively...");
+    if let Some(init_file) = init_file {
+        run_init_file(&mut session, init_file)?;
+    }
+
+    let result = sql::split_statements(sql)
+        .into_iter()
+        .try_for_each(|statement| {
+            crate::signals::check_shutdown()?;
+            let sql_cmd = sql::parser::parse(statement)?;
+            match command::execute(&mut session, sql_cmd)? {
+                command::SqlResult::ResultSet { columns, rows } if batch => {
+                    print!("{}", build_tsv(&columns, &rows));
+                }
+                command::SqlResult::Ok { .. } if batch => {}
+                command::SqlResult::Ok {
+                    affected_rows,
+                    last_insert_id,
+                } => println!("{}", query_ok(affected_rows, last_insert_id)),
+                command::SqlResult::ResultSet { columns, rows } => {
+                    print!("{}", render_result_set(&session.settings, &columns, &rows));
+                    println!(
+                        "{} row{} in set",
+                        rows.len(),
+                        if rows.len() == 1 { "" } else { "s" }
+                    );
+                }
+            }
+            for warning in &session.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            Ok(())
+        });
+
+    // Keep the effects of the statements that succeeded
+    session.close()?;
+    result
+}
+
 struct Console<'a> {
     session: &'a mut session::Session,
     prompt: prompt::Prompt,
     status: status::StatusBar,
     buffer: buffer::Buffer,
+    /// The command `\e` opens statements with, taken from `$EDITOR`.
+    editor: Option<String>,
 }
 
 impl<'a> Console<'a> {
     /// Creates a new console instance.
-    pub fn new(session: &'a mut session::Session) -> Self {
+    ///
+    /// # Arguments
+    /// * `session` - The session statements run in.
+    /// * `save_history` - Whether the prompt loads and saves the history file.
+    pub fn new(session: &'a mut session::Session, save_history: bool) -> Self {
         Self {
             session,
-            prompt: prompt::Prompt::new(),
+            prompt: prompt::Prompt::new(save_history),
             status: status::StatusBar::new(),
             buffer: buffer::Buffer::new(),
+            editor: std::env::var("EDITOR")
+                .ok()
+                .filter(|e| !e.trim().is_empty()),
         }
     }
 
@@ -97,6 +212,7 @@ impl<'a> Console<'a> {
     pub fn start(&mut self) -> Result<(), errors::Error> {
         self.draw()?;
         echo_line(format!("Welcome to the {} {} REPL.", NAME, VERSION))?;
+        echo_lines(build_status_lines(self.session))?;
         echo_lines(BANNER.to_string())?;
 
         let mut continue_prompt = false;
@@ -111,10 +227,12 @@ impl<'a> Console<'a> {
             self.buffer.newline();
 
             self.update_status()?;
-            self.prompt.readline(&mut self.buffer, &mut self.status)?;
+            let outcome = self
+                .prompt
+                .readline(&mut self.buffer, &mut self.status, self.session)?;
 
             let input = self.buffer.build();
-            continue_prompt = self.handle_input(&input)?;
+            continue_prompt = self.handle_read(outcome)?;
             if !continue_prompt && input.as_str() == "quit" {
                 break;
             }
@@ -135,17 +253,52 @@ impl<'a> Console<'a> {
         self.status.draw()
     }
 
+    /// Handles the outcome of reading a line and returns whether to continue the prompt.
+    ///
+    /// A cancelled read discards the partial statement, so the next prompt is a
+    /// primary one even in the middle of a multi-line statement.
+    fn handle_read(&mut self, outcome: prompt::ReadOutcome) -> Result<bool, errors::Error> {
+        match outcome {
+            prompt::ReadOutcome::Cancelled => {
+                self.buffer.clear();
+                Ok(false)
+            }
+            prompt::ReadOutcome::Line => {
+                let input = self.buffer.build();
+                self.handle_input(&input)
+            }
+        }
+    }
+
     /// Handles user input and returns whether to continue the prompt.
     fn handle_input(&mut self, input: &str) -> Result<bool, errors::Error> {
+        // Nothing was typed, start over with a fresh prompt instead of continuing
+        if input.trim().is_empty() {
+            return Ok(false);
+        }
         match input.trim() {
             "exit" | "quit" | "\\q" => {
                 self.prompt.append_line(input);
                 return Ok(false);
             }
             cmd if cmd.starts_with("use") || cmd.starts_with("\\u") => self.handle_use(cmd),
+            cmd if is_clear_command(cmd) => self.handle_clear(cmd),
             "version" | "\\v" => self.handle_version(input),
-            "print_btree" => self.handle_print_btree(input),
+            "tables" | "\\dt" => self.handle_tables(input),
+            cmd if cmd.starts_with("print_btree") || cmd.starts_with("\\btree") => {
+                self.handle_print_btree(cmd)
+            }
+            cmd if cmd.starts_with("check") || cmd.starts_with("\\check") => self.handle_check(cmd),
+            cmd if cmd.starts_with("backup") || cmd.starts_with("\\backup") => {
+                self.handle_backup(cmd)
+            }
+            cmd if cmd.starts_with("dump") || cmd.starts_with("\\dump") => self.handle_dump(cmd),
+            cmd if cmd.starts_with("source") || cmd.starts_with("\\.") => self.handle_source(cmd),
             "help" | "\\h" | "\\?" | "?" => self.handle_help(input),
+            "edit" => self.handle_edit(""),
+            cmd if strip_edit_command(cmd).is_some() => {
+                self.handle_edit(strip_edit_command(cmd).unwrap_or_default())
+            }
             _ => self.handle_command(input),
         }
     }
@@ -157,13 +310,19 @@ impl<'a> Console<'a> {
             .nth(1)
             .ok_or_else(|| err!(Command, "USE must be followed by a database name"))?;
         let dbname = dbname.trim_end_matches(|c: char| c == ';').to_string();
-        let db = database::Database::get(&dbname)?;
-        self.session.set_database(db)?;
+        self.session.set_database(&dbname)?;
         next_line()?;
         echo_line("Database changed".to_string())?;
         Ok(false)
     }
 
+    /// Clears the screen; the REPL loop then starts a fresh prompt at the top.
+    fn handle_clear(&mut self, cmd: &str) -> Result<bool, errors::Error> {
+        self.prompt.append_line(cmd);
+        prompt::clear_screen()?;
+        Ok(false)
+    }
+
     fn handle_version(&mut self, cmd: &str) -> Result<bool, errors::Error> {
         self.prompt.append_line(cmd);
         next_line()?;
@@ -178,67 +337,256 @@ impl<'a> Console<'a> {
         Ok(false)
     }
 
-    fn handle_print_btree(&mut self, cmd: &str) -> Result<bool, errors::Error> {
+    fn handle_tables(&mut self, cmd: &str) -> Result<bool, errors::Error> {
         self.prompt.append_line(cmd);
-        let (total, columns, rows) = self
+        let stats = self
             .session
             .database
-            .find_table(&"users".into())?
-            .lock()
-            .unwrap()
-            .build_btree()?;
+            .table_stats(self.session.lock_timeout)?;
+        next_line()?;
+        if stats.is_empty() {
+            echo_line("No tables".to_string())?;
+            return Ok(false);
+        }
+
+        let columns = vec!["Table".to_string(), "Rows".to_string(), "Pages".to_string()];
+        let rows: Vec<Vec<String>> = stats
+            .into_iter()
+            .map(|(name, rows, pages)| vec![name, rows.to_string(), pages.to_string()])
+            .collect();
+        echo_lines(build_table(&columns, &rows))?;
+        Ok(false)
+    }
+
+    fn handle_print_btree(&mut self, cmd: &str) -> Result<bool, errors::Error> {
+        self.prompt.append_line(cmd);
+        let name = cmd
+            .split_whitespace()
+            .nth(1)
+            .ok_or_else(|| err!(Command, "BTREE must be followed by a table name"))?;
+        let name = name.trim_end_matches(';').to_string();
+        let (total, columns, rows, outline) = {
+            let table = self.session.database.find_table(&name)?;
+            let table = storage::lock_with_timeout(table, &name, self.session.lock_timeout)?;
+            let (total, columns, rows) = table.build_btree()?;
+            (total, columns, rows, table.build_btree_outline()?)
+        };
         next_line()?;
         echo_lines(build_table(&columns, &rows))?;
+        echo_lines(outline.join("\n"))?;
         echo_line(format!("Total pages: {}", total))?;
         Ok(false)
     }
 
-    fn handle_command(&mut self, cmd: &str) -> Result<bool, errors::Error> {
-        if cmd.starts_with('\\') {
-            next_line()?;
-            echo_error(format!("Unrecognized command: {}", cmd))?;
-            echo_lines(HELP.to_string())?;
-            return Ok(false);
+    fn handle_check(&mut self, cmd: &str) -> Result<bool, errors::Error> {
+        self.prompt.append_line(cmd);
+        let sql = cmd
+            .split_once(char::is_whitespace)
+            .map(|(_, sql)| sql.trim())
+            .filter(|sql| !sql.is_empty())
+            .ok_or_else(|| err!(Command, "CHECK must be followed by an SQL statement"))?;
+
+        let database = &self.session.database;
+        let timeout = self.session.lock_timeout;
+        let schema = |name: &str| {
+            let table = database.find_table(&name.to_string()).ok()?;
+            let table = storage::lock_with_timeout(table, name, timeout).ok()?;
+            Some(table.schema.as_ref().clone())
+        };
+        let result = sql::validate(sql, Some(&schema));
+
+        next_line()?;
+        match result {
+            Ok(()) => echo_line("Statement is valid".to_string())?,
+            Err(e) => echo_error(format!("{}\n", e))?,
         }
+        Ok(false)
+    }
 
-        if cmd.ends_with(';') || cmd.ends_with("\\g") {
-            self.prompt.append_line(cmd);
-            let start = Instant::now();
-            match sql::parser::parse(cmd.to_string()) {
-                Ok(sql_cmd) => match command::execute(self.session, sql_cmd) {
-                    Ok(result) => {
-                        let elapsed = start.elapsed().as_secs_f32();
-                        match result {
-                            command::SqlResult::Ok { affected_rows } => {
-                                next_line()?;
-                                echo_line(format!(
-                                    "Query OK, {} row{} affected ({:.2} sec)",
-                                    affected_rows,
-                                    if affected_rows == 1 { "" } else { "s" },
-                                    elapsed
-                                ))?;
-                            }
-                            command::SqlResult::ResultSet { columns, rows } => {
-                                next_line()?;
-                                echo_lines(build_table(&columns, &rows))?;
-                                echo_line(format!(
-                                    "{} row{} in set ({:.2} sec)",
-                                    rows.len(),
-                                    if rows.len() == 1 { "" } else { "s" },
-                                    elapsed
-                                ))?;
-                            }
+    fn handle_backup(&mut self, cmd: &str) -> Result<bool, errors::Error> {
+        self.prompt.append_line(cmd);
+        let target = cmd
+            .split_whitespace()
+            .nth(1)
+            .ok_or_else(|| err!(Command, "BACKUP must be followed by a directory"))?;
+        let target = target.trim_end_matches(';');
+        let tables = self
+            .session
+            .database
+            .backup(std::path::Path::new(target), self.session.lock_timeout)?;
+        next_line()?;
+        echo_line(format!(
+            "Backed up {} table{} to {}",
+            tables.len(),
+            if tables.len() == 1 { "" } else { "s" },
+            target
+        ))?;
+        Ok(false)
+    }
+
+    fn handle_dump(&mut self, cmd: &str) -> Result<bool, errors::Error> {
+        self.prompt.append_line(cmd);
+        let args: Vec<&str> = cmd
+            .trim_end_matches(';')
+            .split_whitespace()
+            .skip(1)
+            .collect();
+        let [name, file] = args[..] else {
+            return Err(err!(
+                Command,
+                "DUMP must be followed by a database name and a file"
+            ));
+        };
+        let script = if name == self.session.database.name {
+            self.session.database.dump(self.session.lock_timeout)?
+        } else {
+            database::Database::get_in(&self.session.data_dir, &name.to_string())?
+                .dump(self.session.lock_timeout)?
+        };
+        std::fs::write(file, script)?;
+        next_line()?;
+        echo_line(format!("Dumped database '{}' to {}", name, file))?;
+        Ok(false)
+    }
+
+    fn handle_source(&mut self, cmd: &str) -> Result<bool, errors::Error> {
+        self.prompt.append_line(cmd);
+        let file = cmd
+            .split_whitespace()
+            .nth(1)
+            .ok_or_else(|| err!(Command, "SOURCE must be followed by a file name"))?;
+        let file = file.trim_end_matches(';');
+        let results = self.session.execute_script(std::fs::File::open(file)?)?;
+        next_line()?;
+        echo_line(format!(
+            "Query OK, {} statement{} executed",
+            results.len(),
+            if results.len() == 1 { "" } else { "s" }
+        ))?;
+        Ok(false)
+    }
+
+    /// Opens the statement typed so far in the editor and reads it back into the buffer.
+    ///
+    /// An edited statement ending with `;` is executed right away; otherwise it stays
+    /// in the buffer and the prompt continues.
+    fn handle_edit(&mut self, statement: &str) -> Result<bool, errors::Error> {
+        let edited = match self.editor.clone() {
+            Some(editor) => self.edit_buffer(&editor, statement),
+            None => Err(err!(Command, "No editor set; set $EDITOR to use \\e")),
+        };
+        next_line()?;
+        match edited {
+            Ok(text) => echo_lines(text)?,
+            Err(e) => {
+                echo_error(e.to_string())?;
+                self.buffer.set_text(statement);
+            }
+        }
+        match self.buffer.build() {
+            edited if edited.is_empty() => Ok(false),
+            edited => self.handle_command(&edited),
+        }
+    }
+
+    /// Replaces the buffer with `statement` as edited by the `editor` command.
+    ///
+    /// The statement is written to a temporary file passed to the editor, with raw mode
+    /// suspended while it runs.
+    ///
+    /// # Returns
+    /// A `Result` containing the edited text, or an `errors::Error` if the editor
+    /// could not be run or failed.
+    fn edit_buffer(&mut self, editor: &str, statement: &str) -> Result<String, errors::Error> {
+        let file = tempfile::Builder::new()
+            .prefix("mysqlite_edit_")
+            .suffix(".sql")
+            .tempfile()?;
+        std::fs::write(file.path(), format!("{}\n", statement))?;
+
+        let raw_mode = terminal::is_raw_mode_enabled()?;
+        if raw_mode {
+            terminal::disable_raw_mode()?;
+        }
+        let mut args = editor.split_whitespace();
+        let status = std::process::Command::new(args.next().unwrap_or_default())
+            .args(args)
+            .arg(file.path())
+            .status();
+        if raw_mode {
+            terminal::enable_raw_mode()?;
+        }
+        let status =
+            status.map_err(|e| err!(Command, "Failed to start editor '{}': {}", editor, e))?;
+        if !status.success() {
+            return Err(err!(Command, "Editor '{}' failed with {}", editor, status));
+        }
+
+        let text = std::fs::read_to_string(file.path())?;
+        self.buffer.set_text(&text);
+        Ok(text)
+    }
+
+    fn execute_statement(&mut self, statement: String) -> Result<(), errors::Error> {
+        let start = Instant::now();
+        match sql::parser::parse(statement) {
+            Ok(sql_cmd) => match command::execute(self.session, sql_cmd) {
+                Ok(result) => {
+                    let elapsed = start.elapsed().as_secs_f32();
+                    let settings = &self.session.settings;
+                    let precision = settings.timing_precision;
+                    match result {
+                        command::SqlResult::Ok {
+                            affected_rows,
+                            last_insert_id,
+                        } => {
+                            next_line()?;
+                            echo_line(format!(
+                                "{} ({:.precision$} sec)",
+                                query_ok(affected_rows, last_insert_id),
+                                elapsed
+                            ))?;
+                        }
+                        command::SqlResult::ResultSet { columns, rows } => {
+                            next_line()?;
+                            echo_lines(render_result_set(settings, &columns, &rows))?;
+                            echo_line(format!(
+                                "{} row{} in set ({:.precision$} sec)",
+                                rows.len(),
+                                if rows.len() == 1 { "" } else { "s" },
+                                elapsed
+                            ))?;
                         }
                     }
-                    Err(e) => {
-                        next_line()?;
-                        echo_error(format!("{}\n", e))?;
+                    for warning in &self.session.warnings {
+                        echo_line(format!("Warning: {}", warning))?;
                     }
-                },
+                }
                 Err(e) => {
                     next_line()?;
                     echo_error(format!("{}\n", e))?;
                 }
+            },
+            Err(e) => {
+                next_line()?;
+                echo_error(format!("{}\n", e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_command(&mut self, cmd: &str) -> Result<bool, errors::Error> {
+        if cmd.starts_with('\\') {
+            next_line()?;
+            echo_error(format!("Unrecognized command: {}", cmd))?;
+            echo_lines(HELP.to_string())?;
+            return Ok(false);
+        }
+
+        if cmd.ends_with(';') || cmd.ends_with("\\g") || cmd.ends_with("\\G") {
+            self.prompt.append_line(cmd);
+            for statement in sql::split_statements(cmd) {
+                self.execute_statement(statement)?;
             }
             Ok(false)
         } else {
@@ -247,6 +595,21 @@ impl<'a> Console<'a> {
     }
 }
 
+/// Splits the `\e` command off the end of the input.
+///
+/// # Returns
+/// The statement typed before `\e`, or `None` if the input does not end with it.
+fn strip_edit_command(cmd: &str) -> Option<&str> {
+    cmd.strip_suffix("\\e")
+        .filter(|rest| rest.is_empty() || rest.ends_with(char::is_whitespace))
+        .map(str::trim_end)
+}
+
+/// Checks whether the input is the `clear` (`\c`) command, with an optional `;`.
+fn is_clear_command(cmd: &str) -> bool {
+    matches!(cmd.trim_end_matches(';').trim_end(), "clear" | "\\c")
+}
+
 /// Echoes a string to the console at the current scroll position.
 pub fn echo_line(s: String) -> io::Result<()> {
     execute!(
@@ -332,6 +695,26 @@ pub fn scroll_maybe(reserved_lines: u16) -> io::Result<u16> {
     }
 }
 
+/// Builds the summary line of a statement that returned no rows.
+///
+/// # Arguments
+/// * `affected_rows` - Number of rows the statement changed.
+/// * `last_insert_id` - Primary key of the row added by an `INSERT`.
+///
+/// # Returns
+/// A line like `Query OK, 1 row affected, id=5`.
+pub fn query_ok(affected_rows: u64, last_insert_id: Option<u32>) -> String {
+    let mut line = format!(
+        "Query OK, {} row{} affected",
+        affected_rows,
+        if affected_rows == 1 { "" } else { "s" }
+    );
+    if let Some(id) = last_insert_id {
+        line.push_str(&format!(", id={}", id));
+    }
+    line
+}
+
 /// Builds an ASCII table from headers and rows.
 ///
 /// # Arguments
@@ -404,3 +787,313 @@ pub fn build_table(headers: &[String], rows: &[Vec<String>]) -> String {
 
     result
 }
+
+/// Replaces NULL cells with the given text.
+fn display_nulls(rows: &[Vec<Option<String>>], null_display: &str) -> Vec<Vec<String>> {
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.clone().unwrap_or_else(|| null_display.to_string()))
+                .collect()
+        })
+        .collect()
+}
+
+/// Renders a result set as configured by the session settings.
+///
+/// # Arguments
+/// * `settings` - The settings selecting the output format and NULL display.
+/// * `headers` - Column headers.
+/// * `rows` - Data rows, with `None` for NULL.
+///
+/// # Returns
+/// The rendered result set as a `String`.
+pub fn render_result_set(
+    settings: &session::Settings,
+    headers: &[String],
+    rows: &[Vec<Option<String>>],
+) -> String {
+    let null_display = settings.null_display.as_str();
+    match settings.output {
+        session::OutputFormat::Table => build_table(headers, &display_nulls(rows, null_display)),
+        session::OutputFormat::Json => build_json(headers, rows),
+        session::OutputFormat::Tsv => escape_tsv(headers, rows, null_display),
+    }
+}
+
+/// Builds a JSON array with one object per row, keyed by column header.
+///
+/// # Arguments
+/// * `headers` - Column headers.
+/// * `rows` - Data rows, with `None` for NULL.
+///
+/// # Returns
+/// The rows as a JSON `String`, with NULLs rendered as `null`.
+pub fn build_json(headers: &[String], rows: &[Vec<Option<String>>]) -> String {
+    let quote = |s: &str| {
+        let mut quoted = String::from('"');
+        for c in s.chars() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                '\t' => quoted.push_str("\\t"),
+                c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    };
+
+    let objects: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            let fields: Vec<String> = headers
+                .iter()
+                .zip(row)
+                .map(|(header, cell)| {
+                    let value = cell.as_deref().map_or("null".to_string(), quote);
+                    format!("{}: {}", quote(header), value)
+                })
+                .collect();
+            format!("  {{{}}}", fields.join(", "))
+        })
+        .collect();
+    if objects.is_empty() {
+        return "[]\n".to_string();
+    }
+    format!("[\n{}\n]\n", objects.join(",\n"))
+}
+
+/// Builds the status lines printed below the welcome message.
+///
+/// The data directory is shown as an absolute path, since it is resolved relative to
+/// the working directory the REPL was started from.
+///
+/// # Arguments
+/// * `session` - The session the REPL runs.
+///
+/// # Returns
+/// One line each for the data directory, database, page size and autocommit mode.
+fn build_status_lines(session: &session::Session) -> String {
+    let data_dir =
+        std::path::absolute(&session.data_dir).unwrap_or_else(|_| session.data_dir.clone());
+    format!(
+        "Data directory: {}\nDatabase: {}\nPage size: {} bytes\nAutocommit: {}\n",
+        data_dir.display(),
+        session.database.name,
+        crate::storage::table::PAGE_SIZE,
+        if session.settings.autocommit {
+            "ON"
+        } else {
+            "OFF"
+        }
+    )
+}
+
+/// Builds tab-separated values from headers and rows, as printed in batch mode.
+///
+/// Tabs, newlines and backslashes in cells are escaped, and NULLs render as `\N`.
+///
+/// # Arguments
+/// * `headers` - Column headers.
+/// * `rows` - Data rows, with `None` for NULL.
+///
+/// # Returns
+/// A header line followed by one line per row.
+pub fn build_tsv(headers: &[String], rows: &[Vec<Option<String>>]) -> String {
+    escape_tsv(headers, rows, "\\N")
+}
+
+/// Builds tab-separated values like `build_tsv`, with NULLs rendered as `null_display`.
+fn escape_tsv(headers: &[String], rows: &[Vec<Option<String>>], null_display: &str) -> String {
+    let escape = |cell: &str| {
+        cell.replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+    };
+
+    let mut result = String::new();
+    result.push_str(
+        &headers
+            .iter()
+            .map(|h| escape(h))
+            .collect::<Vec<_>>()
+            .join("\t"),
+    );
+    result.push('\n');
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|cell| cell.as_deref().map_or(null_display.to_string(), escape))
+            .collect();
+        result.push_str(&cells.join("\t"));
+        result.push('\n');
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_input() {
+        let mut session = session::Session::open_test().unwrap();
+        let mut console = Console::new(&mut session, false);
+        assert!(!console.handle_input("").unwrap());
+        assert!(!console.handle_input("  \n\t").unwrap());
+        // A statement without a terminator still continues on the next line
+        assert!(console.handle_input("select 1").unwrap());
+        assert!(console.handle_input("select 1\n").unwrap());
+    }
+
+    #[test]
+    fn test_cancel_multi_line_statement() {
+        let mut session = session::Session::open_test().unwrap();
+        let mut console = Console::new(&mut session, false);
+        console.buffer.push_str("select *");
+        assert!(console.handle_read(prompt::ReadOutcome::Line).unwrap());
+        console.buffer.newline();
+        console.buffer.push_str("from users");
+        assert!(console.handle_read(prompt::ReadOutcome::Line).unwrap());
+
+        // Ctrl+C drops both lines and asks for a fresh primary prompt
+        console.buffer.newline();
+        assert!(!console.handle_read(prompt::ReadOutcome::Cancelled).unwrap());
+        assert_eq!(console.buffer.len_total(), 0);
+
+        console.buffer.push_str("select 2");
+        assert!(console.handle_read(prompt::ReadOutcome::Line).unwrap());
+        assert_eq!(console.buffer.build(), "select 2");
+    }
+
+    #[test]
+    fn test_status_lines() {
+        let mut session = session::Session::open_test().unwrap();
+        session.settings.autocommit = true;
+        let status = build_status_lines(&session);
+        let lines: Vec<&str> = status.lines().collect();
+        assert_eq!(
+            lines[0],
+            format!("Data directory: {}", session.data_dir.display())
+        );
+        assert!(session.data_dir.is_absolute());
+        assert_eq!(lines[1], format!("Database: {}", session.database.name));
+        assert_eq!(lines[2], "Page size: 4096 bytes");
+        assert_eq!(lines[3], "Autocommit: ON");
+
+        // The default data directory is relative to the working directory
+        session.data_dir = PathBuf::from(database::DATA_DIR);
+        let data_dir = std::env::current_dir().unwrap().join(database::DATA_DIR);
+        assert!(build_status_lines(&session)
+            .starts_with(&format!("Data directory: {}\n", data_dir.display())));
+    }
+
+    #[test]
+    fn test_edit_command() {
+        assert_eq!(strip_edit_command("\\e"), Some(""));
+        assert_eq!(strip_edit_command("select * \\e"), Some("select *"));
+        assert_eq!(strip_edit_command("select 'a\\e"), None);
+        assert_eq!(strip_edit_command("select 1;"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_buffer() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("editor.sh");
+        let seen = dir.path().join("seen.sql");
+        std::fs::write(
+            &script,
+            format!(
+                "cp \"$1\" '{}'\nprintf 'select id\\nfrom users;\\n' > \"$1\"\n",
+                seen.display()
+            ),
+        )
+        .unwrap();
+
+        let mut session = session::Session::open_test().unwrap();
+        let mut console = Console::new(&mut session, false);
+        console.buffer.push_str("select *");
+        let editor = format!("sh {}", script.display());
+        let text = console.edit_buffer(&editor, "select *").unwrap();
+        assert_eq!(text, "select id\nfrom users;\n");
+        assert_eq!(std::fs::read_to_string(&seen).unwrap(), "select *\n");
+        assert_eq!(console.buffer.line_count(), 2);
+        assert_eq!(console.buffer.build(), "select id from users;");
+
+        // A failing editor leaves the buffer alone
+        assert!(console.edit_buffer("false", "select 1").is_err());
+        assert_eq!(console.buffer.build(), "select id from users;");
+    }
+
+    #[test]
+    fn test_clear_command() {
+        for cmd in ["clear", "clear;", "\\c", "\\c;"] {
+            assert!(is_clear_command(cmd), "{}", cmd);
+            // Falling through to the SQL parser would be a syntax error
+            assert!(sql::parser::parse(cmd.to_string()).is_err(), "{}", cmd);
+        }
+        assert!(!is_clear_command("clear_all"));
+        assert!(!is_clear_command("select clear;"));
+    }
+
+    #[test]
+    fn test_query_ok() {
+        assert_eq!(query_ok(0, None), "Query OK, 0 rows affected");
+        assert_eq!(query_ok(1, Some(5)), "Query OK, 1 row affected, id=5");
+    }
+
+    #[test]
+    fn test_build_tsv() {
+        let headers = vec!["id".to_string(), "name".to_string()];
+        let rows = vec![
+            vec![Some("1".to_string()), Some("alice".to_string())],
+            vec![Some("2".to_string()), None],
+            vec![Some("3".to_string()), Some("a\tb\\c".to_string())],
+        ];
+        assert_eq!(
+            build_tsv(&headers, &rows),
+            "id\tname\n1\talice\n2\t\\N\n3\ta\\tb\\\\c\n"
+        );
+    }
+
+    #[test]
+    fn test_null_display() {
+        let headers = vec!["id".to_string(), "name".to_string()];
+        let rows = vec![vec![Some("1".to_string()), None]];
+        let mut settings = session::Settings::default();
+        assert!(render_result_set(&settings, &headers, &rows).contains("|1 |NULL|"));
+
+        settings.set("null_display", "(none)").unwrap();
+        assert!(render_result_set(&settings, &headers, &rows).contains("|1 |(none)|"));
+        settings.set("output", "tsv").unwrap();
+        assert_eq!(
+            render_result_set(&settings, &headers, &rows),
+            "id\tname\n1\t(none)\n"
+        );
+    }
+
+    #[test]
+    fn test_build_json() {
+        let headers = vec!["id".to_string(), "name".to_string()];
+        let rows = vec![
+            vec![Some("1".to_string()), Some("say \"hi\"".to_string())],
+            vec![Some("2".to_string()), None],
+        ];
+        assert_eq!(
+            render_result_set(
+                &session::Settings {
+                    output: session::OutputFormat::Json,
+                    ..Default::default()
+                },
+                &headers,
+                &rows
+            ),
+            "[\n  {\"id\": \"1\", \"name\": \"say \\\"hi\\\"\"},\n  {\"id\": \"2\", \"name\": null}\n]\n"
+        );
+        assert_eq!(build_json(&headers, &[]), "[]\n");
+    }
+}
@@ -78,6 +78,75 @@ pub fn ensure_history_file(path: &PathBuf) -> io::Result<()> {
     Ok(())
 }
 
+/// Incremental reverse search state over the command history (Ctrl+R).
+#[derive(Debug, Default)]
+pub struct HistorySearch {
+    term: String,
+    position: Option<usize>,
+}
+
+impl HistorySearch {
+    /// Creates a new search with an empty term and no match.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a character to the search term and refreshes the match.
+    ///
+    /// # Arguments
+    /// * `c` - The character typed by the user
+    /// * `history` - The history lines, oldest first
+    pub fn push(&mut self, c: char, history: &[String]) {
+        self.term.push(c);
+        // The current match may still contain the longer term
+        let from = self.position.map_or(history.len(), |i| i + 1);
+        self.position = self.find(history, from);
+    }
+
+    /// Removes the last character of the search term and restarts the match from the newest line.
+    ///
+    /// # Arguments
+    /// * `history` - The history lines, oldest first
+    pub fn pop(&mut self, history: &[String]) {
+        self.term.pop();
+        self.position = self.find(history, history.len());
+    }
+
+    /// Cycles to the next older line containing the search term (repeated Ctrl+R).
+    ///
+    /// # Arguments
+    /// * `history` - The history lines, oldest first
+    pub fn next(&mut self, history: &[String]) {
+        let from = self.position.unwrap_or(history.len());
+        if let Some(found) = self.find(history, from) {
+            self.position = Some(found);
+        }
+    }
+
+    /// Returns the currently matched history line, if any.
+    ///
+    /// # Arguments
+    /// * `history` - The history lines, oldest first
+    pub fn matched<'a>(&self, history: &'a [String]) -> Option<&'a str> {
+        self.position.map(|i| history[i].as_str())
+    }
+
+    /// Returns the search prompt label shown in place of the normal prompt.
+    pub fn label(&self) -> String {
+        format!("(reverse-i-search)`{}': ", self.term)
+    }
+
+    /// Finds the newest line before `from` that contains the search term.
+    fn find(&self, history: &[String], from: usize) -> Option<usize> {
+        if self.term.is_empty() {
+            return None;
+        }
+        history[..from.min(history.len())]
+            .iter()
+            .rposition(|line| line.contains(&self.term))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -111,4 +180,42 @@ mod tests {
         let history = load_history(&path);
         assert!(history.is_empty());
     }
+
+    #[test]
+    fn test_history_search() {
+        let history: Vec<String> = vec![
+            "select * from t;",
+            "insert into t values (1);",
+            "select id from t;",
+            "drop table t;",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+        let mut search = HistorySearch::new();
+        assert_eq!(search.matched(&history), None);
+        assert_eq!(search.label(), "(reverse-i-search)`': ");
+
+        search.push('d', &history);
+        assert_eq!(search.matched(&history), Some("drop table t;"));
+        search.pop(&history);
+        search.push('s', &history);
+        assert_eq!(search.matched(&history), Some("select id from t;"));
+        search.push('e', &history);
+        search.push('l', &history);
+        assert_eq!(search.matched(&history), Some("select id from t;"));
+        assert_eq!(search.label(), "(reverse-i-search)`sel': ");
+
+        // Repeated Ctrl+R cycles to older matches and stops at the oldest
+        search.next(&history);
+        assert_eq!(search.matched(&history), Some("select * from t;"));
+        search.next(&history);
+        assert_eq!(search.matched(&history), Some("select * from t;"));
+
+        search.push('x', &history);
+        assert_eq!(search.matched(&history), None);
+        search.pop(&history);
+        assert_eq!(search.matched(&history), Some("select id from t;"));
+    }
 }
@@ -1,3 +1,4 @@
+use crate::{database, session};
 use crossterm::{
     cursor,
     event::{self, KeyCode, KeyEvent, KeyModifiers},
@@ -7,6 +8,7 @@ use crossterm::{
 };
 use std::io::{self, Write};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use tracing::warn;
 
 const NAME: &str = env!("CARGO_PKG_NAME");
@@ -26,28 +28,49 @@ const COMPLETIONS: &[&str] = &[
     "print_btree",
 ];
 
+/// How a call to `Prompt::readline` ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReadOutcome {
+    /// The line was completed with Enter.
+    Line,
+    /// The statement being typed was discarded with Ctrl+C.
+    Cancelled,
+}
+
 /// A terminal prompt structure for handling user input and command history.
 #[derive(Debug)]
 pub struct Prompt {
-    pub x: u16,        // Cursor x position
-    pub y: u16,        // Cursor y position
+    pub x: usize,      // Cursor x position
+    pub y: usize,      // Cursor y position
     pub prompt_y: u16, // Prompt starting y position
+    /// How long a read waits for input before checking for a shutdown or an idle flush.
+    pub poll_interval: Duration,
     history: Vec<String>,
-    history_path: PathBuf,
+    /// File the history is loaded from and saved to, `None` to keep it in memory.
+    history_path: Option<PathBuf>,
     history_index: usize,
 }
 
 impl Prompt {
     /// Creates a new Prompt instance with history loaded from the user's home directory.
-    pub fn new() -> Self {
-        let history_path = super::history::get_home_file(format!(".{}_history", NAME));
-        let history = super::history::load_history(&history_path);
+    ///
+    /// # Arguments
+    /// * `save_history` - Whether to load and save the history file; without it, the
+    ///   history only lasts for the session.
+    pub fn new(save_history: bool) -> Self {
+        let history_path =
+            save_history.then(|| super::history::get_home_file(format!(".{}_history", NAME)));
+        let history = history_path
+            .as_ref()
+            .map(super::history::load_history)
+            .unwrap_or_default();
         let history_index = history.len();
 
         Self {
             x: 0,
             y: 0,
             prompt_y: 0,
+            poll_interval: POLL_INTERVAL,
             history,
             history_path,
             history_index: history_index,
@@ -62,7 +85,10 @@ impl Prompt {
         if !line.trim().is_empty() {
             self.history.push(line.to_string());
             self.history_index = self.history.len();
-            if let Err(e) = super::history::append_history(line, &self.history_path) {
+            let Some(path) = &self.history_path else {
+                return;
+            };
+            if let Err(e) = super::history::append_history(line, path) {
                 warn!("Failed to save history: {}", e);
             }
         }
@@ -134,10 +160,10 @@ impl Prompt {
     /// * `input` - The current input string to check for wrapping
     fn scroll_prompt_if_needed(&mut self, input: &str) -> io::Result<()> {
         let (width, height) = terminal::size()?;
-        let wraps = (NAME.len() as u16 + 1 + input.len() as u16) / width + 1;
+        let wraps = to_u16(wrapped_rows(NAME.len() + 1 + input.len(), width));
 
-        if self.prompt_y + wraps >= height {
-            let to_scroll = (self.prompt_y + wraps - height + 1).max(1);
+        if self.prompt_y.saturating_add(wraps) >= height {
+            let to_scroll = (self.prompt_y.saturating_add(wraps) - height + 1).max(1);
             self.prompt_y = self.prompt_y.saturating_sub(to_scroll);
             execute!(
                 io::stdout(),
@@ -153,16 +179,18 @@ impl Prompt {
     /// # Arguments
     /// * `buffer` - The buffer to store the input
     /// * `status` - The status bar to update with cursor position
+    /// * `session` - The session used to complete database and table names
     ///
     /// # Returns
-    /// `io::Result<()>` indicating success or an error (e.g., interrupt)
+    /// How the line ended, or an error (e.g., interrupt on an empty prompt)
     pub fn readline(
         &mut self,
         buffer: &mut super::buffer::Buffer,
         status: &mut super::status::StatusBar,
-    ) -> io::Result<()> {
+        session: &session::Session,
+    ) -> io::Result<ReadOutcome> {
         loop {
-            match event::read()? {
+            match self.read_event(session)? {
                 event::Event::Key(KeyEvent {
                     code, modifiers, ..
                 }) => match (code, modifiers) {
@@ -186,13 +214,23 @@ impl Prompt {
                     (KeyCode::Char('f'), KeyModifiers::ALT) => {
                         self.handle_word_right(&buffer)?;
                     }
+                    (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                        self.handle_reverse_search(buffer, session)?;
+                    }
+                    (KeyCode::Char('w'), KeyModifiers::CONTROL) => {
+                        self.handle_delete_word(buffer)?;
+                    }
+                    (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
+                        self.handle_kill_line(buffer)?;
+                    }
                     (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
                         self.handle_interrupt(buffer)?;
+                        return Ok(ReadOutcome::Cancelled);
                     }
                     (KeyCode::Left, _) if self.x > 0 => {
                         self.handle_right()?;
                     }
-                    (KeyCode::Right, _) if self.x < buffer.len() as u16 => {
+                    (KeyCode::Right, _) if self.x < buffer.len() => {
                         self.handle_left()?;
                     }
                     (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
@@ -203,7 +241,12 @@ impl Prompt {
                         self.handle_clear_screen(buffer)?;
                     }
                     (KeyCode::Tab, _) => {
-                        self.handle_tab_completion(buffer, COMPLETIONS)?;
+                        self.handle_tab_completion(buffer, session)?;
+                    }
+                    (KeyCode::Char(_), _)
+                        if exceeds_limit(buffer, 1, session.settings.max_input_len) =>
+                    {
+                        self.reject_input(buffer, session.settings.max_input_len)?;
                     }
                     (KeyCode::Char(c), _) => {
                         self.handle_char_input(buffer, c)?;
@@ -215,7 +258,7 @@ impl Prompt {
             status.update(None, Some(self.x), Some(self.y), Some(buffer.len_total()));
             status.draw()?;
         }
-        Ok(())
+        Ok(ReadOutcome::Line)
     }
 
     /// Handles history navigation (up/down arrow keys).
@@ -244,18 +287,88 @@ impl Prompt {
 
         write!(io::stdout(), "{}", input)?;
         io::stdout().flush()?;
-        self.x = buffer.len() as u16;
+        self.x = buffer.len();
+        Ok(())
+    }
+
+    /// Handles incremental reverse history search (Ctrl+R).
+    ///
+    /// Enter puts the matched line into the buffer, Esc restores the original input.
+    ///
+    /// # Arguments
+    /// * `buffer` - The input buffer to replace with the selected line
+    /// * `session` - The session flushed while the search waits for input
+    fn handle_reverse_search(
+        &mut self,
+        buffer: &mut super::buffer::Buffer,
+        session: &session::Session,
+    ) -> io::Result<()> {
+        let mut search = super::history::HistorySearch::new();
+        loop {
+            let matched = search.matched(&self.history).unwrap_or_default();
+            execute!(
+                io::stdout(),
+                cursor::MoveTo(0, self.prompt_y),
+                terminal::Clear(terminal::ClearType::FromCursorDown),
+                style::Print(search.label()),
+                style::Print(matched)
+            )?;
+
+            if let event::Event::Key(KeyEvent {
+                code, modifiers, ..
+            }) = self.read_event(session)?
+            {
+                match (code, modifiers) {
+                    (KeyCode::Enter, _) => {
+                        if let Some(line) = search.matched(&self.history) {
+                            buffer.remove_range(0, buffer.len());
+                            buffer.push_str(line);
+                        }
+                        break;
+                    }
+                    (KeyCode::Esc, _) => break,
+                    (KeyCode::Char('r'), KeyModifiers::CONTROL) => search.next(&self.history),
+                    (KeyCode::Backspace, _) => search.pop(&self.history),
+                    (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                        search.push(c, &self.history)
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let input = buffer.current();
+        self.scroll_prompt_if_needed(&input)?;
+        self.render_prompt(self.prompt_text())?;
+        write!(io::stdout(), "{}", input)?;
+        io::stdout().flush()?;
+        self.x = buffer.len();
         Ok(())
     }
 
+    /// Returns the prompt marker for the current line of a possibly multi-line input.
+    fn prompt_text(&self) -> String {
+        if self.y == 0 {
+            format!("{}> ", NAME)
+        } else {
+            format!("{}-> ", " ".repeat(NAME.len() - 1))
+        }
+    }
+
     /// Handles interrupt signals (Ctrl+C or Esc).
     ///
+    /// Any typed input, including earlier lines of a multi-line statement, is
+    /// discarded and marked with `^C`; the caller then starts a fresh primary prompt.
+    /// On an empty prompt the REPL is exited instead.
+    ///
     /// # Arguments
     /// * `buffer` - The input buffer to clear if non-empty
     fn handle_interrupt(&mut self, buffer: &mut super::buffer::Buffer) -> io::Result<()> {
-        if buffer.len() > 0 {
+        if buffer.len_total() > 0 {
             buffer.clear();
-            self.clear_prompt()?;
+            execute!(io::stdout(), style::Print("^C"), style::Print("\r\n"))?;
+            self.x = 0;
+            self.y = 0;
         } else {
             super::console::echo_line("\nBye".into())?;
             return Err(io::Error::new(io::ErrorKind::Interrupted, "Ctrl-C"));
@@ -268,53 +381,99 @@ impl Prompt {
     /// # Arguments
     /// * `buffer` - The input buffer to redraw
     fn handle_clear_screen(&mut self, buffer: &mut super::buffer::Buffer) -> io::Result<()> {
-        execute!(
-            io::stdout(),
-            terminal::Clear(terminal::ClearType::All),
-            cursor::MoveTo(0, 0)
-        )?;
+        clear_screen()?;
         self.start_prompt()?;
         write!(io::stdout(), "{}", buffer.current())?;
         io::stdout().flush()?;
-        self.x = buffer.len() as u16;
+        self.x = buffer.len();
         Ok(())
     }
 
-    /// Handles tab completion for commands.
+    /// Handles tab completion for keywords, database, table and column names.
+    ///
+    /// A unique match or a longer shared prefix is inserted; otherwise the candidates
+    /// are listed below the prompt.
     ///
     /// # Arguments
     /// * `buffer` - The input buffer to complete
-    /// * `completions` - List of possible command completions
+    /// * `session` - The session providing database, table and column names
     fn handle_tab_completion(
         &mut self,
         buffer: &mut super::buffer::Buffer,
-        completions: &[&str],
+        session: &session::Session,
     ) -> io::Result<()> {
-        if let Some(last_word) = buffer.build().split_whitespace().last() {
-            let matches: Vec<_> = completions
-                .iter()
-                .filter(|c| c.starts_with(last_word))
-                .collect();
-            if matches.len() == 1 {
-                let completed = &matches[0][last_word.len()..];
-                buffer.push_str(completed);
-                self.x += completed.len() as u16;
-                write!(io::stdout(), "{}", completed)?;
-                io::stdout().flush()?;
-            }
+        let input = buffer.build();
+        let (_, prefix) = split_prefix(&input);
+        let matches = completions(&input, session);
+        let common = common_prefix(&matches);
+
+        if common.len() > prefix.len() {
+            let completed = &common[prefix.len()..];
+            buffer.push_str(completed);
+            self.x += completed.len();
+            write!(io::stdout(), "{}", completed)?;
+            io::stdout().flush()?;
+        } else if matches.len() > 1 {
+            self.print_below(buffer, &matches.join("  "))?;
         }
         Ok(())
     }
 
+    /// Rejects input that would exceed the `max_input_len` setting.
+    ///
+    /// The rest of a pending paste is discarded and a message is shown once under the
+    /// prompt, leaving the buffer as it was.
+    ///
+    /// # Arguments
+    /// * `buffer` - The input buffer to redraw
+    /// * `max_input_len` - The exceeded limit, shown in the message
+    fn reject_input(
+        &mut self,
+        buffer: &super::buffer::Buffer,
+        max_input_len: usize,
+    ) -> io::Result<()> {
+        while event::poll(Duration::ZERO)? {
+            event::read()?;
+        }
+        self.print_below(
+            buffer,
+            &format!(
+                "Input is limited to {} characters; the rest was discarded",
+                max_input_len
+            ),
+        )
+    }
+
+    /// Prints a line of text under the prompt and redraws the prompt after it.
+    ///
+    /// # Arguments
+    /// * `buffer` - The input buffer to redraw
+    /// * `text` - The text to print
+    fn print_below(&mut self, buffer: &super::buffer::Buffer, text: &str) -> io::Result<()> {
+        execute!(
+            io::stdout(),
+            style::Print("\r\n"),
+            terminal::Clear(terminal::ClearType::FromCursorDown),
+            style::Print(text),
+            style::Print("\r\n")
+        )?;
+        self.prompt_y = super::console::scroll_maybe(1)?;
+        self.render_prompt(self.prompt_text())?;
+        write!(io::stdout(), "{}", buffer.current())?;
+        io::stdout().flush()?;
+        self.x = buffer.len();
+        Ok(())
+    }
+
     /// Handles character input (typing).
     ///
     /// # Arguments
     /// * `buffer` - The input buffer to modify
     /// * `c` - The character to insert
     fn handle_char_input(&mut self, buffer: &mut super::buffer::Buffer, c: char) -> io::Result<()> {
-        if self.x < buffer.len() as u16 {
-            buffer.insert(self.x as usize, c);
-            self.redraw_from_cursor(buffer)?;
+        if self.x < buffer.len() {
+            buffer.insert(self.x, c);
+            self.redraw_from_cursor(buffer, 1)?;
         } else {
             buffer.push(c);
             write!(io::stdout(), "{}", c)?;
@@ -330,7 +489,7 @@ impl Prompt {
     /// * `buffer` - The input buffer to modify
     fn handle_backspace(&mut self, buffer: &mut super::buffer::Buffer) -> io::Result<()> {
         if self.x > 0 && buffer.len() > 0 {
-            buffer.remove(self.x as usize - 1);
+            buffer.remove(self.x - 1);
             self.x -= 1;
 
             let (width, _) = terminal::size()?;
@@ -352,9 +511,9 @@ impl Prompt {
                 )?;
             }
 
-            write!(io::stdout(), "{}", &buffer.current()[self.x as usize..])?;
+            write!(io::stdout(), "{}", &buffer.current()[self.x..])?;
             io::stdout().flush()?;
-            execute!(io::stdout(), cursor::MoveTo(x - 1, y))?;
+            execute!(io::stdout(), cursor::MoveTo(x.saturating_sub(1), y))?;
         }
         Ok(())
     }
@@ -362,49 +521,59 @@ impl Prompt {
     /// Handles Option+Left (Alt+B) word navigation
     fn handle_word_left(&mut self, buffer: &super::buffer::Buffer) -> io::Result<()> {
         if self.x > 0 {
-            let current = buffer.current();
-            let chars: Vec<char> = current.chars().collect();
-            let mut new_x = self.x as usize;
+            let new_x = word_start(&buffer.current(), self.x);
             let (x, y) = cursor::position()?;
             let (width, _) = terminal::size()?;
-            let prompt_offset = (NAME.len() + 2) as u16;
 
-            // Skip trailing delimiters
-            while new_x > 0
-                && (chars[new_x - 1].is_whitespace()
-                    || chars[new_x - 1] == '('
-                    || chars[new_x - 1] == ')')
-            {
-                new_x -= 1;
-            }
-            // Find start of previous word
-            while new_x > 0
-                && !(chars[new_x - 1].is_whitespace()
-                    || chars[new_x - 1] == '('
-                    || chars[new_x - 1] == ')')
-            {
-                new_x -= 1;
-            }
+            let moves = self.x - new_x;
+            self.x = new_x;
 
-            let moves = self.x - new_x as u16;
-            self.x = new_x as u16;
+            let new_col = column_of(NAME.len() + 2 + self.x, width);
 
-            let abs_pos = prompt_offset + self.x;
-            let new_col = abs_pos % width;
-
-            if moves > x {
-                let lines_up = (moves - x + width - 1) / width;
+            if moves > x as usize {
+                let lines_up = to_u16(wrapped_rows(moves - x as usize - 1, width));
                 execute!(
                     io::stdout(),
                     cursor::MoveTo(new_col, y.saturating_sub(lines_up))
                 )?;
             } else {
-                execute!(io::stdout(), cursor::MoveLeft(moves))?;
+                execute!(io::stdout(), cursor::MoveLeft(to_u16(moves)))?;
             }
         }
         Ok(())
     }
 
+    /// Handles word deletion (Ctrl+W), removing the word left of the cursor.
+    ///
+    /// # Arguments
+    /// * `buffer` - The input buffer to modify
+    fn handle_delete_word(&mut self, buffer: &mut super::buffer::Buffer) -> io::Result<()> {
+        let end = self.x;
+        self.handle_word_left(buffer)?;
+        if self.x < end {
+            buffer.remove_range(self.x, end);
+            self.redraw_from_cursor(buffer, 0)?;
+        }
+        Ok(())
+    }
+
+    /// Handles line kill (Ctrl+U), removing everything from the line start to the cursor.
+    ///
+    /// # Arguments
+    /// * `buffer` - The input buffer to modify
+    fn handle_kill_line(&mut self, buffer: &mut super::buffer::Buffer) -> io::Result<()> {
+        if self.x > 0 {
+            buffer.remove_range(0, self.x);
+            self.x = 0;
+            execute!(
+                io::stdout(),
+                cursor::MoveTo((NAME.len() + 2) as u16, self.prompt_y)
+            )?;
+            self.redraw_from_cursor(buffer, 0)?;
+        }
+        Ok(())
+    }
+
     /// Handles right navigation.
     fn handle_right(&mut self) -> io::Result<()> {
         let (x, _) = cursor::position()?;
@@ -434,14 +603,13 @@ impl Prompt {
     }
     /// Handles Option+Right (Alt+F) word navigation
     fn handle_word_right(&mut self, buffer: &super::buffer::Buffer) -> io::Result<()> {
-        if self.x < buffer.len() as u16 {
+        if self.x < buffer.len() {
             let current = buffer.current();
             let chars: Vec<char> = current.chars().collect();
             let len = chars.len();
-            let mut new_x = self.x as usize;
+            let mut new_x = self.x;
             let (x, y) = cursor::position()?;
             let (width, _) = terminal::size()?;
-            let prompt_offset = (NAME.len() + 2) as u16;
 
             // Skip current word
             while new_x < len
@@ -456,18 +624,20 @@ impl Prompt {
                 new_x += 1;
             }
 
-            let moves = (new_x as u16) - self.x;
-            self.x = new_x as u16;
+            let moves = new_x - self.x;
+            self.x = new_x;
 
-            let abs_pos = prompt_offset + self.x;
-            let new_col = abs_pos % width;
+            let new_col = column_of(NAME.len() + 2 + self.x, width);
 
-            if x + moves >= width {
-                let lines_down = (x + moves) / width;
-                execute!(io::stdout(), cursor::MoveTo(new_col, y + lines_down))?;
+            if x as usize + moves >= width as usize {
+                let lines_down = wrapped_rows(x as usize + moves, width) - 1;
+                execute!(
+                    io::stdout(),
+                    cursor::MoveTo(new_col, y.saturating_add(to_u16(lines_down)))
+                )?;
                 self.y = self.y.saturating_add(lines_down);
             } else {
-                execute!(io::stdout(), cursor::MoveRight(moves))?;
+                execute!(io::stdout(), cursor::MoveRight(to_u16(moves)))?;
             }
         }
         Ok(())
@@ -477,16 +647,408 @@ impl Prompt {
     ///
     /// # Arguments
     /// * `buffer` - The input buffer to redraw
-    fn redraw_from_cursor(&mut self, buffer: &mut super::buffer::Buffer) -> io::Result<()> {
+    /// * `advance` - Number of columns to move the cursor right after redrawing
+    fn redraw_from_cursor(
+        &mut self,
+        buffer: &mut super::buffer::Buffer,
+        advance: u16,
+    ) -> io::Result<()> {
         let (x, y) = cursor::position()?;
         execute!(
             io::stdout(),
             cursor::MoveToColumn(x),
             terminal::Clear(terminal::ClearType::FromCursorDown)
         )?;
-        write!(io::stdout(), "{}", &buffer.current()[self.x as usize..])?;
+        write!(io::stdout(), "{}", &buffer.current()[self.x..])?;
         io::stdout().flush()?;
-        execute!(io::stdout(), cursor::MoveTo(x + 1, y))?;
+        execute!(io::stdout(), cursor::MoveTo(x.saturating_add(advance), y))?;
         Ok(())
     }
+
+    /// Waits for the next terminal event.
+    ///
+    /// Unsaved changes of the session are flushed once the wait outlasts the
+    /// `idle_flush` setting, so they reach disk during natural pauses.
+    ///
+    /// # Arguments
+    /// * `session` - The session to flush while idle
+    ///
+    /// # Returns
+    /// The event, or an `Interrupted` error once a signal requested a shutdown
+    fn read_event(&self, session: &session::Session) -> io::Result<event::Event> {
+        let idle_since = Instant::now();
+        loop {
+            crate::signals::check_shutdown()?;
+            if event::poll(self.poll_interval)? {
+                return event::read();
+            }
+            flush_if_idle(session, idle_since.elapsed());
+        }
+    }
+}
+
+/// Default time a read waits for input before checking for a shutdown or an idle flush.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Flushes the session database once input has been idle for `idle_flush` seconds.
+///
+/// # Arguments
+/// * `session` - The session whose unsaved changes are flushed
+/// * `idle` - How long the prompt has been waiting for input
+///
+/// # Returns
+/// Whether the database was flushed
+fn flush_if_idle(session: &session::Session, idle: Duration) -> bool {
+    let threshold = session.settings.idle_flush;
+    if threshold == 0 || idle < Duration::from_secs(threshold) || !session.database.is_dirty() {
+        return false;
+    }
+    match session.database.flush(session.lock_timeout) {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Failed to flush idle database: {}", e);
+            false
+        }
+    }
+}
+
+/// Clears the whole terminal and moves the cursor to the top left corner.
+pub fn clear_screen() -> io::Result<()> {
+    execute!(
+        io::stdout(),
+        terminal::Clear(terminal::ClearType::All),
+        cursor::MoveTo(0, 0)
+    )
+}
+
+/// Converts a length to a terminal coordinate, saturating at `u16::MAX`.
+fn to_u16(n: usize) -> u16 {
+    u16::try_from(n).unwrap_or(u16::MAX)
+}
+
+/// Returns the number of terminal rows taken by `len` characters.
+///
+/// # Arguments
+/// * `len` - The number of characters, including the prompt marker
+/// * `width` - The terminal width
+fn wrapped_rows(len: usize, width: u16) -> usize {
+    len / (width.max(1) as usize) + 1
+}
+
+/// Returns the terminal column of the character at offset `pos` of the wrapped input.
+fn column_of(pos: usize, width: u16) -> u16 {
+    to_u16(pos % (width.max(1) as usize))
+}
+
+/// Checks whether adding `extra` characters would make the input longer than `max`,
+/// where a `max` of `0` is no limit.
+fn exceeds_limit(buffer: &super::buffer::Buffer, extra: usize, max: usize) -> bool {
+    max > 0 && buffer.len_total().saturating_add(extra) > max
+}
+
+/// Keywords followed by a table name.
+const TABLE_KEYWORDS: &[&str] = &["from", "into", "join", "update", "table", "describe"];
+
+/// Keywords that start a clause; the last one seen decides what is completed.
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "select", "where", "and", "or", "set", "by", "on", "having", "from", "into", "join", "update",
+    "table", "describe", "values",
+];
+
+/// Clauses in which column names are completed.
+const COLUMN_KEYWORDS: &[&str] = &["select", "where", "and", "or", "set", "by", "on", "having"];
+
+/// Checks whether a character separates completion words.
+fn is_completion_delimiter(c: char) -> bool {
+    c.is_whitespace() || c == '(' || c == ')' || c == ','
+}
+
+/// Splits the input into the text before the word being completed and that word.
+fn split_prefix(input: &str) -> (&str, &str) {
+    let start = input.rfind(is_completion_delimiter).map_or(0, |i| i + 1);
+    input.split_at(start)
+}
+
+/// Returns the completion candidates for the last word of the input.
+///
+/// The preceding words decide what is completed: database names after `use`, table
+/// names after `from`, `into`, `join`, `update`, `table` and `describe`, column names
+/// in `select`, `where` and similar clauses and in an `insert into <table> (` column
+/// list, and keywords otherwise.
+///
+/// # Arguments
+/// * `input` - The input typed so far
+/// * `session` - The session providing database, table and column names
+///
+/// # Returns
+/// The sorted candidates starting with the last word
+fn completions(input: &str, session: &session::Session) -> Vec<String> {
+    let (head, prefix) = split_prefix(input);
+    let words: Vec<String> = head
+        .split(is_completion_delimiter)
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let clause = words
+        .iter()
+        .rev()
+        .find(|w| CLAUSE_KEYWORDS.contains(&w.as_str()))
+        .map(String::as_str);
+    let in_parens = head.matches('(').count() > head.matches(')').count();
+    let keywords = || COMPLETIONS.iter().map(|c| c.to_string());
+
+    let mut candidates: Vec<String> = match words.last().map(String::as_str) {
+        Some("use" | "\\u") if words.len() == 1 => {
+            database::show_databases(&session.data_dir).unwrap_or_default()
+        }
+        Some(w) if TABLE_KEYWORDS.contains(&w) => session.database.table_names(),
+        _ if clause == Some("into") && in_parens => column_names(&words, session),
+        _ if clause.is_some_and(|c| COLUMN_KEYWORDS.contains(&c)) => {
+            let mut names = column_names(&words, session);
+            if !prefix.is_empty() {
+                names.extend(keywords());
+            }
+            names
+        }
+        _ if prefix.is_empty() => Vec::new(),
+        _ => keywords().collect(),
+    };
+    candidates.retain(|c| c.starts_with(prefix));
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+/// Returns the column names of the tables named in the input, or of every table when
+/// none is named yet.
+///
+/// Completion must not stall the prompt, so tables locked by a running statement are
+/// skipped.
+fn column_names(words: &[String], session: &session::Session) -> Vec<String> {
+    let all_tables = session.database.table_names();
+    let mut tables: Vec<&String> = words
+        .windows(2)
+        .filter(|w| TABLE_KEYWORDS.contains(&w[0].as_str()))
+        .filter_map(|w| all_tables.iter().find(|t| t.eq_ignore_ascii_case(&w[1])))
+        .collect();
+    if tables.is_empty() {
+        tables = all_tables.iter().collect();
+    }
+
+    tables
+        .into_iter()
+        .filter_map(|name| {
+            let table = session.database.find_table(name).ok()?;
+            let table = table.try_lock().ok()?;
+            Some(
+                table
+                    .schema
+                    .columns()
+                    .iter()
+                    .map(|c| c.name.clone())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .flatten()
+        .collect()
+}
+
+/// Returns the longest prefix shared by all candidates.
+fn common_prefix(candidates: &[String]) -> &str {
+    let Some(first) = candidates.first() else {
+        return "";
+    };
+    let len = candidates.iter().skip(1).fold(first.len(), |len, c| {
+        first
+            .bytes()
+            .zip(c.bytes())
+            .take(len)
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+    &first[..len]
+}
+
+/// Checks whether a character separates words for navigation and deletion.
+fn is_word_delimiter(c: char) -> bool {
+    c.is_whitespace() || c == '(' || c == ')'
+}
+
+/// Finds the start of the word left of the given cursor position.
+///
+/// # Arguments
+/// * `line` - The current input line
+/// * `x` - The cursor position within the line
+///
+/// # Returns
+/// The index where the previous word begins
+fn word_start(line: &str, x: usize) -> usize {
+    let chars: Vec<char> = line.chars().collect();
+    let mut new_x = x.min(chars.len());
+
+    // Skip trailing delimiters
+    while new_x > 0 && is_word_delimiter(chars[new_x - 1]) {
+        new_x -= 1;
+    }
+    // Find start of previous word
+    while new_x > 0 && !is_word_delimiter(chars[new_x - 1]) {
+        new_x -= 1;
+    }
+    new_x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repl::buffer::Buffer;
+
+    fn buffer_with(line: &str) -> Buffer {
+        let mut buffer = Buffer::new();
+        buffer.push_str(line);
+        buffer
+    }
+
+    #[test]
+    fn test_idle_flush() {
+        let mut session = session::Session::open_test().unwrap();
+        session
+            .execute_script(
+                "create table users (id INT PRIMARY KEY, name VARCHAR(32));
+                 insert into users (id, name) values (1, 'alice');"
+                    .as_bytes(),
+            )
+            .unwrap();
+        let on_disk = |session: &session::Session| {
+            let db = database::Database::get_in(&session.data_dir, &session.database.name);
+            let table = db.unwrap().find_table(&"users".into()).unwrap().clone();
+            let n = table.lock().unwrap().pager.table_n_recs().unwrap();
+            n
+        };
+        assert!(session.database.is_dirty());
+
+        // Disabled, then idle for less than the threshold
+        session.settings.idle_flush = 0;
+        assert!(!flush_if_idle(&session, Duration::from_secs(60)));
+        session.settings.idle_flush = 5;
+        assert!(!flush_if_idle(&session, Duration::from_secs(4)));
+        assert!(session.database.is_dirty());
+
+        assert!(flush_if_idle(&session, Duration::from_secs(5)));
+        assert!(!session.database.is_dirty());
+        assert_eq!(on_disk(&session), 1);
+        // Nothing left to flush on the next poll
+        assert!(!flush_if_idle(&session, Duration::from_secs(6)));
+    }
+
+    #[test]
+    fn test_delete_word() {
+        let mut buffer = buffer_with("select * from users  ");
+        let end = buffer.len();
+        let start = word_start(&buffer.current(), end);
+        assert_eq!(start, 14);
+        buffer.remove_range(start, end);
+        assert_eq!(buffer.current(), "select * from ");
+
+        let start = word_start(&buffer.current(), buffer.len());
+        buffer.remove_range(start, buffer.len());
+        assert_eq!(buffer.current(), "select * ");
+
+        let mut buffer = buffer_with("count(id)");
+        let start = word_start(&buffer.current(), 8);
+        buffer.remove_range(start, 8);
+        assert_eq!(buffer.current(), "count()");
+    }
+
+    #[test]
+    fn test_kill_line() {
+        let mut buffer = buffer_with("select * from users");
+        buffer.remove_range(0, 9);
+        assert_eq!(buffer.current(), "from users");
+        buffer.remove_range(0, buffer.len());
+        assert_eq!(buffer.current(), "");
+        assert_eq!(word_start(&buffer.current(), 0), 0);
+    }
+
+    #[test]
+    fn test_long_input() {
+        // Lengths past u16::MAX used to wrap around in the cursor math
+        let len = 100_000;
+        let buffer = buffer_with(&"x".repeat(len));
+        let width = 80;
+        assert_eq!(wrapped_rows(NAME.len() + 1 + buffer.len(), width), 1251);
+        assert_eq!(column_of(NAME.len() + 2 + buffer.len(), width), 10);
+        assert_eq!(column_of(len, 0), 0);
+        assert_eq!(to_u16(len), u16::MAX);
+        assert_eq!(word_start(&buffer.current(), len), 0);
+
+        let max_input_len = session::Settings::default().max_input_len;
+        assert!(!exceeds_limit(&buffer, 1, max_input_len));
+        assert!(!exceeds_limit(&buffer, 0, len));
+        assert!(exceeds_limit(&buffer, 1, len));
+        assert!(exceeds_limit(&buffer, usize::MAX, max_input_len));
+        assert!(!exceeds_limit(&buffer, usize::MAX, 0));
+    }
+
+    #[test]
+    fn test_complete_database_and_table_names() {
+        let mut session = session::Session::open_test().unwrap();
+        session
+            .database
+            .create_table(
+                &"users".into(),
+                crate::storage::table::SCHEMA.as_ref().clone(),
+            )
+            .unwrap();
+        let name = session.database.name.clone();
+
+        assert_eq!(
+            completions(&format!("use {}", &name[..13]), &session),
+            vec![name]
+        );
+        assert_eq!(completions("select * from us", &session), vec!["users"]);
+        assert_eq!(completions("insert into ", &session), vec!["users"]);
+        assert_eq!(completions("sel", &session), vec!["select"]);
+    }
+
+    #[test]
+    fn test_complete_column_names() {
+        let mut session = session::Session::open_test().unwrap();
+        for name in ["users", "orders"] {
+            session
+                .database
+                .create_table(&name.into(), crate::storage::table::SCHEMA.as_ref().clone())
+                .unwrap();
+        }
+
+        assert_eq!(
+            completions("select ", &session),
+            vec!["email", "id", "username"]
+        );
+        assert_eq!(completions("select id, user", &session), vec!["username"]);
+        assert_eq!(
+            completions("select * from users where em", &session),
+            vec!["email"]
+        );
+        assert_eq!(
+            completions("insert into users (id, e", &session),
+            vec!["email"]
+        );
+        assert!(completions("insert into users (id) values (", &session).is_empty());
+        assert_eq!(
+            completions("select * from ", &session),
+            vec!["orders", "users"]
+        );
+        assert_eq!(completions("select * fr", &session), vec!["from"]);
+
+        // A locked table is skipped instead of waiting for its lock
+        let users = session.database.find_table(&"users".into()).unwrap();
+        let _guard = users.lock().unwrap();
+        assert!(completions("select * from users where em", &session).is_empty());
+        assert_eq!(
+            completions("select * from orders where em", &session),
+            vec!["email"]
+        );
+        assert_eq!(common_prefix(&["orders".into(), "order".into()]), "order");
+        assert_eq!(common_prefix(&[]), "");
+    }
 }
@@ -14,8 +14,8 @@ use std::io::{self, Write};
 #[derive(Debug, Default)]
 pub struct StatusBar {
     database: Option<String>,
-    x: Option<u16>,
-    y: Option<u16>,
+    x: Option<usize>,
+    y: Option<usize>,
     buf: Option<usize>,
 }
 
@@ -37,8 +37,8 @@ impl StatusBar {
     pub fn update(
         &mut self,
         database: Option<String>,
-        x: Option<u16>,
-        y: Option<u16>,
+        x: Option<usize>,
+        y: Option<usize>,
         buf: Option<usize>,
     ) {
         self.database = database.or(self.database.clone());
@@ -117,12 +117,12 @@ impl StatusBar {
     }
 
     /// Gets the current cursor x-position, if set.
-    pub fn x(&self) -> Option<u16> {
+    pub fn x(&self) -> Option<usize> {
         self.x
     }
 
     /// Gets the current cursor y-position, if set.
-    pub fn y(&self) -> Option<u16> {
+    pub fn y(&self) -> Option<usize> {
         self.y
     }
 
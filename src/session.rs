@@ -1,13 +1,157 @@
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::Duration;
 use uuid::Uuid;
 
 use crate::{
+    command,
     database::{self, Database},
-    errors,
+    errors, sql, storage,
 };
 
+/// Format used to render result sets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Tsv,
+}
+
+impl OutputFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Tsv => "tsv",
+        }
+    }
+}
+
+/// Runtime settings adjusted with `SET name = value`.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// Flush changes to disk after every statement.
+    pub autocommit: bool,
+    /// Number of decimals shown for statement timings.
+    pub timing_precision: usize,
+    /// Format used to render result sets.
+    pub output: OutputFormat,
+    /// Maximum number of rows a `SELECT` reads, `0` for no cap.
+    pub max_rows: usize,
+    /// Maximum number of characters accepted in a single REPL input, `0` for no limit.
+    pub max_input_len: usize,
+    /// Text shown in place of NULL values in tables and batch output.
+    pub null_display: String,
+    /// Reject string values longer than their column instead of truncating them.
+    pub strict_mode: bool,
+    /// Seconds of REPL inactivity after which unsaved changes are flushed, `0` to disable.
+    pub idle_flush: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            autocommit: false,
+            timing_precision: 2,
+            output: OutputFormat::Table,
+            max_rows: 10_000,
+            max_input_len: 1 << 20,
+            null_display: "NULL".to_string(),
+            strict_mode: true,
+            idle_flush: 5,
+        }
+    }
+}
+
+impl Settings {
+    /// Updates a setting by name.
+    ///
+    /// # Arguments
+    /// * `name` - The setting name, e.g. `output`.
+    /// * `value` - The new value, e.g. `json`.
+    ///
+    /// # Returns
+    /// An `errors::Error::Command` for unknown settings or invalid values.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), errors::Error> {
+        let invalid = || err!(Command, "Invalid value '{}' for '{}'", value, name);
+        let parse_bool = || match value.to_lowercase().as_str() {
+            "1" | "on" | "true" => Ok(true),
+            "0" | "off" | "false" => Ok(false),
+            _ => Err(invalid()),
+        };
+        match name {
+            "autocommit" => {
+                self.autocommit = parse_bool()?;
+            }
+            "strict_mode" => {
+                self.strict_mode = parse_bool()?;
+            }
+            "timing_precision" => {
+                self.timing_precision = value.parse().map_err(|_| invalid())?;
+            }
+            "idle_flush" => {
+                self.idle_flush = value.parse().map_err(|_| invalid())?;
+            }
+            "max_rows" => {
+                self.max_rows = value.parse().map_err(|_| invalid())?;
+            }
+            "max_input_len" => {
+                self.max_input_len = value.parse().map_err(|_| invalid())?;
+            }
+            "null_display" => {
+                self.null_display = value.to_string();
+            }
+            "output" => {
+                self.output = match value.to_lowercase().as_str() {
+                    "table" => OutputFormat::Table,
+                    "json" => OutputFormat::Json,
+                    "tsv" => OutputFormat::Tsv,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(err!(Command, "Unknown variable '{}'", name)),
+        }
+        Ok(())
+    }
+
+    /// Returns all settings as name/value pairs, as listed by `SHOW VARIABLES`.
+    pub fn variables(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "autocommit",
+                if self.autocommit { "ON" } else { "OFF" }.into(),
+            ),
+            ("idle_flush", self.idle_flush.to_string()),
+            ("max_input_len", self.max_input_len.to_string()),
+            ("max_rows", self.max_rows.to_string()),
+            ("null_display", self.null_display.clone()),
+            ("output", self.output.as_str().into()),
+            (
+                "strict_mode",
+                if self.strict_mode { "ON" } else { "OFF" }.into(),
+            ),
+            ("timing_precision", self.timing_precision.to_string()),
+        ]
+    }
+}
+
 pub struct Session {
     pub id: Uuid,
     pub database: database::Database,
+    /// Maximum time to wait for a table lock.
+    pub lock_timeout: Duration,
+    /// Runtime settings changed with `SET`.
+    pub settings: Settings,
+    /// Warnings raised by the last executed statement.
+    pub warnings: Vec<String>,
+    /// Primary key of the last row inserted in this session.
+    pub last_insert_id: Option<u32>,
+    /// Directory holding the databases of this session.
+    pub data_dir: PathBuf,
+    /// Private data directory of a test session, removed when the session is dropped.
+    ///
+    /// Declared last so the database is flushed and dropped before its files go away.
+    temp_dir: Option<tempfile::TempDir>,
 }
 
 impl Session {
@@ -15,24 +159,227 @@ impl Session {
         Ok(Session {
             id: Uuid::new_v4(),
             database: Database::get_or_create(&"default".into())?,
+            lock_timeout: storage::LOCK_TIMEOUT,
+            settings: Settings::default(),
+            warnings: Vec::new(),
+            last_insert_id: None,
+            data_dir: PathBuf::from(database::DATA_DIR),
+            temp_dir: None,
         })
     }
 
+    /// Opens a session for tests, isolated in its own temporary data directory.
+    ///
+    /// All storage paths of the session, including databases created or selected
+    /// later, live under that directory, so concurrent tests never share files. The
+    /// directory is removed when the session is dropped.
     pub fn open_test() -> Result<Self, errors::Error> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("mysqlite_test_")
+            .tempdir()?;
+        let data_dir = temp_dir.path().to_path_buf();
         Ok(Session {
             id: Uuid::new_v4(),
-            database: Database::get_or_create(&format!("test_{}", Uuid::new_v4().to_string()))?,
+            database: Database::get_or_create_in(
+                &data_dir,
+                &format!("test_{}", Uuid::new_v4()),
+            )?,
+            lock_timeout: storage::LOCK_TIMEOUT,
+            settings: Settings::default(),
+            warnings: Vec::new(),
+            last_insert_id: None,
+            data_dir,
+            temp_dir: Some(temp_dir),
         })
     }
 
     pub fn close(&mut self) -> Result<(), errors::Error> {
-        self.database.flush()?;
+        self.database.flush(self.lock_timeout)?;
         Ok(())
     }
 
-    pub fn set_database(&mut self, database: database::Database) -> Result<(), errors::Error> {
-        self.database.flush()?;
-        self.database = database;
+    /// Switches the session to another database.
+    ///
+    /// The current database is flushed before the new one is opened, so switching to
+    /// the same database reads back its pending changes. On any failure the current
+    /// database stays selected.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the database to switch to.
+    pub fn set_database(&mut self, name: &String) -> Result<(), errors::Error> {
+        self.database.flush(self.lock_timeout)?;
+        self.database = Database::get_in(&self.data_dir, name)?;
         Ok(())
     }
+
+    /// Flushes the current database and reloads it from disk.
+    pub fn reopen(&mut self) -> Result<(), errors::Error> {
+        let name = self.database.name.clone();
+        self.set_database(&name)
+    }
+
+    /// Reads an SQL script and executes its statements in order.
+    ///
+    /// Execution stops at the first failing statement; earlier statements keep their effects.
+    ///
+    /// # Arguments
+    /// * `reader` - The source of the script, e.g. a file or a byte slice.
+    ///
+    /// # Returns
+    /// The result of each executed statement, or the first `errors::Error` encountered.
+    pub fn execute_script<R: Read>(
+        &mut self,
+        mut reader: R,
+    ) -> Result<Vec<command::SqlResult>, errors::Error> {
+        let mut script = String::new();
+        reader.read_to_string(&mut script)?;
+
+        sql::split_statements(&script)
+            .into_iter()
+            .map(|statement| command::execute(self, sql::parser::parse(statement)?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_database() {
+        let mut session = Session::open_test().unwrap();
+        let name = session.database.name.clone();
+
+        let missing = format!("test_missing_{}", Uuid::new_v4());
+        assert!(session.set_database(&missing).is_err());
+        assert_eq!(session.database.name, name);
+
+        let other = format!("test_other_{}", Uuid::new_v4());
+        Database::create_in(&session.data_dir, &other).unwrap();
+        session.set_database(&other).unwrap();
+        assert_eq!(session.database.name, other);
+
+        session.reopen().unwrap();
+        assert_eq!(session.database.name, other);
+    }
+
+    #[test]
+    fn test_set_same_database_keeps_pending_rows() {
+        let mut session = Session::open_test().unwrap();
+        session
+            .execute_script(
+                &b"CREATE TABLE t (id INT PRIMARY KEY); INSERT INTO t (id) VALUES (1);"[..],
+            )
+            .unwrap();
+        let name = session.database.name.clone();
+        session.set_database(&name).unwrap();
+
+        match session
+            .execute_script(&b"SELECT id FROM t;"[..])
+            .unwrap()
+            .remove(0)
+        {
+            command::SqlResult::ResultSet { rows, .. } => {
+                assert_eq!(rows, vec![vec![Some("1".to_string())]])
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_open_test_isolation() {
+        let handles: Vec<_> = (0..2)
+            .map(|n| {
+                std::thread::spawn(move || {
+                    let mut session = Session::open_test().unwrap();
+                    let mut script =
+                        "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(8));".to_string();
+                    for id in 1..=20 {
+                        script.push_str(&format!(
+                            "INSERT INTO users (id, name) VALUES ({}, 's{}');",
+                            id * 2 + n,
+                            n
+                        ));
+                    }
+                    session.execute_script(script.as_bytes()).unwrap();
+                    session.close().unwrap();
+                    session.reopen().unwrap();
+                    let rows = match session
+                        .execute_script(&b"SELECT * FROM users;"[..])
+                        .unwrap()
+                        .remove(0)
+                    {
+                        command::SqlResult::ResultSet { rows, .. } => rows,
+                        r => panic!("Unexpected result: {:?}", r),
+                    };
+                    assert_eq!(rows.len(), 20);
+                    assert!(rows.iter().all(|row| row[1] == Some(format!("s{}", n))));
+                    session.data_dir.clone()
+                })
+            })
+            .collect();
+
+        let dirs: Vec<PathBuf> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_ne!(dirs[0], dirs[1]);
+        // Each data directory is removed together with its session
+        assert!(dirs.iter().all(|dir| !dir.exists()));
+    }
+
+    #[test]
+    fn test_execute_script() {
+        let mut session = Session::open_test().unwrap();
+        let script = b"-- setup
+            CREATE TABLE t (id INT PRIMARY KEY, name VARCHAR(8)); /* seed */
+            INSERT INTO t (id, name) VALUES (1, 'a;b'); # trailing comment
+        ";
+        let results = session.execute_script(&script[..]).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(matches!(
+            results[1],
+            command::SqlResult::Ok {
+                affected_rows: 1,
+                ..
+            }
+        ));
+
+        let script = b"SELECT * FROM t; SELECT * FROM missing; SELECT 1;";
+        assert!(session.execute_script(&script[..]).is_err());
+    }
+
+    #[test]
+    fn test_dump_and_source() {
+        let mut session = Session::open_test().unwrap();
+        let script = b"
+            CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(32) DEFAULT guest, score DECIMAL(6,2));
+            INSERT INTO users (id, name, score) VALUES (2, 'bob smith', 7.5);
+            INSERT INTO users (id, name, score) VALUES (1, 'alice', '-1.25');
+            INSERT INTO users (id, score) VALUES (3, 0);
+            INSERT INTO users (id, name) VALUES (4, 'dave');
+        ";
+        session.execute_script(&script[..]).unwrap();
+        let select = |session: &mut Session| match session
+            .execute_script(&b"SELECT * FROM users;"[..])
+            .unwrap()
+            .remove(0)
+        {
+            command::SqlResult::ResultSet { rows, .. } => rows,
+            r => panic!("Unexpected result: {:?}", r),
+        };
+        let expected = select(&mut session);
+
+        let dump = session.database.dump(session.lock_timeout).unwrap();
+        assert!(dump.contains(
+            "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(32) DEFAULT guest, score DECIMAL(6,2));"
+        ));
+        assert!(dump.contains("VALUES (2, 'bob smith', 7.50);"));
+        assert!(dump.contains("VALUES (4, 'dave', NULL);"));
+        session.execute_script(&b"DROP TABLE users;"[..]).unwrap();
+        assert!(session
+            .execute_script(&b"SELECT * FROM users;"[..])
+            .is_err());
+
+        // Restoring runs the dump the way `\source` does
+        session.execute_script(dump.as_bytes()).unwrap();
+        assert_eq!(select(&mut session), expected);
+    }
 }
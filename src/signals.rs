@@ -0,0 +1,39 @@
+//! # Signal Handling
+//!
+//! SIGINT and SIGTERM request a graceful shutdown instead of killing the process, so
+//! the REPL and batch execution can flush their session and restore the terminal
+//! before exiting. A second signal terminates the process right away.
+
+use once_cell::sync::Lazy;
+use signal_hook::consts::{SIGINT, SIGTERM};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+static SHUTDOWN: Lazy<Arc<AtomicBool>> = Lazy::new(|| Arc::new(AtomicBool::new(false)));
+
+/// Installs the SIGINT and SIGTERM handlers.
+pub fn install() -> io::Result<()> {
+    for signal in [SIGINT, SIGTERM] {
+        // Registered first, so it sees the flag set by an earlier signal
+        signal_hook::flag::register_conditional_shutdown(signal, 1, Arc::clone(&SHUTDOWN))?;
+        signal_hook::flag::register(signal, Arc::clone(&SHUTDOWN))?;
+    }
+    Ok(())
+}
+
+/// Checks whether a shutdown was requested by a signal.
+pub fn shutdown_requested() -> bool {
+    SHUTDOWN.load(Ordering::Relaxed)
+}
+
+/// Returns an `Interrupted` error if a shutdown was requested, for `?` at loop heads.
+pub fn check_shutdown() -> io::Result<()> {
+    if shutdown_requested() {
+        return Err(io::Error::new(
+            io::ErrorKind::Interrupted,
+            "Shutdown requested",
+        ));
+    }
+    Ok(())
+}
@@ -2,4 +2,6 @@ pub mod parser;
 mod statement;
 mod tokenizer;
 mod validator;
+pub use parser::validate;
 pub use statement::*;
+pub use tokenizer::split_statements;
@@ -1,9 +1,10 @@
 use super::statement::*;
 use super::tokenizer;
+use super::tokenizer::ExpressionToken;
 use super::validator;
 use crate::errors;
-use crate::storage::column::ColumnType;
-use crate::storage::schema::ColumnSchema;
+use crate::storage::column::{ColumnType, DECIMAL_MAX_PRECISION};
+use crate::storage::schema::{ColumnSchema, DefaultValue, TableSchema};
 use std::collections::VecDeque;
 
 /// Parses an `INSERT` statement from tokenized SQL.
@@ -21,6 +22,13 @@ fn parse_insert(sql: &mut VecDeque<String>) -> Result<InsertStatement, errors::E
         "'INSERT INTO table' must be followed by column names in parentheses.",
     )?;
     let (columns, values) = parse_columns_and_values(sql, columns_sql)?;
+    validator::validate_value_count(&table, &columns, values.len())?;
+    if let Some(column) = find_duplicate(columns.iter()) {
+        return Err(errors::Error::Syntax(format!(
+            "Duplicate column '{}' in INSERT",
+            column
+        )));
+    }
     Ok(InsertStatement {
         table,
         columns,
@@ -36,12 +44,18 @@ fn parse_insert(sql: &mut VecDeque<String>) -> Result<InsertStatement, errors::E
 /// # Returns
 /// A `Result` containing the parsed `SelectStatement` or an `errors::Error`.
 fn parse_select(sql: &mut VecDeque<String>) -> Result<SelectStatement, errors::Error> {
+    let lock = parse_lock_mode(sql);
     let mut columns_sql = String::new();
+    let mut has_from = false;
     while let Some(token) = sql.front() {
         if token.to_uppercase() == "FROM" {
             sql.pop_front(); // Consume "FROM"
+            has_from = true;
             break;
         }
+        if !columns_sql.is_empty() {
+            columns_sql.push(' ');
+        }
         columns_sql.push_str(&sql.pop_front().unwrap());
     }
     if columns_sql.is_empty() {
@@ -49,9 +63,182 @@ fn parse_select(sql: &mut VecDeque<String>) -> Result<SelectStatement, errors::E
             "'SELECT' must specify columns.".to_owned(),
         ));
     }
+    if !has_from && columns_sql.split(' ').next() == Some("*") {
+        // Without FROM the loop above swallowed every token, so report the missing
+        // clause rather than whatever follows the star.
+        return Err(errors::Error::Semantic(
+            "SELECT * requires a FROM clause".to_owned(),
+        ));
+    }
     let columns = parse_columns(columns_sql)?;
-    let table = pop_token(sql, "'SELECT ... FROM' must be followed by a table name.")?;
-    Ok(SelectStatement { table, columns })
+    if !has_from {
+        // Constant query, e.g. `SELECT 1`.
+        return Ok(SelectStatement {
+            table: None,
+            columns,
+            join: None,
+            where_clause: None,
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            lock,
+        });
+    }
+    let table = Some(pop_token(
+        sql,
+        "'SELECT ... FROM' must be followed by a table name.",
+    )?);
+    let join = parse_join(sql)?;
+    let where_clause = parse_where_clause(sql)?;
+    let group_by = parse_group_by(sql)?;
+    let having = parse_having(sql)?;
+    let order_by = parse_order_by(sql)?;
+
+    let stmt = SelectStatement {
+        table,
+        columns,
+        join,
+        where_clause,
+        group_by,
+        having,
+        order_by,
+        lock,
+    };
+    validate_qualifiers(&stmt)?;
+    Ok(stmt)
+}
+
+/// Removes a trailing `FOR UPDATE` or `LOCK IN SHARE MODE` from a `SELECT`.
+///
+/// The clause always ends the statement, so it is taken off the back before the
+/// other clauses, which consume every remaining token, are parsed.
+///
+/// # Arguments
+/// * `sql` - A mutable `VecDeque<String>` of SQL tokens.
+///
+/// # Returns
+/// The requested `LockMode`, or `None` without a locking clause.
+fn parse_lock_mode(sql: &mut VecDeque<String>) -> Option<LockMode> {
+    let ends_with = |sql: &VecDeque<String>, words: &[&str]| {
+        sql.len() >= words.len()
+            && sql
+                .iter()
+                .skip(sql.len() - words.len())
+                .zip(words)
+                .all(|(token, word)| token.eq_ignore_ascii_case(word))
+    };
+    let (mode, len) = if ends_with(sql, &["FOR", "UPDATE"]) {
+        (LockMode::Exclusive, 2)
+    } else if ends_with(sql, &["LOCK", "IN", "SHARE", "MODE"]) {
+        (LockMode::Shared, 4)
+    } else {
+        return None;
+    };
+    sql.truncate(sql.len() - len);
+    Some(mode)
+}
+
+/// Checks that every qualified column of a `SELECT` refers to one of its tables.
+///
+/// # Arguments
+/// * `stmt` - The parsed `SelectStatement`.
+///
+/// # Returns
+/// An `errors::Error::Semantic` naming the first unknown qualifier.
+fn validate_qualifiers(stmt: &SelectStatement) -> Result<(), errors::Error> {
+    // Columns are qualified with the bare name of `database.table`
+    let tables: Vec<&str> = stmt
+        .table
+        .iter()
+        .chain(stmt.join.as_ref().map(|j| &j.table))
+        .map(|t| t.split_once('.').map_or(t.as_str(), |(_, table)| table))
+        .collect();
+
+    let mut refs: Vec<&ColumnRef> = Vec::new();
+    if let Columns::List(exprs) = &stmt.columns {
+        refs.extend(exprs.iter().flat_map(|e| e.column_refs()));
+    }
+    if let Some(join) = &stmt.join {
+        refs.extend([&join.left, &join.right]);
+    }
+    if let Some(where_clause) = &stmt.where_clause {
+        refs.extend(where_clause.column_refs());
+    }
+    refs.extend(&stmt.group_by);
+    if let Some(having) = &stmt.having {
+        refs.extend(having.column_refs());
+    }
+    refs.extend(stmt.order_by.iter().map(|key| &key.column));
+
+    for column in refs {
+        if let Some(table) = &column.table {
+            if !tables.contains(&table.as_str()) {
+                return Err(errors::Error::Semantic(format!(
+                    "Unknown table '{}' in '{}'",
+                    table, column
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses an optional `[INNER] JOIN table ON left = right` clause of a `SELECT`.
+///
+/// # Arguments
+/// * `tokens` - A mutable `VecDeque<String>` of SQL tokens following the `FROM` table.
+///
+/// # Returns
+/// A `Result` containing the parsed `JoinClause`, `None` if there is no join, or an `errors::Error`.
+fn parse_join(tokens: &mut VecDeque<String>) -> Result<Option<JoinClause>, errors::Error> {
+    match tokens.front().map(|t| t.to_uppercase()).as_deref() {
+        Some("INNER") => {
+            tokens.pop_front();
+            expect_token(tokens, "JOIN", "'INNER' must be followed by 'JOIN'.")?;
+        }
+        Some("JOIN") => {
+            tokens.pop_front();
+        }
+        _ => return Ok(None),
+    }
+    let table = pop_token(tokens, "'JOIN' must be followed by a table name.")?;
+    expect_token(tokens, "ON", "'JOIN table' must be followed by 'ON'.")?;
+
+    let condition = take_until(tokens, &["WHERE", "ORDER"]).join(" ");
+    let (left, right) = condition.split_once('=').ok_or_else(|| {
+        errors::Error::Syntax(
+            "'JOIN ... ON' condition must be an equality of two columns.".to_owned(),
+        )
+    })?;
+    Ok(Some(JoinClause {
+        table,
+        left: parse_column_ref(left.trim())?,
+        right: parse_column_ref(right.trim())?,
+    }))
+}
+
+/// Parses a column name, optionally qualified by its table (`table.column`).
+///
+/// # Arguments
+/// * `name` - The column name as written in the query.
+///
+/// # Returns
+/// A `Result` containing the `ColumnRef` or an `errors::Error`.
+fn parse_column_ref(name: &str) -> Result<ColumnRef, errors::Error> {
+    let (table, column) = match name.split_once('.') {
+        Some((table, column)) => (Some(validator::validate_column_name(table)?), column),
+        None => (None, name),
+    };
+    if column.contains('.') {
+        return Err(errors::Error::Syntax(format!(
+            "Invalid column reference: {}.",
+            name
+        )));
+    }
+    Ok(ColumnRef {
+        table,
+        name: validator::validate_column_name(column)?,
+    })
 }
 
 /// Parses a `CREATE` statement from tokenized SQL.
@@ -87,8 +274,29 @@ fn parse_create(sql: &mut VecDeque<String>) -> Result<CreateStatement, errors::E
 fn parse_show(tokens: &mut VecDeque<String>) -> Result<ShowStatement, errors::Error> {
     let entity = pop_token(tokens, "'SHOW' must specify 'DATABASES' or 'TABLES'.")?.to_uppercase();
     match entity.as_str() {
-        "DATABASES" => Ok(ShowStatement::ShowDatabasesStatement),
-        "TABLES" => Ok(ShowStatement::ShowTablesStatement),
+        "DATABASES" => Ok(ShowStatement::ShowDatabasesStatement(parse_show_like(
+            tokens, &entity,
+        )?)),
+        "TABLES" => Ok(ShowStatement::ShowTablesStatement(parse_show_like(
+            tokens, &entity,
+        )?)),
+        "TABLE" => {
+            let error_msg = "'SHOW TABLE' must be followed by STATUS.";
+            if !pop_token(tokens, error_msg)?.eq_ignore_ascii_case("STATUS") {
+                return Err(errors::Error::Syntax(error_msg.to_string()));
+            }
+            Ok(ShowStatement::ShowTableStatusStatement(parse_show_like(
+                tokens,
+                "TABLE STATUS",
+            )?))
+        }
+        "VARIABLES" => Ok(ShowStatement::ShowVariablesStatement),
+        "COLUMNS" | "FIELDS" => Ok(ShowStatement::ShowColumnsStatement(parse_show_table(
+            tokens, &entity,
+        )?)),
+        "INDEX" | "INDEXES" | "KEYS" => Ok(ShowStatement::ShowIndexStatement(parse_show_table(
+            tokens, &entity,
+        )?)),
         _ => Err(errors::Error::Syntax(format!(
             "Unknown entity to show: {}.",
             entity
@@ -96,6 +304,81 @@ fn parse_show(tokens: &mut VecDeque<String>) -> Result<ShowStatement, errors::Er
     }
 }
 
+/// Parses the optional `LIKE 'pattern'` suffix of a `SHOW DATABASES` or `SHOW TABLES`
+/// statement.
+///
+/// # Arguments
+/// * `tokens` - A mutable `VecDeque<String>` of SQL tokens.
+/// * `entity` - The shown entity, used in error messages.
+///
+/// # Returns
+/// A `Result` containing the unquoted pattern, `None` without `LIKE`, or an `errors::Error`.
+fn parse_show_like(
+    tokens: &mut VecDeque<String>,
+    entity: &str,
+) -> Result<Option<String>, errors::Error> {
+    match tokens.front() {
+        Some(t) if t.eq_ignore_ascii_case("LIKE") => tokens.pop_front(),
+        _ => return Ok(None),
+    };
+    let error_msg = format!(
+        "'SHOW {} LIKE' must be followed by a quoted pattern.",
+        entity
+    );
+    let pattern = pop_token(tokens, &error_msg)?;
+    match pattern
+        .strip_prefix('\'')
+        .and_then(|p| p.strip_suffix('\''))
+    {
+        Some(pattern) => Ok(Some(pattern.replace("''", "'"))),
+        None => Err(errors::Error::Syntax(error_msg)),
+    }
+}
+
+/// Parses the `FROM table` of a `SHOW COLUMNS` or `SHOW INDEX` statement.
+///
+/// # Arguments
+/// * `tokens` - A mutable `VecDeque<String>` of SQL tokens.
+/// * `entity` - The shown entity, used in error messages.
+///
+/// # Returns
+/// A `Result` containing the table name or an `errors::Error`.
+fn parse_show_table(tokens: &mut VecDeque<String>, entity: &str) -> Result<String, errors::Error> {
+    let error_msg = format!(
+        "'SHOW {}' must be followed by FROM and a table name.",
+        entity
+    );
+    let keyword = pop_token(tokens, &error_msg)?;
+    if !keyword.eq_ignore_ascii_case("FROM") && !keyword.eq_ignore_ascii_case("IN") {
+        return Err(errors::Error::Syntax(error_msg));
+    }
+    pop_token(tokens, &error_msg)
+}
+
+/// Parses a `SET name = value` (or `PRAGMA name = value`) statement from tokenized SQL.
+///
+/// # Arguments
+/// * `tokens` - A mutable `VecDeque<String>` of SQL tokens.
+///
+/// # Returns
+/// A `Result` containing the parsed `SetStatement` or an `errors::Error`.
+fn parse_set(tokens: &mut VecDeque<String>) -> Result<SetStatement, errors::Error> {
+    let assignment = tokens.drain(..).collect::<Vec<_>>().join(" ");
+    let (name, value) = assignment
+        .split_once('=')
+        .ok_or_else(|| errors::Error::Syntax("'SET' must be followed by name = value.".into()))?;
+    let (name, value) = (name.trim(), value.trim().trim_matches('\''));
+    if name.is_empty() || value.is_empty() {
+        return Err(errors::Error::Syntax(
+            "'SET' must be followed by name = value.".into(),
+        ));
+    }
+    Ok(SetStatement {
+        name: name.to_lowercase(),
+        value: value.to_string(),
+    })
+}
+
 /// Parses a `DESCRIBE` statement from tokenized SQL.
 ///
 /// # Arguments
@@ -108,6 +391,108 @@ fn parse_describe(tokens: &mut VecDeque<String>) -> Result<DescribeStatement, er
     Ok(DescribeStatement { name })
 }
 
+/// Parses a `TRUNCATE [TABLE]` statement from tokenized SQL.
+///
+/// # Arguments
+/// * `tokens` - A mutable `VecDeque<String>` of SQL tokens.
+///
+/// # Returns
+/// A `Result` containing the parsed `TruncateStatement` or an `errors::Error`.
+fn parse_truncate(tokens: &mut VecDeque<String>) -> Result<TruncateStatement, errors::Error> {
+    if tokens.front().map(|t| t.to_uppercase()).as_deref() == Some("TABLE") {
+        tokens.pop_front();
+    }
+    let name = pop_token(tokens, "'TRUNCATE TABLE' must be followed by a table name.")?;
+    Ok(TruncateStatement { name })
+}
+
+/// Parses an `ALTER TABLE` statement from tokenized SQL.
+///
+/// Supports `ALTER TABLE name RENAME [TO] new_name`, `ALTER TABLE name RENAME COLUMN a TO b`
+/// and `ALTER TABLE name CHANGE [COLUMN] a b [type]`.
+///
+/// # Arguments
+/// * `tokens` - A mutable `VecDeque<String>` of SQL tokens.
+///
+/// # Returns
+/// A `Result` containing the parsed `AlterStatement` or an `errors::Error`.
+fn parse_alter(tokens: &mut VecDeque<String>) -> Result<AlterStatement, errors::Error> {
+    expect_token(tokens, "TABLE", "'ALTER' must be followed by 'TABLE'.")?;
+    let name = pop_token(tokens, "'ALTER TABLE' must be followed by a table name.")?;
+    let action = pop_token(tokens, "'ALTER TABLE name' must be followed by an action.")?;
+    match action.to_uppercase().as_str() {
+        "RENAME" if tokens.front().map(|t| t.to_uppercase()).as_deref() == Some("COLUMN") => {
+            tokens.pop_front();
+            let column = pop_token(tokens, "'RENAME COLUMN' must be followed by a column name.")?;
+            expect_token(
+                tokens,
+                "TO",
+                "'RENAME COLUMN name' must be followed by 'TO'.",
+            )?;
+            let new_column = pop_token(
+                tokens,
+                "'RENAME COLUMN name TO' must be followed by a column name.",
+            )?;
+            Ok(AlterStatement::RenameColumnStatement {
+                name,
+                column,
+                new_column: validator::validate_column_name(&new_column)?,
+                type_: None,
+            })
+        }
+        "CHANGE" => {
+            if tokens.front().map(|t| t.to_uppercase()).as_deref() == Some("COLUMN") {
+                tokens.pop_front();
+            }
+            let column = pop_token(tokens, "'CHANGE' must be followed by a column name.")?;
+            let new_column = pop_token(tokens, "'CHANGE name' must be followed by a column name.")?;
+            let type_ = tokens
+                .pop_front()
+                .map(|t| parse_column_type(&t))
+                .transpose()?;
+            Ok(AlterStatement::RenameColumnStatement {
+                name,
+                column,
+                new_column: validator::validate_column_name(&new_column)?,
+                type_,
+            })
+        }
+        "RENAME" => {
+            if tokens.front().map(|t| t.to_uppercase()).as_deref() == Some("TO") {
+                tokens.pop_front();
+            }
+            let new_name = pop_token(tokens, "'RENAME TO' must be followed by a table name.")?;
+            Ok(AlterStatement::RenameTableStatement { name, new_name })
+        }
+        _ => Err(errors::Error::Syntax(format!(
+            "Unsupported ALTER TABLE action: {}.",
+            action
+        ))),
+    }
+}
+
+/// Parses a `RENAME TABLE name TO new_name` statement from tokenized SQL.
+///
+/// # Arguments
+/// * `tokens` - A mutable `VecDeque<String>` of SQL tokens.
+///
+/// # Returns
+/// A `Result` containing the parsed `AlterStatement` or an `errors::Error`.
+fn parse_rename(tokens: &mut VecDeque<String>) -> Result<AlterStatement, errors::Error> {
+    expect_token(tokens, "TABLE", "'RENAME' must be followed by 'TABLE'.")?;
+    let name = pop_token(tokens, "'RENAME TABLE' must be followed by a table name.")?;
+    expect_token(
+        tokens,
+        "TO",
+        "'RENAME TABLE name' must be followed by 'TO'.",
+    )?;
+    let new_name = pop_token(
+        tokens,
+        "'RENAME TABLE name TO' must be followed by a table name.",
+    )?;
+    Ok(AlterStatement::RenameTableStatement { name, new_name })
+}
+
 /// Parses a `DROP` statement from tokenized SQL.
 ///
 /// # Arguments
@@ -213,12 +598,34 @@ fn parse_create_table(sql: &mut VecDeque<String>) -> Result<CreateTableStatement
     }
     let columns_str = &columns_sql[1..columns_sql.len() - 1];
     let columns_schemas = parse_column_schemas(columns_str)?;
+    if let Some(column) = find_duplicate(columns_schemas.iter().map(|c| &c.name)) {
+        return Err(errors::Error::Syntax(format!(
+            "Duplicate column '{}' in CREATE TABLE",
+            column
+        )));
+    }
+    if columns_schemas.iter().filter(|c| c.is_primary).count() > 1 {
+        return Err(errors::Error::Syntax(
+            "Multiple PRIMARY KEY columns in CREATE TABLE".to_owned(),
+        ));
+    }
     Ok(CreateTableStatement {
         name,
         columns_schemas,
     })
 }
 
+/// Returns the first name that occurs more than once, ignoring case.
+///
+/// # Arguments
+/// * `names` - The names to check, in order of appearance.
+fn find_duplicate<'a>(names: impl Iterator<Item = &'a String>) -> Option<&'a String> {
+    let mut seen = std::collections::HashSet::new();
+    names
+        .into_iter()
+        .find(|name| !seen.insert(name.to_lowercase()))
+}
+
 /// Helper function to expect and consume a specific token.
 ///
 /// # Arguments
@@ -264,7 +671,7 @@ fn pop_token(tokens: &mut VecDeque<String>, error_msg: &str) -> Result<String, e
 fn parse_columns_and_values(
     tokens: &mut VecDeque<String>,
     columns_sql: String,
-) -> Result<(Vec<String>, Vec<String>), errors::Error> {
+) -> Result<(Vec<String>, Vec<InsertValue>), errors::Error> {
     if !columns_sql.starts_with('(') || !columns_sql.ends_with(')') {
         return Err(errors::Error::Syntax(
             "Column names must be enclosed in parentheses.".to_owned(),
@@ -291,41 +698,175 @@ fn parse_columns_and_values(
     }
     let values = values_sql[1..values_sql.len() - 1]
         .split(',')
-        .map(|s| validator::validate_value(s.trim()))
+        .map(|s| parse_insert_value(s.trim()))
         .collect::<Result<Vec<_>, _>>()?;
+    Ok((columns, values))
+}
 
-    if columns.len() != values.len() {
-        return Err(errors::Error::Syntax(format!(
-            "Column count ({}) does not match value count ({}).",
-            columns.len(),
-            values.len()
-        )));
+/// Parses a value of an `INSERT` statement.
+///
+/// Unquoted `NOW()` and `CURRENT_TIMESTAMP` are function calls evaluated at execution
+/// time and an unquoted `NULL` is no value; anything else is a literal. A quoted string is stored without its quotes,
+/// with doubled quotes read as one.
+fn parse_insert_value(value: &str) -> Result<InsertValue, errors::Error> {
+    let trimmed = value.trim();
+    if trimmed.len() >= 2 && trimmed.starts_with('\'') && trimmed.ends_with('\'') {
+        let text = trimmed[1..trimmed.len() - 1].replace("''", "'");
+        return Ok(InsertValue::Literal(text));
+    }
+    match value.to_uppercase().as_str() {
+        "NOW()" | "CURRENT_TIMESTAMP" => Ok(InsertValue::Now),
+        "NULL" => Ok(InsertValue::Null),
+        _ => validator::validate_value(value).map(InsertValue::Literal),
     }
-    Ok((columns, values))
 }
 
-/// Parses column names for `SELECT` statements.
+/// Parses column expressions for `SELECT` statements.
 ///
 /// # Arguments
-/// * `columns_sql` - The string containing column definitions.
+/// * `columns_sql` - The string containing the comma-separated column list.
 ///
 /// # Returns
 /// A `Result` containing the `Columns` enum or an `errors::Error`.
 fn parse_columns(columns_sql: String) -> Result<Columns, errors::Error> {
-    let trimmed = columns_sql.replace(" ", "");
-    if trimmed == "*" {
-        Ok(Columns::All)
-    } else {
-        let columns = trimmed
-            .split(',')
-            .map(|s| validator::validate_column_name(s.trim()))
-            .collect::<Result<Vec<_>, _>>()?;
-        if columns.is_empty() {
-            return Err(errors::Error::Syntax(
-                "No columns specified in SELECT.".to_owned(),
-            ));
+    if columns_sql.trim() == "*" {
+        return Ok(Columns::All);
+    }
+    let mut tokens = tokenizer::tokenize_expression(&columns_sql)?;
+    if tokens.is_empty() {
+        return Err(errors::Error::Syntax(
+            "No columns specified in SELECT.".to_owned(),
+        ));
+    }
+    let mut columns = Vec::new();
+    loop {
+        columns.push(parse_expression(&mut tokens)?);
+        match tokens.pop_front() {
+            None => break,
+            Some(ExpressionToken::Comma) => continue,
+            Some(token) => {
+                return Err(errors::Error::Syntax(format!(
+                    "Unexpected token in column list: {:?}.",
+                    token
+                )))
+            }
         }
-        Ok(Columns::List(columns))
+    }
+    Ok(Columns::List(columns))
+}
+
+/// Parses an arithmetic or boolean expression, honoring operator precedence.
+///
+/// # Arguments
+/// * `tokens` - The expression token queue.
+///
+/// # Returns
+/// A `Result` containing the parsed `Expression` or an `errors::Error`.
+fn parse_expression(tokens: &mut VecDeque<ExpressionToken>) -> Result<Expression, errors::Error> {
+    parse_binary_expression(tokens, 1)
+}
+
+/// Parses a chain of binary operators binding at least as tight as `min_precedence`.
+fn parse_binary_expression(
+    tokens: &mut VecDeque<ExpressionToken>,
+    min_precedence: u8,
+) -> Result<Expression, errors::Error> {
+    let mut left = parse_operand(tokens)?;
+    // `AND`/`OR` are lexed as identifiers.
+    while let Some(ExpressionToken::Operator(op) | ExpressionToken::Identifier(op)) = tokens.front()
+    {
+        let Some(operator) = Operator::from_sql(op) else {
+            break;
+        };
+        if operator.precedence() < min_precedence {
+            break;
+        }
+        tokens.pop_front();
+        let right = parse_binary_expression(tokens, operator.precedence() + 1)?;
+        left = Expression::Binary {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+        };
+    }
+    Ok(left)
+}
+
+/// Parses a single operand: a column, a literal, an aggregate call or a parenthesized
+/// expression.
+fn parse_operand(tokens: &mut VecDeque<ExpressionToken>) -> Result<Expression, errors::Error> {
+    match tokens.pop_front() {
+        Some(ExpressionToken::Identifier(name))
+            if tokens.front() == Some(&ExpressionToken::LeftParen) =>
+        {
+            tokens.pop_front();
+            parse_aggregate(tokens, &name)
+        }
+        Some(ExpressionToken::Identifier(name)) => Ok(Expression::Column(parse_column_ref(&name)?)),
+        Some(ExpressionToken::Number(n)) => Ok(Expression::Literal(Literal::Number(n))),
+        Some(ExpressionToken::Text(s)) => Ok(Expression::Literal(Literal::Text(s))),
+        Some(ExpressionToken::Operator(op)) if op == "-" => match tokens.pop_front() {
+            Some(ExpressionToken::Number(n)) => {
+                Ok(Expression::Literal(Literal::Number(format!("-{}", n))))
+            }
+            _ => Err(errors::Error::Syntax(
+                "Unary '-' must be followed by a number.".to_owned(),
+            )),
+        },
+        Some(ExpressionToken::LeftParen) => {
+            let expression = parse_expression(tokens)?;
+            match tokens.pop_front() {
+                Some(ExpressionToken::RightParen) => Ok(expression),
+                _ => Err(errors::Error::Syntax(
+                    "Missing closing parenthesis.".to_owned(),
+                )),
+            }
+        }
+        Some(token) => Err(errors::Error::Syntax(format!(
+            "Unexpected token in expression: {:?}.",
+            token
+        ))),
+        None => Err(errors::Error::Syntax(
+            "Expression cannot be empty.".to_owned(),
+        )),
+    }
+}
+
+/// Parses the argument of an aggregate call whose opening parenthesis was consumed.
+///
+/// # Arguments
+/// * `tokens` - The expression token queue.
+/// * `name` - The function name.
+///
+/// # Returns
+/// A `Result` containing an `Expression::Aggregate` or an `errors::Error`.
+fn parse_aggregate(
+    tokens: &mut VecDeque<ExpressionToken>,
+    name: &str,
+) -> Result<Expression, errors::Error> {
+    let function = Aggregate::from_sql(name)
+        .ok_or_else(|| errors::Error::Syntax(format!("Unknown function: {}.", name)))?;
+    let argument = match tokens.front() {
+        Some(ExpressionToken::Operator(op)) if op == "*" && function == Aggregate::Count => {
+            tokens.pop_front();
+            None
+        }
+        _ => {
+            let argument = parse_expression(tokens)?;
+            if argument.has_aggregate() {
+                return Err(errors::Error::Semantic(
+                    "Invalid use of group function".to_owned(),
+                ));
+            }
+            Some(Box::new(argument))
+        }
+    };
+    match tokens.pop_front() {
+        Some(ExpressionToken::RightParen) => Ok(Expression::Aggregate { function, argument }),
+        _ => Err(errors::Error::Syntax(format!(
+            "Missing closing parenthesis after {}(.",
+            function
+        ))),
     }
 }
 
@@ -338,7 +879,7 @@ fn parse_columns(columns_sql: String) -> Result<Columns, errors::Error> {
 /// A `Result` containing a vector of `ColumnSchema` or an `errors::Error`.
 fn parse_column_schemas(columns_str: &str) -> Result<Vec<ColumnSchema>, errors::Error> {
     let mut schemas = Vec::new();
-    for column_def in columns_str.split(',') {
+    for column_def in split_column_definitions(columns_str) {
         let parts: Vec<&str> = column_def.trim().split_whitespace().collect();
         if parts.is_empty() {
             continue; // Skip empty definitions
@@ -361,16 +902,23 @@ fn parse_column_schemas(columns_str: &str) -> Result<Vec<ColumnSchema>, errors::
                 }
                 "DEFAULT" => {
                     i += 1;
-                    default = Some(pop_value(
-                        &parts,
-                        i,
-                        "DEFAULT must be followed by a value.",
-                    )?);
+                    let value = pop_value(&parts, i, "DEFAULT must be followed by a value.")?;
+                    default = Some(parse_default_value(&name, &type_, value)?);
+                    i += 1;
+                }
+                "AUTO_INCREMENT" => {
+                    default = Some(DefaultValue::AutoIncrement);
                     i += 1;
                 }
                 _ => break,
             }
         }
+        if default == Some(DefaultValue::AutoIncrement) && !is_primary {
+            return Err(errors::Error::Syntax(format!(
+                "Incorrect table definition; AUTO_INCREMENT column '{}' must be the PRIMARY KEY.",
+                name
+            )));
+        }
         let is_nullable = true; // TODO
         schemas.push(ColumnSchema {
             name,
@@ -388,14 +936,43 @@ fn parse_column_schemas(columns_str: &str) -> Result<Vec<ColumnSchema>, errors::
     Ok(schemas)
 }
 
+/// Parses the value of a column's `DEFAULT` clause.
+///
+/// # Arguments
+/// * `name` - The column name, used in error messages.
+/// * `type_` - The column type.
+/// * `value` - The raw default value.
+///
+/// # Returns
+/// A `Result` containing the `DefaultValue` or an `errors::Error`.
+fn parse_default_value(
+    name: &str,
+    type_: &ColumnType,
+    value: String,
+) -> Result<DefaultValue, errors::Error> {
+    match value.to_uppercase().as_str() {
+        "CURRENT_TIMESTAMP" | "NOW()" => match type_ {
+            ColumnType::DATETIME | ColumnType::TIMESTAMP => Ok(DefaultValue::Now),
+            _ => Err(errors::Error::Syntax(format!(
+                "Invalid default value for '{}': {} requires a DATETIME or TIMESTAMP column.",
+                name, value
+            ))),
+        },
+        _ => Ok(DefaultValue::Literal(value)),
+    }
+}
+
 /// Parses a column type from a string.
 ///
+/// Accepts the canonical form produced by `ColumnType`'s `Display`, so printed types
+/// can be fed back unchanged.
+///
 /// # Arguments
 /// * `type_str` - The type string to parse.
 ///
 /// # Returns
 /// A `Result` containing the `ColumnType` or an `errors::Error`.
-fn parse_column_type(type_str: &str) -> Result<ColumnType, errors::Error> {
+pub fn parse_column_type(type_str: &str) -> Result<ColumnType, errors::Error> {
     validator::validate_column_type(type_str)?;
     let upper = type_str.to_uppercase();
 
@@ -417,6 +994,25 @@ fn parse_column_type(type_str: &str) -> Result<ColumnType, errors::Error> {
             })?;
             Ok(ColumnType::VARCHAR(len))
         }
+        _ if upper.starts_with("CHAR(") && upper.ends_with(")") => {
+            let len_str = &upper[5..upper.len() - 1];
+            let len = len_str
+                .parse::<u16>()
+                .map_err(|_| errors::Error::Syntax(format!("Invalid CHAR length: {}.", len_str)))?;
+            Ok(ColumnType::CHAR(len))
+        }
+        "DECIMAL" => Ok(ColumnType::DECIMAL(10, 0)),
+        _ if upper.starts_with("DECIMAL(") && upper.ends_with(")") => {
+            let args = &upper[8..upper.len() - 1];
+            let invalid = || errors::Error::Syntax(format!("Invalid DECIMAL arguments: {}.", args));
+            let (precision, scale) = args.split_once(',').unwrap_or((args, "0"));
+            let precision = precision.trim().parse::<u8>().map_err(|_| invalid())?;
+            let scale = scale.trim().parse::<u8>().map_err(|_| invalid())?;
+            if precision == 0 || precision > DECIMAL_MAX_PRECISION || scale > precision {
+                return Err(invalid());
+            }
+            Ok(ColumnType::DECIMAL(precision, scale))
+        }
         _ => Err(errors::Error::Syntax(format!(
             "Unsupported column type: {}.",
             type_str
@@ -424,6 +1020,27 @@ fn parse_column_type(type_str: &str) -> Result<ColumnType, errors::Error> {
     }
 }
 
+/// Splits column definitions on commas outside parentheses and quotes, so that
+/// types like `DECIMAL(10,2)` stay in one definition.
+fn split_column_definitions(columns_str: &str) -> Vec<&str> {
+    let mut definitions = Vec::new();
+    let (mut depth, mut inside_text, mut start) = (0usize, false, 0);
+    for (i, c) in columns_str.char_indices() {
+        match c {
+            '\'' => inside_text = !inside_text,
+            '(' if !inside_text => depth += 1,
+            ')' if !inside_text => depth = depth.saturating_sub(1),
+            ',' if !inside_text && depth == 0 => {
+                definitions.push(&columns_str[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    definitions.push(&columns_str[start..]);
+    definitions
+}
+
 /// Helper to expect a keyword in a parts array.
 ///
 /// # Arguments
@@ -472,27 +1089,181 @@ fn pop_value(parts: &[&str], index: usize, error_msg: &str) -> Result<String, er
 /// * `tokens` - The token queue.
 ///
 /// # Returns
-/// A `Result` containing an optional WHERE clause string or an `errors::Error`.
-fn parse_where_clause(sql: &mut VecDeque<String>) -> Result<Option<String>, errors::Error> {
+/// A `Result` containing an optional WHERE predicate or an `errors::Error`.
+fn parse_where_clause(sql: &mut VecDeque<String>) -> Result<Option<Expression>, errors::Error> {
     if let Some(token) = sql.front() {
         if token.to_uppercase() == "WHERE" {
             sql.pop_front(); // Consume "WHERE"
-            let clause = sql
-                .into_iter()
-                .map(|c| c.clone())
-                .collect::<Vec<_>>()
-                .join(" ");
-            if clause.is_empty() {
+            let clause = take_until(sql, &["GROUP", "HAVING", "ORDER"]).join(" ");
+            let mut tokens = tokenizer::tokenize_expression(&clause)?;
+            if tokens.is_empty() {
                 return Err(errors::Error::Syntax(
                     "WHERE clause cannot be empty.".to_owned(),
                 ));
             }
-            return Ok(Some(clause));
+            let predicate = parse_expression(&mut tokens)?;
+            if let Some(token) = tokens.pop_front() {
+                return Err(errors::Error::Syntax(format!(
+                    "Unexpected token in WHERE clause: {:?}.",
+                    token
+                )));
+            }
+            return Ok(Some(predicate));
         }
     }
     Ok(None)
 }
 
+/// Parses an optional `GROUP BY column, ...` clause.
+///
+/// # Arguments
+/// * `sql` - A mutable `VecDeque<String>` of SQL tokens.
+///
+/// # Returns
+/// A `Result` containing the grouping columns, empty without `GROUP BY`, or an `errors::Error`.
+fn parse_group_by(sql: &mut VecDeque<String>) -> Result<Vec<ColumnRef>, errors::Error> {
+    if sql.front().map(|t| t.to_uppercase()).as_deref() != Some("GROUP") {
+        return Ok(Vec::new());
+    }
+    sql.pop_front(); // Consume "GROUP"
+    expect_token(sql, "BY", "'GROUP' must be followed by 'BY'.")?;
+
+    let clause = take_until(sql, &["HAVING", "ORDER"]).join(" ");
+    clause
+        .split(',')
+        .map(
+            |column| match column.split_whitespace().collect::<Vec<_>>()[..] {
+                [column] => parse_column_ref(column),
+                _ => Err(errors::Error::Syntax(format!(
+                    "Invalid GROUP BY column: {}.",
+                    column.trim()
+                ))),
+            },
+        )
+        .collect()
+}
+
+/// Parses an optional `HAVING <predicate>` clause.
+///
+/// # Arguments
+/// * `sql` - A mutable `VecDeque<String>` of SQL tokens.
+///
+/// # Returns
+/// A `Result` containing the predicate, `None` without `HAVING`, or an `errors::Error`.
+fn parse_having(sql: &mut VecDeque<String>) -> Result<Option<Expression>, errors::Error> {
+    if sql.front().map(|t| t.to_uppercase()).as_deref() != Some("HAVING") {
+        return Ok(None);
+    }
+    sql.pop_front(); // Consume "HAVING"
+    let clause = take_until(sql, &["ORDER"]).join(" ");
+    let mut tokens = tokenizer::tokenize_expression(&clause)?;
+    if tokens.is_empty() {
+        return Err(errors::Error::Syntax(
+            "HAVING clause cannot be empty.".to_owned(),
+        ));
+    }
+    let predicate = parse_expression(&mut tokens)?;
+    if let Some(token) = tokens.pop_front() {
+        return Err(errors::Error::Syntax(format!(
+            "Unexpected token in HAVING clause: {:?}.",
+            token
+        )));
+    }
+    Ok(Some(predicate))
+}
+
+/// Removes and returns the tokens up to the first keyword token matching any of
+/// `keywords`.
+///
+/// Text literals are single tokens, so a literal such as `'order by'` never ends the
+/// clause.
+///
+/// # Arguments
+/// * `tokens` - The token queue.
+/// * `keywords` - Upper-case keywords that start the next clause.
+fn take_until(tokens: &mut VecDeque<String>, keywords: &[&str]) -> Vec<String> {
+    let end = tokens
+        .iter()
+        .position(|t| !t.starts_with('\'') && keywords.contains(&t.to_uppercase().as_str()))
+        .unwrap_or(tokens.len());
+    tokens.drain(..end).collect()
+}
+
+/// Parses an optional `ORDER BY column [ASC|DESC] [NULLS FIRST|LAST], ...` clause.
+///
+/// # Arguments
+/// * `sql` - A mutable `VecDeque<String>` of SQL tokens.
+///
+/// # Returns
+/// A `Result` containing the sort keys in order, empty if there is no `ORDER BY`.
+fn parse_order_by(sql: &mut VecDeque<String>) -> Result<Vec<OrderByKey>, errors::Error> {
+    if sql.front().map(|t| t.to_uppercase()).as_deref() != Some("ORDER") {
+        return Ok(Vec::new());
+    }
+    sql.pop_front(); // Consume "ORDER"
+    expect_token(sql, "BY", "'ORDER' must be followed by 'BY'.")?;
+
+    let clause = sql.drain(..).collect::<Vec<_>>().join(" ");
+    let mut keys = Vec::new();
+    for item in clause.split(',') {
+        let parts: Vec<String> = item.split_whitespace().map(|p| p.to_uppercase()).collect();
+        let column = item.split_whitespace().next().ok_or_else(|| {
+            errors::Error::Syntax("'ORDER BY' must be followed by a column.".to_owned())
+        })?;
+        let (ascending, rest) = match parts.get(1).map(String::as_str) {
+            Some("ASC") => (true, &parts[2..]),
+            Some("DESC") => (false, &parts[2..]),
+            _ => (true, &parts[1..]),
+        };
+        let nulls = match rest {
+            [] if ascending => NullsOrder::First,
+            [] => NullsOrder::Last,
+            [nulls, first] if nulls == "NULLS" && first == "FIRST" => NullsOrder::First,
+            [nulls, last] if nulls == "NULLS" && last == "LAST" => NullsOrder::Last,
+            _ => {
+                return Err(errors::Error::Syntax(format!(
+                    "Invalid ORDER BY key: {}.",
+                    item.trim()
+                )))
+            }
+        };
+        keys.push(OrderByKey {
+            column: parse_column_ref(column)?,
+            ascending,
+            nulls,
+        });
+    }
+    Ok(keys)
+}
+
+/// Parses an `OPTIMIZE TABLE` or `VACUUM [TABLE]` statement from tokenized SQL.
+///
+/// # Arguments
+/// * `tokens` - A mutable `VecDeque<String>` of SQL tokens.
+/// * `keyword` - The leading keyword, already consumed.
+///
+/// # Returns
+/// A `Result` containing the parsed `OptimizeStatement` or an `errors::Error`.
+fn parse_optimize(
+    tokens: &mut VecDeque<String>,
+    keyword: &str,
+) -> Result<OptimizeStatement, errors::Error> {
+    let has_table = tokens.front().map(|t| t.to_uppercase()).as_deref() == Some("TABLE");
+    if has_table {
+        tokens.pop_front();
+    } else if keyword == "OPTIMIZE" {
+        return Err(errors::Error::Syntax(
+            "Expected TABLE after OPTIMIZE.".to_owned(),
+        ));
+    }
+    let message = format!("'{} TABLE' must be followed by a table name.", keyword);
+    let name = pop_token(tokens, &message)?;
+    Ok(OptimizeStatement { name })
+}
+
+/// Terminators accepted at the end of a statement, as in the MySQL client.
+const TERMINATORS: [&str; 3] = [";", "\\g", "\\G"];
+
 /// Parses a full SQL statement.
 ///
 /// # Arguments
@@ -501,11 +1272,16 @@ fn parse_where_clause(sql: &mut VecDeque<String>) -> Result<Option<String>, erro
 /// # Returns
 /// A `Result` containing the parsed `SqlCommand` or an `errors::Error`.
 pub fn parse(raw_sql: String) -> Result<SqlCommand, errors::Error> {
-    let mut tokens = tokenizer::tokenize_sql(raw_sql.strip_suffix(';').unwrap_or(&raw_sql))?;
+    let body = raw_sql.trim_end();
+    let body = TERMINATORS
+        .iter()
+        .find_map(|t| body.strip_suffix(t))
+        .unwrap_or(body);
+    let mut tokens = tokenizer::tokenize_sql(body)?;
 
     let first = pop_token(&mut tokens, "SQL statement cannot be empty.")?.to_uppercase();
-    let statement = match first.as_str() {
-        "SELECT" => Statement::Select(parse_select(&mut tokens)?),
+    let mut statement = match first.as_str() {
+        "SELECT" => Statement::Select(Box::new(parse_select(&mut tokens)?)),
         "INSERT" => Statement::Insert(parse_insert(&mut tokens)?),
         "UPDATE" => Statement::Update(parse_update(&mut tokens)?),
         "DELETE" => Statement::Delete(parse_delete(&mut tokens)?),
@@ -513,6 +1289,11 @@ pub fn parse(raw_sql: String) -> Result<SqlCommand, errors::Error> {
         "DROP" => Statement::Drop(parse_drop(&mut tokens)?),
         "SHOW" => Statement::Show(parse_show(&mut tokens)?),
         "DESCRIBE" => Statement::Describe(parse_describe(&mut tokens)?),
+        "TRUNCATE" => Statement::Truncate(parse_truncate(&mut tokens)?),
+        "OPTIMIZE" | "VACUUM" => Statement::Optimize(parse_optimize(&mut tokens, &first)?),
+        "ALTER" => Statement::Alter(parse_alter(&mut tokens)?),
+        "RENAME" => Statement::Alter(parse_rename(&mut tokens)?),
+        "SET" | "PRAGMA" => Statement::Set(parse_set(&mut tokens)?),
         _ => {
             return Err(errors::Error::Syntax(format!(
                 "Unrecognized statement: {}.",
@@ -525,8 +1306,213 @@ pub fn parse(raw_sql: String) -> Result<SqlCommand, errors::Error> {
             "Unexpected tokens after statement.".to_owned(),
         ));
     }
+    let database = take_database(&mut statement)?;
     Ok(SqlCommand {
         statement,
         sql: raw_sql,
+        database,
     })
 }
+
+/// Splits `database.table` qualified table names of a statement, leaving the bare
+/// table names in the statement.
+///
+/// All tables of a statement must be in the same database, so either every name is
+/// qualified with the same database or none is.
+///
+/// # Arguments
+/// * `statement` - The parsed statement.
+///
+/// # Returns
+/// A `Result` containing the qualifying database, `None` for unqualified names, or an
+/// `errors::Error::Syntax`.
+fn take_database(statement: &mut Statement) -> Result<Option<String>, errors::Error> {
+    let mut names = statement.table_names_mut();
+    let databases = names
+        .iter()
+        .map(|name| match name.split_once('.') {
+            Some((database, table))
+                if !database.is_empty() && !table.is_empty() && !table.contains('.') =>
+            {
+                Ok(Some(database.to_string()))
+            }
+            Some(_) => Err(errors::Error::Syntax(format!(
+                "Invalid table name: {}.",
+                name
+            ))),
+            None => Ok(None),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let database = match databases.split_first() {
+        None => return Ok(None),
+        Some((first, rest)) if rest.iter().all(|d| d == first) => first.clone(),
+        Some(_) => {
+            return Err(errors::Error::Syntax(
+                "All tables of a statement must be in the same database.".to_owned(),
+            ))
+        }
+    };
+    if database.is_some() {
+        for name in names.iter_mut() {
+            let table = name.split_once('.').map(|(_, t)| t.to_string());
+            **name = table.unwrap_or_default();
+        }
+    }
+    Ok(database)
+}
+
+/// Parses a buffer holding one or more `;`-separated SQL statements.
+///
+/// # Arguments
+/// * `raw_sql` - The raw SQL buffer to parse.
+///
+/// # Returns
+/// A `Result` containing the parsed `SqlCommand`s in order or the first `errors::Error`.
+pub fn parse_many(raw_sql: &str) -> Result<Vec<SqlCommand>, errors::Error> {
+    tokenizer::split_statements(raw_sql)
+        .into_iter()
+        .map(parse)
+        .collect()
+}
+
+/// Looks up the schema of a table by name.
+pub type SchemaLookup<'a> = &'a dyn Fn(&str) -> Option<TableSchema>;
+
+/// Parses an SQL statement and runs semantic checks on it without executing it.
+///
+/// When a `schema` lookup is given, the tables and columns referenced by the statement
+/// must resolve against it.
+///
+/// # Arguments
+/// * `raw_sql` - The raw SQL string to check.
+/// * `schema` - Optional lookup returning the schema of a table by name.
+///
+/// # Returns
+/// `Ok(())` if the statement is valid, an `errors::Error::Syntax` for parse errors or an
+/// `errors::Error::Semantic` for unresolvable tables or columns.
+pub fn validate(raw_sql: &str, schema: Option<SchemaLookup>) -> Result<(), errors::Error> {
+    let command = parse(raw_sql.to_string())?;
+    // The lookup only knows the tables of the current database
+    let Some(schema) = schema.filter(|_| command.database.is_none()) else {
+        return Ok(());
+    };
+    let resolve_table = |name: &String| {
+        schema(name)
+            .map(|s| (name.clone(), s))
+            .ok_or_else(|| errors::Error::Semantic(format!("Table '{}' doesn't exist", name)))
+    };
+
+    match &command.statement {
+        Statement::Select(stmt) => {
+            let mut tables = Vec::new();
+            if let Some(table) = &stmt.table {
+                tables.push(resolve_table(table)?);
+            }
+            if let Some(join) = &stmt.join {
+                tables.push(resolve_table(&join.table)?);
+            }
+            let mut refs: Vec<&ColumnRef> = Vec::new();
+            if let Columns::List(exprs) = &stmt.columns {
+                refs.extend(exprs.iter().flat_map(|e| e.column_refs()));
+            }
+            if let Some(join) = &stmt.join {
+                refs.extend([&join.left, &join.right]);
+            }
+            if let Some(where_clause) = &stmt.where_clause {
+                refs.extend(where_clause.column_refs());
+            }
+            refs.extend(&stmt.group_by);
+            if let Some(having) = &stmt.having {
+                refs.extend(having.column_refs());
+            }
+            refs.extend(stmt.order_by.iter().map(|key| &key.column));
+            refs.into_iter()
+                .try_for_each(|column| resolve_column(column, &tables))
+        }
+        Statement::Insert(stmt) => {
+            let tables = [resolve_table(&stmt.table)?];
+            stmt.columns.iter().try_for_each(|name| {
+                resolve_column(
+                    &ColumnRef {
+                        table: None,
+                        name: name.clone(),
+                    },
+                    &tables,
+                )
+            })
+        }
+        Statement::Update(stmt) => {
+            let tables = [resolve_table(&stmt.table)?];
+            stmt.sets
+                .iter()
+                .map(|(name, _)| ColumnRef {
+                    table: None,
+                    name: name.clone(),
+                })
+                .collect::<Vec<_>>()
+                .iter()
+                .chain(stmt.where_clause.iter().flat_map(|w| w.column_refs()))
+                .try_for_each(|column| resolve_column(column, &tables))
+        }
+        Statement::Delete(stmt) => {
+            let tables = [resolve_table(&stmt.table)?];
+            stmt.where_clause
+                .iter()
+                .flat_map(|w| w.column_refs())
+                .try_for_each(|column| resolve_column(column, &tables))
+        }
+        Statement::Describe(DescribeStatement { name })
+        | Statement::Show(ShowStatement::ShowColumnsStatement(name))
+        | Statement::Show(ShowStatement::ShowIndexStatement(name))
+        | Statement::Truncate(TruncateStatement { name })
+        | Statement::Optimize(OptimizeStatement { name })
+        | Statement::Drop(DropStatement::DropTablesStatement(name)) => {
+            resolve_table(name).map(|_| ())
+        }
+        Statement::Alter(AlterStatement::RenameTableStatement { name, new_name }) => {
+            resolve_table(name)?;
+            match schema(new_name) {
+                Some(_) => Err(errors::Error::Semantic(format!(
+                    "Table '{}' already exists",
+                    new_name
+                ))),
+                None => Ok(()),
+            }
+        }
+        Statement::Alter(AlterStatement::RenameColumnStatement { name, column, .. }) => {
+            let column = ColumnRef {
+                table: None,
+                name: column.clone(),
+            };
+            resolve_column(&column, &[resolve_table(name)?])
+        }
+        Statement::Create(_) | Statement::Drop(_) | Statement::Show(_) | Statement::Set(_) => {
+            Ok(())
+        }
+    }
+}
+
+/// Checks that a column reference resolves to exactly one of the given tables.
+fn resolve_column(
+    column: &ColumnRef,
+    tables: &[(String, TableSchema)],
+) -> Result<(), errors::Error> {
+    let has_column = |schema: &TableSchema| schema.columns().iter().any(|c| c.name == column.name);
+    let matches = tables
+        .iter()
+        .filter(|(name, schema)| {
+            column.table.as_ref().is_none_or(|table| table == name) && has_column(schema)
+        })
+        .count();
+    match matches {
+        0 => Err(errors::Error::Semantic(format!(
+            "Unknown column '{}' in field list",
+            column
+        ))),
+        1 => Ok(()),
+        _ => Err(errors::Error::Semantic(format!(
+            "Column '{}' in field list is ambiguous",
+            column
+        ))),
+    }
+}
@@ -1,37 +1,67 @@
+use crate::storage::column::ColumnType;
 use crate::storage::schema::ColumnSchema;
+use std::fmt;
 
 /// Represents an SQL command with its parsed statement and original SQL string.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct SqlCommand {
     pub statement: Statement,
     pub sql: String,
+    /// Database named by the `database.table` qualified table names of the statement,
+    /// which then hold the bare table names.
+    pub database: Option<String>,
 }
 
 /// Variants of `CREATE` statements.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum CreateStatement {
     CreateDatabaseStatement(CreateDatabaseStatement),
     CreateTableStatement(CreateTableStatement),
 }
 
 /// Variants of `DROP` statements.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum DropStatement {
     DropDatabasesStatement(String),
     DropTablesStatement(String),
 }
 
+/// Variants of `ALTER TABLE` and `RENAME TABLE` statements.
+#[derive(Debug, PartialEq)]
+pub enum AlterStatement {
+    RenameTableStatement {
+        name: String,
+        new_name: String,
+    },
+    RenameColumnStatement {
+        name: String,
+        column: String,
+        new_column: String,
+        /// Column type given by `CHANGE`, which must match the current type.
+        type_: Option<ColumnType>,
+    },
+}
+
 /// Variants of `SHOW` statements.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum ShowStatement {
-    ShowDatabasesStatement,
-    ShowTablesStatement,
+    /// Lists databases, optionally filtered by a `LIKE` pattern.
+    ShowDatabasesStatement(Option<String>),
+    /// Lists tables of the current database, optionally filtered by a `LIKE` pattern.
+    ShowTablesStatement(Option<String>),
+    ShowVariablesStatement,
+    ShowColumnsStatement(String),
+    ShowIndexStatement(String),
+    /// Lists the size of the tables of the current database, optionally filtered by a
+    /// `LIKE` pattern.
+    ShowTableStatusStatement(Option<String>),
 }
 
 /// Core SQL statement types supported by the parser.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Statement {
-    Select(SelectStatement),
+    /// Boxed, as `SELECT` is by far the largest statement.
+    Select(Box<SelectStatement>),
     Insert(InsertStatement),
     Update(UpdateStatement),
     Delete(DeleteStatement),
@@ -39,67 +69,419 @@ pub enum Statement {
     Drop(DropStatement),
     Show(ShowStatement),
     Describe(DescribeStatement),
+    Truncate(TruncateStatement),
+    Optimize(OptimizeStatement),
+    Alter(AlterStatement),
+    Set(SetStatement),
+}
+
+impl Statement {
+    /// Returns the names of the tables the statement refers to, for resolving
+    /// `database.table` qualified names.
+    pub fn table_names_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Statement::Select(stmt) => stmt
+                .table
+                .iter_mut()
+                .chain(stmt.join.as_mut().map(|join| &mut join.table))
+                .collect(),
+            Statement::Insert(InsertStatement { table, .. })
+            | Statement::Update(UpdateStatement { table, .. })
+            | Statement::Delete(DeleteStatement { table, .. })
+            | Statement::Create(CreateStatement::CreateTableStatement(CreateTableStatement {
+                name: table,
+                ..
+            }))
+            | Statement::Drop(DropStatement::DropTablesStatement(table))
+            | Statement::Show(ShowStatement::ShowColumnsStatement(table))
+            | Statement::Show(ShowStatement::ShowIndexStatement(table))
+            | Statement::Describe(DescribeStatement { name: table })
+            | Statement::Truncate(TruncateStatement { name: table })
+            | Statement::Optimize(OptimizeStatement { name: table })
+            | Statement::Alter(AlterStatement::RenameColumnStatement { name: table, .. }) => {
+                vec![table]
+            }
+            Statement::Alter(AlterStatement::RenameTableStatement { name, new_name }) => {
+                vec![name, new_name]
+            }
+            Statement::Create(CreateStatement::CreateDatabaseStatement(_))
+            | Statement::Drop(DropStatement::DropDatabasesStatement(_))
+            | Statement::Show(_)
+            | Statement::Set(_) => Vec::new(),
+        }
+    }
 }
 
 /// SQL clauses (currently unused but included for future expansion).
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Clause {
     Join,
     Where,
 }
 
 /// Represents an `INSERT` statement with table, columns, and values.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct InsertStatement {
     pub table: String,
     pub columns: Vec<String>,
-    pub values: Vec<String>,
+    pub values: Vec<InsertValue>,
+}
+
+/// A value of an `INSERT` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertValue {
+    /// A literal, with its quotes removed.
+    Literal(String),
+    /// `NOW()` or `CURRENT_TIMESTAMP`, evaluated when the statement is executed.
+    Now,
+    /// An unquoted `NULL`.
+    Null,
 }
 
 /// Represents column selection in a `SELECT` statement.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Columns {
     All,
-    List(Vec<String>),
+    List(Vec<Expression>),
 }
 
-/// Represents a `SELECT` statement with table and columns.
-#[derive(Debug)]
+/// Represents a `SELECT` statement with table, columns, an optional join and WHERE clause.
+///
+/// The table is `None` for constant queries without `FROM` (e.g. `SELECT 1`).
+#[derive(Debug, PartialEq)]
 pub struct SelectStatement {
-    pub table: String,
+    pub table: Option<String>,
     pub columns: Columns,
+    pub join: Option<JoinClause>,
+    pub where_clause: Option<Expression>,
+    /// Columns of `GROUP BY`; empty when the rows are not grouped.
+    pub group_by: Vec<ColumnRef>,
+    /// Condition on the groups, which may use aggregates.
+    pub having: Option<Expression>,
+    pub order_by: Vec<OrderByKey>,
+    /// Locking read requested by a trailing `FOR UPDATE` or `LOCK IN SHARE MODE`.
+    ///
+    /// Accepted for client compatibility; the table mutex already serializes access.
+    pub lock: Option<LockMode>,
+}
+
+/// Row lock requested by a locking `SELECT`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LockMode {
+    /// `FOR UPDATE`
+    Exclusive,
+    /// `LOCK IN SHARE MODE`
+    Shared,
+}
+
+/// Placement of missing (`NULL`) values in an `ORDER BY` key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+/// A sort key of an `ORDER BY` clause.
+///
+/// Without `NULLS FIRST`/`NULLS LAST`, `NULL` sorts as the smallest value: first in
+/// ascending and last in descending order.
+#[derive(Debug, PartialEq)]
+pub struct OrderByKey {
+    pub column: ColumnRef,
+    pub ascending: bool,
+    pub nulls: NullsOrder,
+}
+
+/// Represents an `INNER JOIN` of a second table on an equality of two columns.
+#[derive(Debug, PartialEq)]
+pub struct JoinClause {
+    pub table: String,
+    pub left: ColumnRef,
+    pub right: ColumnRef,
 }
 
 /// Represents a `CREATE DATABASE` statement.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct CreateDatabaseStatement {
     pub name: String,
 }
 
 /// Represents a `CREATE TABLE` statement with table name and column schemas.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct CreateTableStatement {
     pub name: String,
     pub columns_schemas: Vec<ColumnSchema>,
 }
 
 /// Represents a `DESCRIBE` table statement with table name.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct DescribeStatement {
     pub name: String,
 }
 
+/// Represents a `SET name = value` statement for a session setting.
+#[derive(Debug, PartialEq)]
+pub struct SetStatement {
+    pub name: String,
+    pub value: String,
+}
+
+/// Represents a `TRUNCATE TABLE` statement with table name.
+#[derive(Debug, PartialEq)]
+pub struct TruncateStatement {
+    pub name: String,
+}
+
+/// Represents an `OPTIMIZE TABLE` (or `VACUUM`) statement with table name.
+#[derive(Debug, PartialEq)]
+pub struct OptimizeStatement {
+    pub name: String,
+}
+
 /// Represents a `DELETE` statement with table and optional WHERE clause.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct DeleteStatement {
     pub table: String,
-    pub where_clause: Option<String>,
+    pub where_clause: Option<Expression>,
 }
 
 /// Represents an `UPDATE` statement with table, column-value pairs, and optional WHERE clause.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct UpdateStatement {
     pub table: String,
     pub sets: Vec<(String, String)>,
-    pub where_clause: Option<String>,
+    pub where_clause: Option<Expression>,
+}
+
+/// Binary operators supported in expressions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    And,
+    Or,
+}
+
+/// Represents a literal value in an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    /// Numeric literal, kept as written (e.g. `1`, `2.5`).
+    Number(String),
+    /// Quoted text literal without the surrounding quotes.
+    Text(String),
+}
+
+/// Reference to a column, optionally qualified by its table (e.g. `users.id`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnRef {
+    pub table: Option<String>,
+    pub name: String,
+}
+
+/// Represents an expression, e.g. an item of a `SELECT` column list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Column(ColumnRef),
+    Literal(Literal),
+    Binary {
+        left: Box<Expression>,
+        operator: Operator,
+        right: Box<Expression>,
+    },
+    /// An aggregate over the rows of a result; the argument is `None` for `COUNT(*)`.
+    Aggregate {
+        function: Aggregate,
+        argument: Option<Box<Expression>>,
+    },
+}
+
+/// Aggregate functions computed over a set of rows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    /// Parses an aggregate function name, case-insensitively.
+    pub fn from_sql(name: &str) -> Option<Self> {
+        match name.to_uppercase().as_str() {
+            "COUNT" => Some(Aggregate::Count),
+            "SUM" => Some(Aggregate::Sum),
+            "AVG" => Some(Aggregate::Avg),
+            "MIN" => Some(Aggregate::Min),
+            "MAX" => Some(Aggregate::Max),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Aggregate::Count => write!(f, "COUNT"),
+            Aggregate::Sum => write!(f, "SUM"),
+            Aggregate::Avg => write!(f, "AVG"),
+            Aggregate::Min => write!(f, "MIN"),
+            Aggregate::Max => write!(f, "MAX"),
+        }
+    }
+}
+
+impl Operator {
+    /// Returns the binding strength of the operator, higher binds tighter.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Operator::Or => 1,
+            Operator::And => 2,
+            Operator::Equal
+            | Operator::NotEqual
+            | Operator::Less
+            | Operator::LessOrEqual
+            | Operator::Greater
+            | Operator::GreaterOrEqual => 3,
+            Operator::Add | Operator::Subtract => 4,
+            Operator::Multiply | Operator::Divide => 5,
+        }
+    }
+
+    /// Returns whether the operator compares two values.
+    pub fn is_comparison(&self) -> bool {
+        self.precedence() == 3
+    }
+
+    /// Parses an operator from its SQL spelling, e.g. `<=` or `AND`.
+    pub fn from_sql(sql: &str) -> Option<Self> {
+        match sql.to_uppercase().as_str() {
+            "+" => Some(Operator::Add),
+            "-" => Some(Operator::Subtract),
+            "*" => Some(Operator::Multiply),
+            "/" => Some(Operator::Divide),
+            "=" => Some(Operator::Equal),
+            "!=" | "<>" => Some(Operator::NotEqual),
+            "<" => Some(Operator::Less),
+            "<=" => Some(Operator::LessOrEqual),
+            ">" => Some(Operator::Greater),
+            ">=" => Some(Operator::GreaterOrEqual),
+            "AND" => Some(Operator::And),
+            "OR" => Some(Operator::Or),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operator::Add => write!(f, "+"),
+            Operator::Subtract => write!(f, "-"),
+            Operator::Multiply => write!(f, "*"),
+            Operator::Divide => write!(f, "/"),
+            Operator::Equal => write!(f, "="),
+            Operator::NotEqual => write!(f, "!="),
+            Operator::Less => write!(f, "<"),
+            Operator::LessOrEqual => write!(f, "<="),
+            Operator::Greater => write!(f, ">"),
+            Operator::GreaterOrEqual => write!(f, ">="),
+            Operator::And => write!(f, "AND"),
+            Operator::Or => write!(f, "OR"),
+        }
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Number(n) => write!(f, "{}", n),
+            Literal::Text(s) => write!(f, "'{}'", s.replace('\'', "''")),
+        }
+    }
+}
+
+impl Expression {
+    /// Returns all column references of the expression, in order of appearance.
+    pub fn column_refs(&self) -> Vec<&ColumnRef> {
+        match self {
+            Expression::Column(column) => vec![column],
+            Expression::Literal(_) => Vec::new(),
+            Expression::Binary { left, right, .. } => {
+                let mut refs = left.column_refs();
+                refs.extend(right.column_refs());
+                refs
+            }
+            Expression::Aggregate { argument, .. } => argument
+                .as_ref()
+                .map(|argument| argument.column_refs())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns whether the expression contains an aggregate function.
+    pub fn has_aggregate(&self) -> bool {
+        match self {
+            Expression::Column(_) | Expression::Literal(_) => false,
+            Expression::Binary { left, right, .. } => left.has_aggregate() || right.has_aggregate(),
+            Expression::Aggregate { .. } => true,
+        }
+    }
+}
+
+impl fmt::Display for ColumnRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.table {
+            Some(table) => write!(f, "{}.{}", table, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    /// Formats the expression as SQL text; used for result set headers.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Column(column) => write!(f, "{}", column),
+            Expression::Literal(literal) => write!(f, "{}", literal),
+            Expression::Aggregate { function, argument } => match argument {
+                Some(argument) => write!(f, "{}({})", function, argument),
+                None => write!(f, "{}(*)", function),
+            },
+            Expression::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let needs_parens = |e: &Expression, strict: bool| match e {
+                    Expression::Binary {
+                        operator: inner, ..
+                    } => {
+                        inner.precedence() < operator.precedence()
+                            || (strict && inner.precedence() == operator.precedence())
+                    }
+                    _ => false,
+                };
+                if needs_parens(left, false) {
+                    write!(f, "({})", left)?;
+                } else {
+                    write!(f, "{}", left)?;
+                }
+                write!(f, " {} ", operator)?;
+                if needs_parens(right, true) {
+                    write!(f, "({})", right)
+                } else {
+                    write!(f, "{}", right)
+                }
+            }
+        }
+    }
 }
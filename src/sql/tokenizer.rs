@@ -1,6 +1,61 @@
 use crate::errors;
 use std::collections::VecDeque;
 
+/// Splits a buffer into `;`-terminated statements, ignoring semicolons inside quotes.
+///
+/// Comments outside quotes (`-- ...`, `# ...` and `/* ... */`) are dropped.
+///
+/// # Arguments
+/// * `sql` - The raw SQL buffer to split.
+///
+/// # Returns
+/// A `Vec<String>` of non-empty statements, each keeping its terminating `;`.
+pub fn split_statements(sql: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut inside_text = false;
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '-' if !inside_text && chars.peek() == Some(&'-') => {
+                chars.by_ref().find(|&c| c == '\n');
+                current.push('\n');
+            }
+            '#' if !inside_text => {
+                chars.by_ref().find(|&c| c == '\n');
+                current.push('\n');
+            }
+            '/' if !inside_text && chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                chars.by_ref().find(|&c| {
+                    let closed = prev == '*' && c == '/';
+                    prev = c;
+                    closed
+                });
+                current.push(' ');
+            }
+            '\'' => {
+                inside_text = !inside_text;
+                current.push(c);
+            }
+            ';' if !inside_text => {
+                current.push(c);
+                if current.trim() != ";" {
+                    result.push(current.trim().to_string());
+                }
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.trim().is_empty() {
+        result.push(current.trim().to_string());
+    }
+    result
+}
+
 /// Splits an SQL statement into tokens, respecting spaces inside parentheses and quotes.
 ///
 /// # Arguments
@@ -11,43 +66,39 @@ use std::collections::VecDeque;
 pub fn tokenize_sql(sql: &str) -> Result<VecDeque<String>, errors::Error> {
     let mut result = VecDeque::new();
     let mut current = String::new();
-    let mut inside_parens = false;
+    // Nesting depth of parentheses, e.g. `(name VARCHAR(32))` is a single token.
+    let mut depth = 0usize;
     let mut inside_text = false;
 
     for c in sql.chars() {
         match c {
+            // An escaped quote (`''`) toggles twice and stays inside the literal
             '\'' => {
                 inside_text = !inside_text;
                 current.push(c);
             }
             ')' => {
                 if !inside_text {
-                    if !inside_parens {
-                        return Err(errors::Error::Syntax(
-                            "Unmatched closing parenthesis.".to_owned(),
-                        ));
-                    }
-                    inside_parens = false;
+                    depth = depth.checked_sub(1).ok_or_else(|| {
+                        errors::Error::Syntax("Unmatched closing parenthesis.".to_owned())
+                    })?;
                 }
                 current.push(c);
             }
             '(' => {
                 if !inside_text {
-                    if inside_parens {
-                        return Err(errors::Error::Syntax(
-                            "Nested opening parenthesis.".to_owned(),
-                        ));
-                    }
-                    inside_parens = true;
+                    depth += 1;
                 }
                 current.push(c);
             }
-            ' ' if !inside_parens && !inside_text => {
+            c if c.is_ascii_whitespace() && depth == 0 && !inside_text => {
                 if !current.is_empty() {
                     result.push_back(current);
                     current = String::new();
                 }
             }
+            // Line breaks and tabs inside parentheses read as plain spaces
+            c if c.is_ascii_whitespace() && !inside_text => current.push(' '),
             _ => current.push(c),
         }
     }
@@ -55,7 +106,7 @@ pub fn tokenize_sql(sql: &str) -> Result<VecDeque<String>, errors::Error> {
     if !current.is_empty() {
         result.push_back(current);
     }
-    if inside_parens {
+    if depth > 0 {
         return Err(errors::Error::Syntax(
             "Missing closing parenthesis.".to_owned(),
         ));
@@ -66,3 +117,117 @@ pub fn tokenize_sql(sql: &str) -> Result<VecDeque<String>, errors::Error> {
 
     Ok(result)
 }
+
+/// Tokens of an SQL expression, e.g. a `SELECT` column list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionToken {
+    Identifier(String),
+    Number(String),
+    Text(String),
+    Operator(String),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+/// Splits an SQL expression into tokens.
+///
+/// # Arguments
+/// * `sql` - The raw expression string to split.
+///
+/// # Returns
+/// A `Result` containing a `VecDeque<ExpressionToken>` or an `errors::Error` on an unexpected character.
+pub fn tokenize_expression(sql: &str) -> Result<VecDeque<ExpressionToken>, errors::Error> {
+    let mut result = VecDeque::new();
+    let mut chars = sql.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '+' | '-' | '*' | '/' | '=' => {
+                result.push_back(ExpressionToken::Operator(c.to_string()));
+                chars.next();
+            }
+            '<' | '>' | '!' => {
+                chars.next();
+                let mut operator = c.to_string();
+                if let Some(&next) = chars.peek() {
+                    if next == '=' || (c == '<' && next == '>') {
+                        operator.push(next);
+                        chars.next();
+                    }
+                }
+                if operator == "!" {
+                    return Err(errors::Error::Syntax(
+                        "Unexpected character '!' in expression.".to_owned(),
+                    ));
+                }
+                result.push_back(ExpressionToken::Operator(operator));
+            }
+            '(' => {
+                result.push_back(ExpressionToken::LeftParen);
+                chars.next();
+            }
+            ')' => {
+                result.push_back(ExpressionToken::RightParen);
+                chars.next();
+            }
+            ',' => {
+                result.push_back(ExpressionToken::Comma);
+                chars.next();
+            }
+            '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        // A doubled quote is an escaped quote, not the end of the text
+                        Some('\'') if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                            text.push('\'');
+                        }
+                        Some('\'') => break,
+                        Some(c) => text.push(c),
+                        None => {
+                            return Err(errors::Error::Syntax("Unclosed text literal.".to_owned()))
+                        }
+                    }
+                }
+                result.push_back(ExpressionToken::Text(text));
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_digit() || c == '.') {
+                        break;
+                    }
+                    number.push(c);
+                    chars.next();
+                }
+                result.push_back(ExpressionToken::Number(number));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                // Qualified names (`table.column`) are kept as a single identifier.
+                let mut ident = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '.') {
+                        break;
+                    }
+                    ident.push(c);
+                    chars.next();
+                }
+                result.push_back(ExpressionToken::Identifier(ident));
+            }
+            _ => {
+                return Err(errors::Error::Syntax(format!(
+                    "Unexpected character '{}' in expression.",
+                    c
+                )))
+            }
+        }
+    }
+
+    Ok(result)
+}
@@ -4,7 +4,7 @@ use regex::Regex;
 
 lazy_static! {
     static ref COLUMN_REGEX: Regex = Regex::new(r#"[A-Za-z_][A-Za-z0-9_]*"#).unwrap();
-    static ref TYPE_REGEX: Regex = Regex::new(r#"(?i)(INT|SMALLINT|TINYINT|BIGINT|FLOAT|DOUBLE|VARCHAR\(\d+\)|TEXT|DATETIME|TIMESTAMP|BOOLEAN)"#).unwrap();
+    static ref TYPE_REGEX: Regex = Regex::new(r#"(?i)(INT|SMALLINT|TINYINT|BIGINT|FLOAT|DOUBLE|VARCHAR\(\d+\)|CHAR\(\d+\)|DECIMAL(\(\d+(,\d+)?\))?|TEXT|DATETIME|TIMESTAMP|BOOLEAN)"#).unwrap();
 }
 
 /// Validates a column name against the regex.
@@ -31,6 +31,30 @@ pub fn validate_column_name(name: &str) -> Result<String, errors::Error> {
     }
 }
 
+/// Validates that an `INSERT` gives one value per listed column.
+///
+/// # Arguments
+/// * `table` - The table inserted into, named in the error.
+/// * `columns` - The inserted column names, listed in the error.
+/// * `values` - The number of inserted values.
+///
+/// # Returns
+/// An `errors::Error::Syntax` naming the table and the listed columns if the counts differ.
+pub fn validate_value_count(
+    table: &str,
+    columns: &[String],
+    values: usize,
+) -> Result<(), errors::Error> {
+    if columns.len() == values {
+        return Ok(());
+    }
+    Err(errors::Error::Syntax(format!(
+        "Column count doesn't match value count: table '{}' expects ({})",
+        table,
+        columns.join(", ")
+    )))
+}
+
 /// Validates a column type against the regex.
 ///
 /// # Arguments
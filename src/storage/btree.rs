@@ -677,10 +677,10 @@ impl Node {
     /// Returns `Error::Storage` if the key cannot be found or updated.
     pub fn update_internal_node_key(&mut self, old_key: u32, new_key: u32) -> Result<(), Error> {
         let old_child_index = self.internal_node_find_child(old_key)?;
-        // let parent_old_child = parent.internal_node_find_child(old_max)?;
-        // parent.set_internal_node_key(parent_old_child, new_max)?;
-        // drop(parent);
-        //
+        // The right child has no key, its max key is bounded by the parent's instead
+        if old_child_index == self.internal_node_num_keys()? {
+            return Ok(());
+        }
         self.set_internal_node_key(old_child_index, new_key)
     }
 
@@ -778,7 +778,10 @@ impl Node {
 
     /// Returns the index of the child that should contain the given key in this internal node.
     ///
-    /// Uses binary search to find the appropriate child pointer.
+    /// Uses binary search to find the appropriate child pointer. Each key of the node is
+    /// the maximum key of the child to its left, so a key equal to a separator is routed
+    /// to that left child, the one whose max key it is. Keys above every separator go to
+    /// the right child.
     ///
     /// # Arguments
     /// - `key`: The key to locate.
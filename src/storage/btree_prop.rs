@@ -0,0 +1,210 @@
+//! # B-tree Property Tests
+//!
+//! Inserts random permutations of keys into tables of different leaf capacities and
+//! checks the structure of the whole tree after every insert:
+//!
+//! - keys are sorted within each leaf and no node holds more cells than it can fit;
+//! - each internal key equals the max key of the child to its left;
+//! - every child points back to its parent;
+//! - the leaf sibling chain, walked by `Table::keys`, visits all keys in order.
+//!
+//! Half of the keys are then deleted in random order, with the same checks after each.
+//!
+//! The leaf capacity follows from the row size, so it is varied through the schema.
+//! The internal capacity is the compile-time `INTERNAL_NODE_MAX_CELLS`.
+
+use super::btree::{NodeType, INTERNAL_NODE_MAX_CELLS};
+use super::column::ColumnType;
+use super::schema::{ColumnSchema, TableSchema};
+use super::table::{self, Pager, Table};
+use crate::database::Database;
+use std::sync::Arc;
+
+/// A linear congruential generator, so failures reproduce from the seed alone.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    fn permutation(&mut self, n: u32) -> Vec<u32> {
+        let mut keys: Vec<u32> = (1..=n).collect();
+        for i in (1..keys.len()).rev() {
+            keys.swap(i, self.next() as usize % (i + 1));
+        }
+        keys
+    }
+}
+
+/// Builds a schema whose rows carry a `VARCHAR(size)` next to the primary key.
+fn schema(size: u16) -> Arc<TableSchema> {
+    Arc::new(TableSchema::new(vec![
+        ColumnSchema {
+            name: "id".into(),
+            type_: ColumnType::INT,
+            default: None,
+            is_primary: true,
+            is_nullable: false,
+        },
+        ColumnSchema {
+            name: "name".into(),
+            type_: ColumnType::VARCHAR(size),
+            default: None,
+            is_primary: false,
+            is_nullable: false,
+        },
+    ]))
+}
+
+/// Checks the subtree at `page_num` and returns its keys in order.
+fn check_node(pager: &Pager, page_num: u32, parent: Option<u32>) -> Vec<u32> {
+    let node = pager.get(page_num).unwrap();
+    assert_eq!(
+        node.is_node_root().unwrap(),
+        parent.is_none(),
+        "page {}",
+        page_num
+    );
+    if let Some(parent) = parent {
+        assert_eq!(
+            node.node_parent().unwrap(),
+            parent,
+            "parent of page {}",
+            page_num
+        );
+    }
+
+    match node.get_node_type().unwrap() {
+        NodeType::NodeLeaf => {
+            let num_cells = node.leaf_node_num_cells().unwrap() as usize;
+            assert!(num_cells <= node.max_cells(), "page {} overflows", page_num);
+            let keys: Vec<u32> = (0..num_cells)
+                .map(|i| node.leaf_node_key(i).unwrap())
+                .collect();
+            assert!(keys.windows(2).all(|w| w[0] < w[1]), "page {}", page_num);
+            keys
+        }
+        NodeType::NodeInternal => {
+            let num_keys = node.internal_node_num_keys().unwrap();
+            assert!(num_keys >= 1, "page {} has no keys", page_num);
+            assert!(
+                num_keys as usize <= INTERNAL_NODE_MAX_CELLS,
+                "page {}",
+                page_num
+            );
+            let children: Vec<(u32, Option<u32>)> = (0..=num_keys)
+                .map(|i| {
+                    let key = (i < num_keys).then(|| node.internal_node_key(i).unwrap());
+                    (node.internal_node_child(i).unwrap(), key)
+                })
+                .collect();
+            drop(node);
+
+            let mut keys = Vec::new();
+            for (child, separator) in children {
+                let child_keys = check_node(pager, child, Some(page_num));
+                assert!(!child_keys.is_empty(), "page {} is empty", child);
+                if let Some(separator) = separator {
+                    assert_eq!(
+                        child_keys.last(),
+                        Some(&separator),
+                        "separator of page {} in page {}",
+                        child,
+                        page_num
+                    );
+                }
+                if let Some(last) = keys.last() {
+                    assert!(child_keys[0] > *last, "page {} overlaps", child);
+                }
+                keys.extend(child_keys);
+            }
+            keys
+        }
+    }
+}
+
+/// Checks all invariants of the table btree against the keys inserted so far.
+fn check_tree(table: &Table, inserted: &[u32]) {
+    let mut expected = inserted.to_vec();
+    expected.sort();
+    assert_eq!(
+        check_node(&table.pager, table.root_page_num, None),
+        expected
+    );
+    assert_eq!(table.keys().unwrap(), expected);
+}
+
+/// Inserts `keys` in order into a new table with `VARCHAR(size)` rows, then deletes
+/// half of them one by one and half of the rest in one batch, checking the tree after
+/// every change.
+fn run(size: u16, keys: &[u32]) {
+    let (_dir, db) = Database::open_test().unwrap();
+    let schema = schema(size);
+    let mut table =
+        table::create_table(db.path(), &db.name, &"t".to_string(), schema.clone()).unwrap();
+    for (i, &key) in keys.iter().enumerate() {
+        let row = super::build_row(
+            &schema,
+            &["id".into(), "name".into()],
+            &[key.to_string(), format!("name{}", key)],
+        )
+        .unwrap();
+        table::insert_row(&mut table, &row).unwrap();
+        check_tree(&table, &keys[..=i]);
+    }
+
+    let mut remaining = keys.to_vec();
+    let mut rng = Lcg(keys.len() as u64);
+    for _ in 0..keys.len() / 2 {
+        let key = remaining.swap_remove(rng.next() as usize % remaining.len());
+        assert_eq!(
+            table::delete_rows(&mut table, &[key]).unwrap(),
+            1,
+            "key {}",
+            key
+        );
+        assert_eq!(
+            table::delete_rows(&mut table, &[key]).unwrap(),
+            0,
+            "key {}",
+            key
+        );
+        check_tree(&table, &remaining);
+    }
+
+    // Every other remaining key, plus one that is already gone
+    let (batch, rest): (Vec<_>, Vec<_>) =
+        remaining.iter().enumerate().partition(|(i, _)| i % 2 == 0);
+    let mut batch: Vec<u32> = batch.into_iter().map(|(_, &key)| key).collect();
+    let deleted = batch.len() as u64;
+    batch.extend(keys.iter().find(|key| !remaining.contains(key)));
+    assert_eq!(table::delete_rows(&mut table, &batch).unwrap(), deleted);
+    let rest: Vec<u32> = rest.into_iter().map(|(_, &key)| key).collect();
+    check_tree(&table, &rest);
+}
+
+/// Leaves of 3, 12 and about 80 cells, each with enough keys for several levels of
+/// internal nodes within the page limit.
+const CASES: [(u16, u32); 3] = [(1300, 100), (320, 250), (40, 1000)];
+
+#[test]
+fn test_btree_invariants() {
+    for (size, n) in CASES {
+        for seed in 0..4 {
+            run(size, &Lcg(seed).permutation(n));
+        }
+    }
+}
+
+#[test]
+fn test_btree_invariants_sorted_keys() {
+    for (size, n) in CASES {
+        run(size, &(1..=n).collect::<Vec<_>>());
+        run(size, &(1..=n).rev().collect::<Vec<_>>());
+    }
+}
@@ -1,7 +1,9 @@
+use super::datetime;
+use crate::errors::Error;
 use bincode::{Decode, Encode};
 use std::fmt;
 
-#[derive(Debug, Clone)]
+#[derive(Encode, Decode, Debug, Clone, PartialEq)]
 pub enum ColumnType {
     INT,          // i64, equivalent to SQL's BIGINT
     SMALLINT,     // i16
@@ -14,9 +16,12 @@ pub enum ColumnType {
     DATETIME,     // Date and time combined
     TIMESTAMP,    // Date and time with timezone information
     BOOLEAN,      // True/False value
+    // New variants go last, keeping the encoded tags of the others
+    CHAR(u16),       // Fixed-length string, space padded to exactly n bytes
+    DECIMAL(u8, u8), // Exact number with precision and scale, stored scaled as i128
 }
 
-#[derive(Encode, Decode, Debug)]
+#[derive(Encode, Decode, Debug, Clone, PartialEq)]
 pub enum ColumnValue {
     Int(i64),
     SmallInt(i16),
@@ -26,11 +31,17 @@ pub enum ColumnValue {
     Double(f64),
     VarChar(Vec<u8>),
     Text(Vec<u8>),
-    DateTime(Vec<u8>),
-    Timestamp(Vec<u8>),
+    DateTime(i64),  // Seconds since the Unix epoch
+    Timestamp(i64), // Seconds since the Unix epoch
     Boolean(bool),
+    // New variants go last, keeping the encoded tags of the others
+    Char(Vec<u8>),
+    Decimal(i128, u8), // Unscaled value and scale, e.g. (1234, 2) is 12.34
+    Null,              // No value, marked in the NULL bitmap of the encoded row
 }
 
+/// Formats the type as canonical SQL, e.g. `VARCHAR(32)` or `DECIMAL(10,2)`, which
+/// `parse_column_type` reads back as the same type.
 impl fmt::Display for ColumnType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -41,6 +52,8 @@ impl fmt::Display for ColumnType {
             ColumnType::FLOAT => write!(f, "FLOAT"),
             ColumnType::DOUBLE => write!(f, "DOUBLE"),
             ColumnType::VARCHAR(max_length) => write!(f, "VARCHAR({})", max_length),
+            ColumnType::CHAR(length) => write!(f, "CHAR({})", length),
+            ColumnType::DECIMAL(precision, scale) => write!(f, "DECIMAL({},{})", precision, scale),
             ColumnType::TEXT => write!(f, "TEXT"),
             ColumnType::DATETIME => write!(f, "DATETIME"),
             ColumnType::TIMESTAMP => write!(f, "TIMESTAMP"),
@@ -49,6 +62,209 @@ impl fmt::Display for ColumnType {
     }
 }
 
+/// Number of significant digits shown for `FLOAT` and `DOUBLE` values.
+const REAL_SIGNIFICANT_DIGITS: i32 = 6;
+
+/// Formats a floating point value with up to `REAL_SIGNIFICANT_DIGITS` significant digits,
+/// trimming trailing zeros but keeping at least one decimal (e.g. `1.0`, `0.1`, `3.14159`).
+fn format_real(v: f64) -> String {
+    if !v.is_finite() {
+        return v.to_string();
+    }
+    let magnitude = if v == 0.0 {
+        0
+    } else {
+        v.abs().log10().floor() as i32
+    };
+    let decimals = (REAL_SIGNIFICANT_DIGITS - 1 - magnitude).max(1) as usize;
+    let formatted = format!("{:.*}", decimals, v);
+    let trimmed = formatted.trim_end_matches('0');
+    if trimmed.ends_with('.') {
+        format!("{}0", trimmed)
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Largest precision of a `DECIMAL`, the number of digits that always fit in an `i128`.
+pub const DECIMAL_MAX_PRECISION: u8 = 38;
+
+/// Parses a decimal literal into its unscaled value for a `DECIMAL(precision, scale)`.
+///
+/// Extra fractional digits are rounded half away from zero, like MySQL does.
+///
+/// # Arguments
+/// * `value` - The literal, e.g. `-12.345`
+/// * `precision` - Total number of digits the column holds
+/// * `scale` - Number of digits after the decimal point
+///
+/// # Returns
+/// The value multiplied by `10^scale`, or an `Error::Schema` for malformed or
+/// out-of-range values
+pub fn parse_decimal(value: &str, precision: u8, scale: u8) -> Result<i128, Error> {
+    let invalid = || Error::Schema(format!("Invalid DECIMAL: {value}"));
+    let out_of_range = || {
+        Error::Schema(format!(
+            "Value {value} out of range for DECIMAL({precision},{scale})"
+        ))
+    };
+
+    let trimmed = value.trim();
+    let (negative, digits) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+    if int_part.is_empty() && frac_part.is_empty()
+        || !int_part
+            .bytes()
+            .chain(frac_part.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    let mut unscaled: i128 = 0;
+    let kept = frac_part.bytes().chain(std::iter::repeat(b'0'));
+    for digit in int_part.bytes().chain(kept.take(scale as usize)) {
+        unscaled = unscaled
+            .checked_mul(10)
+            .and_then(|v| v.checked_add((digit - b'0') as i128))
+            .ok_or_else(out_of_range)?;
+    }
+    if frac_part
+        .as_bytes()
+        .get(scale as usize)
+        .is_some_and(|&d| d >= b'5')
+    {
+        unscaled = unscaled.checked_add(1).ok_or_else(out_of_range)?;
+    }
+    if unscaled >= 10i128.pow(precision as u32) {
+        return Err(out_of_range());
+    }
+    Ok(if negative { -unscaled } else { unscaled })
+}
+
+/// Formats an unscaled decimal with `scale` digits after the decimal point.
+fn format_decimal(unscaled: i128, scale: u8) -> String {
+    let digits = unscaled.unsigned_abs().to_string();
+    let sign = if unscaled < 0 { "-" } else { "" };
+    if scale == 0 {
+        return format!("{sign}{digits}");
+    }
+    let digits = format!("{:0>width$}", digits, width = scale as usize + 1);
+    let (int_part, frac_part) = digits.split_at(digits.len() - scale as usize);
+    format!("{sign}{int_part}.{frac_part}")
+}
+
+macro_rules! impl_from {
+    ($($type:ty => $variant:ident),* $(,)?) => {
+        $(impl From<$type> for ColumnValue {
+            fn from(value: $type) -> Self {
+                ColumnValue::$variant(value.into())
+            }
+        })*
+    };
+}
+
+// Native conversions for building rows programmatically, see `RowBuilder`.
+impl_from! {
+    i8 => TinyInt,
+    i16 => SmallInt,
+    i32 => Int,
+    i64 => Int,
+    i128 => BigInt,
+    f32 => Float,
+    f64 => Double,
+    bool => Boolean,
+    &str => VarChar,
+    String => VarChar,
+}
+
+/// Returns `bytes` without the trailing NUL padding of fixed-size columns.
+pub fn trim_padding(bytes: &[u8]) -> &[u8] {
+    let len = bytes.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    &bytes[..len]
+}
+
+/// Converts the bytes of a `VARCHAR` or `TEXT` value to a string, dropping NUL padding.
+fn bytes_to_string(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(trim_padding(bytes)).into_owned()
+}
+
+impl ColumnValue {
+    /// Returns whether the value is SQL `NULL`.
+    pub fn is_null(&self) -> bool {
+        matches!(self, ColumnValue::Null)
+    }
+
+    /// Returns the value as an integer, for the integer types and `BOOLEAN`.
+    ///
+    /// # Returns
+    /// `None` for other types, or a `BIGINT` out of the `i64` range.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ColumnValue::Int(v) => Some(*v),
+            ColumnValue::SmallInt(v) => Some(*v as i64),
+            ColumnValue::TinyInt(v) => Some(*v as i64),
+            ColumnValue::BigInt(v) => i64::try_from(*v).ok(),
+            ColumnValue::Boolean(v) => Some(*v as i64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a real number, for the numeric types and `BOOLEAN`.
+    ///
+    /// # Returns
+    /// `None` for strings and dates.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ColumnValue::Int(v) => Some(*v as f64),
+            ColumnValue::SmallInt(v) => Some(*v as f64),
+            ColumnValue::TinyInt(v) => Some(*v as f64),
+            ColumnValue::BigInt(v) => Some(*v as f64),
+            ColumnValue::Float(v) => Some(*v as f64),
+            ColumnValue::Double(v) => Some(*v),
+            ColumnValue::Decimal(v, scale) => Some(*v as f64 / 10f64.powi(*scale as i32)),
+            ColumnValue::Boolean(v) => Some(*v as i64 as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the text of a `VARCHAR`, `CHAR` or `TEXT` value.
+    ///
+    /// # Returns
+    /// `None` for other types.
+    pub fn as_str(&self) -> Option<String> {
+        match self {
+            ColumnValue::VarChar(_) | ColumnValue::Char(_) | ColumnValue::Text(_) => {
+                Some(self.to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// Formats the value as a SQL literal that parses back to the same value, as
+    /// written by `\dump`.
+    ///
+    /// Numbers, booleans and `NULL` are written bare, everything else is single quoted
+    /// with embedded quotes doubled.
+    pub fn to_sql_literal(&self) -> String {
+        match self {
+            ColumnValue::Int(_)
+            | ColumnValue::SmallInt(_)
+            | ColumnValue::TinyInt(_)
+            | ColumnValue::BigInt(_)
+            | ColumnValue::Float(_)
+            | ColumnValue::Double(_)
+            | ColumnValue::Decimal(..)
+            | ColumnValue::Boolean(_)
+            | ColumnValue::Null => self.to_string(),
+            _ => format!("'{}'", self.to_string().replace('\'', "''")),
+        }
+    }
+}
+
 impl ToString for ColumnValue {
     fn to_string(&self) -> String {
         match self {
@@ -56,25 +272,153 @@ impl ToString for ColumnValue {
             ColumnValue::SmallInt(v) => v.to_string(),
             ColumnValue::TinyInt(v) => v.to_string(),
             ColumnValue::BigInt(v) => v.to_string(),
-            ColumnValue::Float(v) => v.to_string(),
-            ColumnValue::Double(v) => v.to_string(),
-            ColumnValue::VarChar(v) => {
-                let trimmed = v.split(|&b| b == 0).next().unwrap_or(&[]);
-                String::from_utf8_lossy(trimmed).to_string()
-            }
-            ColumnValue::Text(v) => {
-                let trimmed = v.split(|&b| b == 0).next().unwrap_or(&[]);
-                String::from_utf8_lossy(trimmed).to_string()
-            }
-            ColumnValue::DateTime(v) => {
-                let trimmed = v.split(|&b| b == 0).next().unwrap_or(&[]);
-                String::from_utf8_lossy(trimmed).to_string()
-            }
-            ColumnValue::Timestamp(v) => {
-                let trimmed = v.split(|&b| b == 0).next().unwrap_or(&[]);
-                String::from_utf8_lossy(trimmed).to_string()
-            }
+            ColumnValue::Float(v) => format_real(*v as f64),
+            ColumnValue::Double(v) => format_real(*v),
+            ColumnValue::VarChar(v) | ColumnValue::Text(v) => bytes_to_string(v),
+            ColumnValue::Char(v) => String::from_utf8_lossy(v).into_owned(),
+            ColumnValue::Decimal(v, scale) => format_decimal(*v, *scale),
+            ColumnValue::DateTime(v) => datetime::format_datetime(*v),
+            ColumnValue::Timestamp(v) => datetime::format_datetime(*v),
             ColumnValue::Boolean(v) => v.to_string(),
+            ColumnValue::Null => "NULL".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::parser::parse_column_type;
+    use crate::storage::build_row;
+    use crate::storage::schema::{ColumnSchema, TableSchema};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_real_formatting() {
+        assert_eq!(ColumnValue::Double(1.0).to_string(), "1.0");
+        assert_eq!(ColumnValue::Float(0.1).to_string(), "0.1");
+        assert_eq!(ColumnValue::Double(0.0).to_string(), "0.0");
+        assert_eq!(ColumnValue::Double(-2.5).to_string(), "-2.5");
+        assert_eq!(ColumnValue::Double(1.0 / 3.0).to_string(), "0.333333");
+        assert_eq!(ColumnValue::Double(1.23456789).to_string(), "1.23457");
+        assert_eq!(ColumnValue::Double(1234567.0).to_string(), "1234567.0");
+        assert_eq!(ColumnValue::Float(0.000123456).to_string(), "0.000123456");
+    }
+
+    #[test]
+    fn test_varchar_display_round_trip() {
+        let schema = Arc::new(TableSchema::new(vec![ColumnSchema {
+            name: "name".into(),
+            type_: ColumnType::VARCHAR(16),
+            default: None,
+            is_primary: false,
+            is_nullable: false,
+        }]));
+        let row = build_row(&schema, &["name".into()], &["héllo".into()]).unwrap();
+        let built = row.get("name").unwrap();
+        let stored = ColumnType::VARCHAR(16)
+            .from_fixed_bytes(&built.to_fixed_bytes(16).unwrap())
+            .unwrap();
+
+        assert_eq!(built.to_string(), "héllo");
+        assert_eq!(stored.to_string(), built.to_string());
+        assert_eq!(ColumnValue::Text(b"abc\0\0".to_vec()).to_string(), "abc");
+        assert_eq!(trim_padding(&[0, 0]), &[] as &[u8]);
+    }
+
+    #[test]
+    fn test_decimal_parsing() {
+        assert_eq!(parse_decimal("12.34", 10, 2).unwrap(), 1234);
+        assert_eq!(parse_decimal("-0.5", 10, 2).unwrap(), -50);
+        assert_eq!(parse_decimal("12.345", 10, 2).unwrap(), 1235);
+        assert_eq!(parse_decimal("7", 3, 0).unwrap(), 7);
+        assert_eq!(parse_decimal(".25", 3, 2).unwrap(), 25);
+        assert!(parse_decimal("1000", 3, 0).is_err());
+        assert!(parse_decimal("99.995", 4, 2).is_err());
+        assert!(parse_decimal("1.2.3", 10, 2).is_err());
+        assert!(parse_decimal("abc", 10, 2).is_err());
+        assert!(parse_decimal("", 10, 2).is_err());
+
+        assert_eq!(ColumnValue::Decimal(1234, 2).to_string(), "12.34");
+        assert_eq!(ColumnValue::Decimal(-5, 2).to_string(), "-0.05");
+        assert_eq!(ColumnValue::Decimal(42, 0).to_string(), "42");
+    }
+
+    #[test]
+    fn test_accessors() {
+        let values = [
+            ColumnValue::Int(-7),
+            ColumnValue::SmallInt(300),
+            ColumnValue::TinyInt(-8),
+            ColumnValue::BigInt(1 << 40),
+            ColumnValue::BigInt(i128::MAX),
+            ColumnValue::Float(0.5),
+            ColumnValue::Double(-2.25),
+            ColumnValue::VarChar(b"abc\0\0".to_vec()),
+            ColumnValue::Char(b"ab".to_vec()),
+            ColumnValue::Decimal(1250, 2),
+            ColumnValue::Text(b"text".to_vec()),
+            ColumnValue::DateTime(86400),
+            ColumnValue::Timestamp(86400),
+            ColumnValue::Boolean(true),
+            ColumnValue::Null,
+        ];
+        let expected: [(Option<i64>, Option<f64>, Option<&str>); 15] = [
+            (Some(-7), Some(-7.0), None),
+            (Some(300), Some(300.0), None),
+            (Some(-8), Some(-8.0), None),
+            (Some(1 << 40), Some((1u64 << 40) as f64), None),
+            (None, Some(i128::MAX as f64), None),
+            (None, Some(0.5), None),
+            (None, Some(-2.25), None),
+            (None, None, Some("abc")),
+            (None, None, Some("ab")),
+            (None, Some(12.5), None),
+            (None, None, Some("text")),
+            (None, None, None),
+            (None, None, None),
+            (Some(1), Some(1.0), None),
+            (None, None, None),
+        ];
+        for (value, (i, f, s)) in values.iter().zip(expected) {
+            assert_eq!(value.as_i64(), i, "{:?}", value);
+            assert_eq!(value.as_f64(), f, "{:?}", value);
+            assert_eq!(value.as_str().as_deref(), s, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_to_sql_literal() {
+        assert_eq!(ColumnValue::Int(-3).to_sql_literal(), "-3");
+        assert_eq!(ColumnValue::Decimal(1250, 2).to_sql_literal(), "12.50");
+        assert_eq!(ColumnValue::Boolean(true).to_sql_literal(), "true");
+        assert_eq!(ColumnValue::from("it's").to_sql_literal(), "'it''s'");
+        assert_eq!(ColumnValue::Null.to_sql_literal(), "NULL");
+    }
+
+    #[test]
+    fn test_column_type_display_round_trip() {
+        let types = [
+            ColumnType::INT,
+            ColumnType::SMALLINT,
+            ColumnType::TINYINT,
+            ColumnType::BIGINT,
+            ColumnType::FLOAT,
+            ColumnType::DOUBLE,
+            ColumnType::VARCHAR(32),
+            ColumnType::VARCHAR(u16::MAX),
+            ColumnType::CHAR(1),
+            ColumnType::DECIMAL(10, 2),
+            ColumnType::DECIMAL(DECIMAL_MAX_PRECISION, 0),
+            ColumnType::TEXT,
+            ColumnType::DATETIME,
+            ColumnType::TIMESTAMP,
+            ColumnType::BOOLEAN,
+        ];
+        for type_ in types {
+            let sql = type_.to_string();
+            assert_eq!(parse_column_type(&sql).unwrap(), type_, "{}", sql);
         }
+        assert_eq!(ColumnType::DECIMAL(10, 2).to_string(), "DECIMAL(10,2)");
     }
 }
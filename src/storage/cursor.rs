@@ -55,35 +55,24 @@ impl<'a> Cursor<'a> {
     /// # Returns
     /// A new `Cursor` positioned at the table's first element
     pub fn start(table: &'a mut table::Table) -> Result<Self, Error> {
-        let mut cursor = Cursor::find(table, 0)?;
-
+        let cursor = Cursor::seek_first(table)?;
         debug!(
             page_num = cursor.page_num,
             "Created cursor on start of the table."
         );
-        let num_cells = cursor
-            .table
-            .pager
-            .get(cursor.page_num)?
-            .leaf_node_num_cells()?;
-        cursor.end_of_table = num_cells == 0;
-
         Ok(cursor)
     }
 
-    /// Creates a new cursor positioned at the end of the table
+    /// Creates a new cursor positioned after the last element of the table
     ///
     /// # Arguments
     /// * `table` - Mutable reference to the table
     ///
     /// # Returns
-    /// A new `Cursor` positioned after the last element
+    /// A new `Cursor` one past the last cell of the rightmost leaf
     pub fn end(table: &'a mut table::Table) -> Result<Self, Error> {
-        let page_num = table.root_page_num;
-        let cell_num = table
-            .pager
-            .get(table.root_page_num)?
-            .leaf_node_num_cells()?;
+        let page_num = Cursor::edge_leaf(table, true)?;
+        let cell_num = table.pager.get(page_num)?.leaf_node_num_cells()?;
 
         Ok(Cursor {
             table,
@@ -93,6 +82,60 @@ impl<'a> Cursor<'a> {
         })
     }
 
+    /// Creates a new cursor on the first cell of the leftmost leaf
+    ///
+    /// # Arguments
+    /// * `table` - Mutable reference to the table
+    ///
+    /// # Returns
+    /// A new `Cursor`, at the end of the table if it is empty
+    pub fn seek_first(table: &'a mut table::Table) -> Result<Self, Error> {
+        let page_num = Cursor::edge_leaf(table, false)?;
+        let num_cells = table.pager.get(page_num)?.leaf_node_num_cells()?;
+
+        Ok(Cursor {
+            table,
+            page_num,
+            cell_num: 0,
+            end_of_table: num_cells == 0,
+        })
+    }
+
+    /// Creates a new cursor on the last cell of the rightmost leaf
+    ///
+    /// # Arguments
+    /// * `table` - Mutable reference to the table
+    ///
+    /// # Returns
+    /// A new `Cursor`, at the end of the table if it is empty
+    pub fn seek_last(table: &'a mut table::Table) -> Result<Self, Error> {
+        let page_num = Cursor::edge_leaf(table, true)?;
+        let num_cells = table.pager.get(page_num)?.leaf_node_num_cells()?;
+
+        Ok(Cursor {
+            table,
+            page_num,
+            cell_num: num_cells.saturating_sub(1),
+            end_of_table: num_cells == 0,
+        })
+    }
+
+    /// Descends from the root to the leftmost or rightmost leaf and returns its page
+    fn edge_leaf(table: &table::Table, rightmost: bool) -> Result<u32, Error> {
+        let mut page_num = table.root_page_num;
+        loop {
+            let node = table.pager.get(page_num)?;
+            if node.get_node_type()? == NodeType::NodeLeaf {
+                return Ok(page_num);
+            }
+            page_num = if rightmost {
+                node.internal_node_right_child()?
+            } else {
+                node.internal_node_child(0)?
+            };
+        }
+    }
+
     /// Find a new cursor position for a given key ID.
     ///
     /// # Arguments
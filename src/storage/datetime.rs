@@ -0,0 +1,128 @@
+//! # Date and Time Conversion
+//!
+//! Converts `DATETIME`/`TIMESTAMP` values between their `YYYY-MM-DD HH:MM:SS` text form
+//! and the number of seconds since the Unix epoch (UTC) they are stored as.
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Parses a date-time string into seconds since the Unix epoch.
+///
+/// Accepts `YYYY-MM-DD`, `YYYY-MM-DD HH:MM` and `YYYY-MM-DD HH:MM:SS`, with either a space
+/// or `T` between the date and the time.
+///
+/// # Arguments
+/// * `value` - The date-time string to parse
+///
+/// # Returns
+/// The epoch seconds, or `None` if the string is not a valid date-time
+pub fn parse_datetime(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (date, time) = match value.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time.trim())),
+        None => (value, None),
+    };
+
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: u32 = date_parts.next()?.parse().ok()?;
+    let day: u32 = date_parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let (hour, minute, second) = match time {
+        Some(time) => {
+            let mut time_parts = time.splitn(3, ':');
+            let hour: i64 = time_parts.next()?.parse().ok()?;
+            let minute: i64 = time_parts.next()?.parse().ok()?;
+            let second: i64 = time_parts.next().map_or(Some(0), |s| s.parse().ok())?;
+            if !(0..24).contains(&hour) || !(0..60).contains(&minute) || !(0..60).contains(&second)
+            {
+                return None;
+            }
+            (hour, minute, second)
+        }
+        None => (0, 0, 0),
+    };
+
+    Some(days_from_civil(year, month, day) * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second)
+}
+
+/// Formats seconds since the Unix epoch as `YYYY-MM-DD HH:MM:SS` (UTC).
+///
+/// # Arguments
+/// * `secs` - The epoch seconds to format
+///
+/// # Returns
+/// The formatted date-time string
+pub fn format_datetime(secs: i64) -> String {
+    let days = secs.div_euclid(SECONDS_PER_DAY);
+    let time = secs.rem_euclid(SECONDS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12; // March = 0
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Returns the proleptic Gregorian date for a number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_datetime_round_trip() {
+        assert_eq!(parse_datetime("1970-01-01 00:00:00"), Some(0));
+        assert_eq!(parse_datetime("2024-01-02 03:04:05"), Some(1_704_164_645));
+        assert_eq!(parse_datetime("2024-01-02T03:04"), Some(1_704_164_640));
+        assert_eq!(parse_datetime("2024-02-29"), Some(1_709_164_800));
+        assert_eq!(format_datetime(1_704_164_645), "2024-01-02 03:04:05");
+        assert_eq!(format_datetime(-1), "1969-12-31 23:59:59");
+
+        assert_eq!(parse_datetime("2023-02-29"), None);
+        assert_eq!(parse_datetime("2024-01-02 24:00:00"), None);
+        assert_eq!(parse_datetime("yesterday"), None);
+    }
+}
@@ -1,15 +1,16 @@
-use super::column::{ColumnType, ColumnValue};
-use super::row::Row;
-use super::schema::TableSchema;
+use super::column::{trim_padding, ColumnType, ColumnValue};
+use super::row::{self, Row};
+use super::schema::{ColumnSchema, TableSchema};
 use crate::errors;
 use bincode::{config, decode_from_slice, encode_into_slice};
 use std;
-use std::collections::HashMap;
+use std::sync::Arc;
 
 const TEXT_SIZE: usize = 65_535;
 const DATETIME_SIZE: usize = 8;
 const TIMESTAMP_SIZE: usize = 8;
 const VARCHAR_MAXSIZE: usize = 2048;
+const DECIMAL_SIZE: usize = 16;
 
 impl ColumnType {
     pub fn fixed_size(&self) -> usize {
@@ -24,6 +25,8 @@ impl ColumnType {
             ColumnType::DATETIME => DATETIME_SIZE, // Fixed size for DATETIME (e.g., 12 bytes)
             ColumnType::TIMESTAMP => TIMESTAMP_SIZE, // Fixed size for TIMESTAMP (e.g., 16 bytes)
             ColumnType::VARCHAR(max_len) => *max_len as usize, // Max length specified
+            ColumnType::CHAR(len) => *len as usize, // Exact length specified
+            ColumnType::DECIMAL(..) => DECIMAL_SIZE, // Scaled i128: 16 bytes
             ColumnType::BOOLEAN => 1,      // bool: 1 byte
         }
     }
@@ -62,23 +65,21 @@ impl ColumnType {
                     .map_err(|e| err!(Encoding, format!("Failed to decode DOUBLE. {}", e)))?;
                 Ok(ColumnValue::Double(val))
             }
-            ColumnType::TEXT => {
-                let text = String::from_utf8_lossy(&buffer)
-                    .trim_end_matches('\0')
-                    .to_string()
-                    .as_bytes()
-                    .to_vec();
-                Ok(ColumnValue::Text(text))
-            }
-            ColumnType::DATETIME => Ok(ColumnValue::DateTime(buffer.to_vec())),
-            ColumnType::TIMESTAMP => Ok(ColumnValue::Timestamp(buffer.to_vec())),
-            ColumnType::VARCHAR(_) => {
-                let text = String::from_utf8_lossy(&buffer)
-                    .trim_end_matches('\0')
-                    .to_string()
-                    .as_bytes()
-                    .to_vec();
-                Ok(ColumnValue::VarChar(text))
+            ColumnType::TEXT => Ok(ColumnValue::Text(trim_padding(buffer).to_vec())),
+            ColumnType::DATETIME => Ok(ColumnValue::DateTime(decode_epoch(buffer, "DATETIME")?)),
+            ColumnType::TIMESTAMP => Ok(ColumnValue::Timestamp(decode_epoch(buffer, "TIMESTAMP")?)),
+            ColumnType::VARCHAR(_) => Ok(ColumnValue::VarChar(trim_padding(buffer).to_vec())),
+            ColumnType::CHAR(_) => Ok(ColumnValue::Char(buffer.to_vec())),
+            ColumnType::DECIMAL(_, scale) => {
+                let bytes = buffer.get(..DECIMAL_SIZE).ok_or_else(|| {
+                    err!(
+                        Encoding,
+                        "Failed to decode DECIMAL. Expected {} bytes",
+                        DECIMAL_SIZE
+                    )
+                })?;
+                let unscaled = i128::from_le_bytes(bytes.try_into().expect("16 bytes"));
+                Ok(ColumnValue::Decimal(unscaled, *scale))
             }
             ColumnType::BOOLEAN => {
                 let (val, _) = decode_from_slice::<bool, _>(buffer, c)
@@ -89,6 +90,43 @@ impl ColumnType {
     }
 }
 
+/// Decodes epoch seconds stored as 8 little-endian bytes.
+fn decode_epoch(buffer: &[u8], type_name: &str) -> Result<i64, errors::Error> {
+    buffer
+        .get(..DATETIME_SIZE)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(i64::from_le_bytes)
+        .ok_or_else(|| {
+            err!(
+                Encoding,
+                format!(
+                    "Failed to decode {}. Expected {} bytes",
+                    type_name, DATETIME_SIZE
+                )
+            )
+        })
+}
+
+/// Copies `bytes` to the start of `buffer`, leaving the rest zero-padded.
+///
+/// Returns an `Encoding` error instead of panicking when `bytes` doesn't fit.
+fn copy_padded(buffer: &mut [u8], bytes: &[u8], type_name: &str) -> Result<usize, errors::Error> {
+    let len = buffer.len();
+    buffer
+        .get_mut(..bytes.len())
+        .ok_or_else(|| {
+            err!(
+                Encoding,
+                "Failed to encode {}. {} bytes don't fit in {}",
+                type_name,
+                bytes.len(),
+                len
+            )
+        })?
+        .copy_from_slice(bytes);
+    Ok(bytes.len())
+}
+
 impl ColumnValue {
     pub fn to_fixed_bytes(&self, max_size: usize) -> Result<Vec<u8>, errors::Error> {
         let mut buffer = vec![0u8; max_size];
@@ -115,16 +153,18 @@ impl ColumnValue {
                         TEXT_SIZE
                     )));
                 }
-                buffer[..TEXT_SIZE].copy_from_slice(s);
-                TEXT_SIZE
-            }
-            ColumnValue::DateTime(bytes) => {
-                buffer.copy_from_slice(bytes);
-                bytes.len() as usize
+                copy_padded(&mut buffer, s, "TEXT")?
             }
-            ColumnValue::Timestamp(bytes) => {
-                buffer.copy_from_slice(bytes);
-                bytes.len() as usize
+            ColumnValue::DateTime(v) | ColumnValue::Timestamp(v) => {
+                if max_size != DATETIME_SIZE {
+                    return Err(err!(
+                        Encoding,
+                        "Failed to encode DATETIME. Expected {} bytes, got {}",
+                        DATETIME_SIZE,
+                        max_size
+                    ));
+                }
+                copy_padded(&mut buffer, &v.to_le_bytes(), "DATETIME")?
             }
             ColumnValue::VarChar(s) => {
                 if s.len() > VARCHAR_MAXSIZE {
@@ -134,32 +174,52 @@ impl ColumnValue {
                         VARCHAR_MAXSIZE
                     )));
                 }
-                buffer[..s.len()].copy_from_slice(s);
-                s.len() as usize
+                copy_padded(&mut buffer, s, "VARCHAR")?
             }
+            ColumnValue::Char(s) => copy_padded(&mut buffer, s, "CHAR")?,
+            ColumnValue::Decimal(v, _) => copy_padded(&mut buffer, &v.to_le_bytes(), "DECIMAL")?,
             ColumnValue::Boolean(v) => {
                 encode_into_slice(v, &mut buffer, c)
                     .map_err(|e| err!(Encoding, format!("Failed to encode BOOLEAN. {}", e)))?;
                 1
             }
+            // Zero-filled, the NULL bitmap of the row tells it apart from a zero
+            ColumnValue::Null => 0,
         };
         Ok(buffer)
     }
 }
 
+/// Returns the size of the NULL bitmap that ends an encoded row, one bit per column.
+pub fn null_bitmap_size(num_columns: usize) -> usize {
+    num_columns.div_ceil(8)
+}
+
 // Encode a row from bytes based on the schema
+//
+// The column values are followed by a bitmap with the bit of every NULL column set.
+// Rows that predate a schema change may lack the trailing columns; those are encoded
+// as described in `missing_value`.
 pub fn encode_row(schema: &TableSchema, row: &Row) -> Result<Vec<u8>, errors::Error> {
     let row_size = schema.get_row_size();
     let mut result = Vec::with_capacity(row_size);
+    let mut nulls = vec![0u8; null_bitmap_size(schema.columns().len())];
 
-    for column in &schema.columns {
-        let value = row
-            .inner
-            .get(&column.name)
-            .ok_or_else(|| errors::Error::Schema(format!("Missing column: {}", column.name)))?;
-        let fixed_bytes = value.to_fixed_bytes(column.type_.fixed_size())?;
-        result.extend_from_slice(&fixed_bytes);
+    for (index, column) in schema.columns().iter().enumerate() {
+        let missing;
+        let value = match row.values.get(index) {
+            Some(value) => value,
+            None => {
+                missing = missing_value(column)?;
+                &missing
+            }
+        };
+        if value.is_null() {
+            nulls[index / 8] |= 1 << (index % 8);
+        }
+        result.extend_from_slice(&value.to_fixed_bytes(column.type_.fixed_size())?);
     }
+    result.extend_from_slice(&nulls);
 
     debug_assert_eq!(
         result.len(),
@@ -170,11 +230,26 @@ pub fn encode_row(schema: &TableSchema, row: &Row) -> Result<Vec<u8>, errors::Er
     Ok(result)
 }
 
+/// Returns the value of a column the row has no value for.
+///
+/// The column's default is used when it has one, and a nullable column without a
+/// default is NULL.
+///
+/// # Returns
+/// The value, or an `Error::Schema` for a `NOT NULL` column without a default
+fn missing_value(column: &ColumnSchema) -> Result<ColumnValue, errors::Error> {
+    match (&column.default, column.accepts_null()) {
+        (None, false) => Err(errors::Error::Schema(format!(
+            "Missing column: {}",
+            column.name
+        ))),
+        _ => row::default_value(column),
+    }
+}
+
 // Decode a row from bytes based on the schema
-pub fn decode_row(schema: &TableSchema, encoded: &Vec<u8>) -> Result<Row, errors::Error> {
-    let mut row = Row {
-        inner: HashMap::new(),
-    };
+pub fn decode_row(schema: &Arc<TableSchema>, encoded: &[u8]) -> Result<Row, errors::Error> {
+    let mut values = Vec::with_capacity(schema.columns().len());
     let mut offset = 0;
 
     let row_size = schema.get_row_size();
@@ -186,7 +261,8 @@ pub fn decode_row(schema: &TableSchema, encoded: &Vec<u8>) -> Result<Row, errors
         )));
     }
 
-    for column in &schema.columns {
+    let nulls = &encoded[row_size - null_bitmap_size(schema.columns().len())..];
+    for (index, column) in schema.columns().iter().enumerate() {
         let size = column.type_.fixed_size();
         if offset + size > encoded.len() {
             return Err(errors::Error::Schema(format!(
@@ -196,10 +272,121 @@ pub fn decode_row(schema: &TableSchema, encoded: &Vec<u8>) -> Result<Row, errors
         }
 
         let slice = &encoded[offset..offset + size];
-        row.inner
-            .insert(column.name.clone(), column.type_.from_fixed_bytes(slice)?);
+        if nulls[index / 8] & (1 << (index % 8)) != 0 {
+            values.push(ColumnValue::Null);
+        } else {
+            values.push(column.type_.from_fixed_bytes(slice)?);
+        }
         offset += size;
     }
 
-    Ok(row)
+    Ok(Row::new(schema.clone(), values))
+}
+
+/// Lookup table of the CRC-32 (IEEE 802.3) polynomial, built at compile time.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Computes the CRC-32 (IEEE 802.3) checksum of the given bytes.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &b| {
+        CRC32_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::schema::DefaultValue;
+
+    #[test]
+    fn test_encode_row_missing_columns() {
+        let column = |name: &str, type_, default, is_nullable| ColumnSchema {
+            name: name.into(),
+            type_,
+            default,
+            is_primary: name == "id",
+            is_nullable,
+        };
+        let old = Arc::new(TableSchema::new(vec![column(
+            "id",
+            ColumnType::INT,
+            None,
+            false,
+        )]));
+        let row = Row::new(old, vec![ColumnValue::Int(7)]);
+
+        // The row predates two columns added to the schema
+        let new = Arc::new(TableSchema::new(vec![
+            column("id", ColumnType::INT, None, false),
+            column(
+                "name",
+                ColumnType::VARCHAR(8),
+                Some(DefaultValue::Literal("guest".into())),
+                false,
+            ),
+            column("score", ColumnType::SMALLINT, None, true),
+        ]));
+        let encoded = encode_row(&new, &row).unwrap();
+        assert_eq!(encoded.len(), new.get_row_size());
+        let decoded = decode_row(&new, &encoded).unwrap();
+        assert_eq!(decoded.get_column("id").as_deref(), Some("7"));
+        assert_eq!(decoded.get_column("name").as_deref(), Some("guest"));
+        assert_eq!(decoded.get_column("score"), None);
+        assert!(decoded.get("score").unwrap().is_null());
+
+        let strict = TableSchema::new(vec![
+            column("id", ColumnType::INT, None, false),
+            column("name", ColumnType::VARCHAR(8), None, false),
+        ]);
+        let err = encode_row(&strict, &row).unwrap_err();
+        assert!(matches!(err, errors::Error::Schema(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_to_fixed_bytes_length_mismatch() {
+        let err = ColumnValue::DateTime(0).to_fixed_bytes(4).unwrap_err();
+        assert!(matches!(err, errors::Error::Encoding(_)), "{}", err);
+        let err = ColumnValue::Timestamp(0).to_fixed_bytes(16).unwrap_err();
+        assert!(matches!(err, errors::Error::Encoding(_)), "{}", err);
+        let err = ColumnType::DATETIME.from_fixed_bytes(&[0; 4]).unwrap_err();
+        assert!(matches!(err, errors::Error::Encoding(_)), "{}", err);
+
+        let err = ColumnValue::VarChar(b"too long".to_vec())
+            .to_fixed_bytes(4)
+            .unwrap_err();
+        assert!(matches!(err, errors::Error::Encoding(_)), "{}", err);
+
+        // Shorter values are zero-padded.
+        assert_eq!(
+            ColumnValue::VarChar(b"ab".to_vec())
+                .to_fixed_bytes(4)
+                .unwrap(),
+            vec![b'a', b'b', 0, 0]
+        );
+        assert_eq!(
+            ColumnValue::Text(b"ab".to_vec())
+                .to_fixed_bytes(TEXT_SIZE)
+                .unwrap()
+                .len(),
+            TEXT_SIZE
+        );
+    }
 }
@@ -1,10 +1,16 @@
 pub mod btree;
+#[cfg(test)]
+mod btree_prop;
 pub mod column;
 pub mod cursor;
+mod datetime;
 mod encoding;
 mod engine;
 mod row;
 pub mod schema;
 pub mod table;
-pub use row::{build_row, Row};
-pub use table::{insert_row, select_rows, Table, SCHEMA};
+pub use row::{build_row, current_datetime, Row};
+pub use table::{
+    delete_rows, find_row, for_each_row, insert_row, lock_with_timeout, optimize_table,
+    select_rows, truncate_table, Table, LOCK_TIMEOUT,
+};
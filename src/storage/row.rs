@@ -1,30 +1,75 @@
 //! # Row Management
-use super::column::{ColumnType, ColumnValue};
-use super::schema::TableSchema;
+use super::column::{parse_decimal, ColumnType, ColumnValue};
+use super::datetime;
+use super::schema::{ColumnSchema, DefaultValue, TableSchema};
 use crate::errors::Error;
-use bincode::{Decode, Encode};
-use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Represents a database row with flexible column storage.
+/// Represents a database row.
 ///
-/// The `Row` struct uses a `HashMap` to store column values, allowing
-/// for dynamic and schema-based row representation.
-#[derive(Encode, Decode, Debug)]
+/// Values are stored positionally, aligned to the columns of the table schema the row
+/// shares with every other row of its table; column names are resolved through it.
+#[derive(Debug, Clone)]
 pub struct Row {
-    /// Internal storage of column values
-    pub inner: HashMap<String, ColumnValue>,
+    /// Schema of the table the row belongs to
+    schema: Arc<TableSchema>,
+    /// Column values, in the order of `schema.columns`
+    pub values: Vec<ColumnValue>,
 }
 
 impl Row {
+    /// Creates a row from values given in schema order.
+    ///
+    /// # Arguments
+    /// * `schema` - Schema of the table the row belongs to
+    /// * `values` - One value per schema column
+    pub fn new(schema: Arc<TableSchema>, values: Vec<ColumnValue>) -> Self {
+        debug_assert_eq!(schema.columns().len(), values.len());
+        Row { schema, values }
+    }
+
+    /// Returns the schema of the table the row belongs to.
+    pub fn schema(&self) -> &TableSchema {
+        &self.schema
+    }
+
+    /// Retrieves a column value.
+    ///
+    /// # Arguments
+    /// * `column` - The name of the column to retrieve, optionally qualified (`table.column`)
+    ///
+    /// # Returns
+    /// An `Option` containing the column value, `None` if the schema has no such column
+    pub fn get(&self, column: &str) -> Option<&ColumnValue> {
+        let name = column.rsplit_once('.').map_or(column, |(_, name)| name);
+        let index = self.schema.columns().iter().position(|c| c.name == name)?;
+        self.values.get(index)
+    }
+
     /// Retrieves a column value as a string representation.
     ///
     /// # Arguments
-    /// * `column` - The name of the column to retrieve
+    /// * `column` - The name of the column to retrieve, optionally qualified (`table.column`)
     ///
     /// # Returns
-    /// An `Option` containing the string representation of the column value
+    /// An `Option` containing the string representation of the column value, `None`
+    /// for NULL
     pub fn get_column(&self, column: &str) -> Option<String> {
-        Some(self.inner.get(column)?.to_string())
+        self.get(column)
+            .filter(|value| !value.is_null())
+            .map(|value| value.to_string())
+    }
+
+    /// Projects the row onto the given columns as display strings.
+    ///
+    /// # Arguments
+    /// * `columns` - The column names to project, optionally qualified (`table.column`)
+    ///
+    /// # Returns
+    /// One string per column, `None` for NULL values and columns the row doesn't have
+    pub fn to_values(&self, columns: &[String]) -> Vec<Option<String>> {
+        columns.iter().map(|column| self.get_column(column)).collect()
     }
 
     /// Extracts the primary key value from the row based on the table schema.
@@ -37,18 +82,20 @@ impl Row {
     /// - No primary key column is defined
     /// - Primary key column is missing from the row
     /// - Primary key is not of integer type
+    /// - Primary key is negative or doesn't fit in a `u32`
     pub fn get_id(&self, schema: &TableSchema) -> Result<u32, Error> {
         schema
-            .columns
+            .columns()
             .iter()
-            .find(|col_schema| col_schema.is_primary)
+            .position(|col_schema| col_schema.is_primary)
             .ok_or_else(|| Error::Schema("No primary key column defined".into()))
-            .and_then(|primary_col| {
-                self.inner
-                    .get(&primary_col.name)
+            .and_then(|index| {
+                self.values
+                    .get(index)
                     .ok_or_else(|| Error::Schema("Primary key column missing in the row".into()))
                     .and_then(|value| match value {
-                        ColumnValue::Int(v) => Ok(*v as u32),
+                        ColumnValue::Int(v) => u32::try_from(*v)
+                            .map_err(|_| Error::Schema(format!("Primary key {v} out of range"))),
                         _ => Err(Error::Schema("Invalid primary key type".into())),
                     })
             })
@@ -64,13 +111,24 @@ impl Row {
     /// # Returns
     /// A new `Row` instance, or an error if validation fails
     pub fn from_columns(
-        schema: &TableSchema,
+        schema: &Arc<TableSchema>,
         columns: &[String],
         values: &[String],
     ) -> Result<Self, Error> {
         build_row(schema, columns, values)
     }
 
+    /// Starts building a row from native Rust values, see `RowBuilder`.
+    ///
+    /// # Arguments
+    /// * `schema` - Schema of the table the row belongs to
+    pub fn builder(schema: &Arc<TableSchema>) -> RowBuilder {
+        RowBuilder {
+            schema: schema.clone(),
+            values: Vec::new(),
+        }
+    }
+
     /// Validates the row against the given schema.
     ///
     /// # Arguments
@@ -79,31 +137,140 @@ impl Row {
     /// # Returns
     /// `true` if the row is valid according to the schema, `false` otherwise
     pub fn validate(&self, schema: &TableSchema) -> bool {
-        schema.columns.iter().all(|col_schema| {
-            self.inner.get(&col_schema.name).map_or_else(
-                || col_schema.default.is_some(), // Column missing but has default
-                |value| {
-                    // Check if value matches column type
-                    match (&col_schema.type_, value) {
-                        (ColumnType::INT, ColumnValue::Int(_)) => true,
-                        (ColumnType::SMALLINT, ColumnValue::SmallInt(_)) => true,
-                        (ColumnType::TINYINT, ColumnValue::TinyInt(_)) => true,
-                        (ColumnType::BIGINT, ColumnValue::BigInt(_)) => true,
-                        (ColumnType::FLOAT, ColumnValue::Float(_)) => true,
-                        (ColumnType::DOUBLE, ColumnValue::Double(_)) => true,
-                        (ColumnType::VARCHAR(_), ColumnValue::VarChar(_)) => true,
-                        (ColumnType::TEXT, ColumnValue::Text(_)) => true,
-                        (ColumnType::DATETIME, ColumnValue::DateTime(_)) => true,
-                        (ColumnType::TIMESTAMP, ColumnValue::Timestamp(_)) => true,
-                        (ColumnType::BOOLEAN, ColumnValue::Boolean(_)) => true,
-                        _ => false,
-                    }
-                },
-            )
-        })
+        schema
+            .columns()
+            .iter()
+            .enumerate()
+            .all(|(index, col_schema)| {
+                self.values.get(index).map_or_else(
+                    || col_schema.default.is_some(), // Column missing but has default
+                    |value| {
+                        matches_type(&col_schema.type_, value)
+                            || value.is_null() && col_schema.accepts_null()
+                    },
+                )
+            })
+    }
+}
+
+/// Builds a row from typed values instead of strings, for programmatic inserts.
+///
+/// # Example
+/// ```ignore
+/// let row = Row::builder(&schema)
+///     .insert("id", 1)
+///     .insert("username", "alice")
+///     .build()?;
+/// storage::insert_row(&mut table, &row)?;
+/// ```
+#[derive(Debug)]
+pub struct RowBuilder {
+    schema: Arc<TableSchema>,
+    values: Vec<(String, ColumnValue)>,
+}
+
+impl RowBuilder {
+    /// Sets the value of a column, replacing any earlier value for it.
+    ///
+    /// # Arguments
+    /// * `name` - The column name
+    /// * `value` - The value, converted with `From` (e.g. `i64`, `&str`, `bool`)
+    pub fn insert(mut self, name: &str, value: impl Into<ColumnValue>) -> Self {
+        self.values.retain(|(column, _)| column != name);
+        self.values.push((name.to_string(), value.into()));
+        self
+    }
+
+    /// Finishes the row, filling columns that were not set from their defaults.
+    ///
+    /// Values that don't have the exact column type are converted the way a SQL
+    /// literal would be, e.g. an `i64` into a `TINYINT` column is range checked.
+    ///
+    /// # Returns
+    /// The new `Row`, or an `Error::Schema` for unknown columns, values that don't
+    /// fit their column, or missing values
+    pub fn build(self) -> Result<Row, Error> {
+        if let Some((name, _)) = self
+            .values
+            .iter()
+            .find(|(name, _)| !self.schema.columns().iter().any(|c| &c.name == name))
+        {
+            return Err(Error::Schema(format!("Unknown column '{name}'")));
+        }
+        let mut row_values = Vec::with_capacity(self.schema.columns().len());
+        for col_schema in self.schema.columns() {
+            let value = match self
+                .values
+                .iter()
+                .find(|(name, _)| name == &col_schema.name)
+            {
+                Some((_, value)) if is_exact(&col_schema.type_, value) => value.clone(),
+                Some((_, value)) => parse_value(&col_schema.type_, &value.to_string())?,
+                None => default_value(col_schema)?,
+            };
+            row_values.push(value);
+        }
+        Ok(Row::new(self.schema, row_values))
     }
 }
 
+/// Checks whether a value can be stored in a column of the given type as is.
+///
+/// Strings never qualify, since columns store them padded to their declared length.
+fn is_exact(type_: &ColumnType, value: &ColumnValue) -> bool {
+    !matches!(
+        value,
+        ColumnValue::VarChar(_) | ColumnValue::Char(_) | ColumnValue::Text(_)
+    ) && matches_type(type_, value)
+}
+
+/// Checks whether a value has the variant a column of the given type stores.
+fn matches_type(type_: &ColumnType, value: &ColumnValue) -> bool {
+    match (type_, value) {
+        (ColumnType::INT, ColumnValue::Int(_)) => true,
+        (ColumnType::SMALLINT, ColumnValue::SmallInt(_)) => true,
+        (ColumnType::TINYINT, ColumnValue::TinyInt(_)) => true,
+        (ColumnType::BIGINT, ColumnValue::BigInt(_)) => true,
+        (ColumnType::FLOAT, ColumnValue::Float(_)) => true,
+        (ColumnType::DOUBLE, ColumnValue::Double(_)) => true,
+        (ColumnType::VARCHAR(_), ColumnValue::VarChar(_)) => true,
+        (ColumnType::CHAR(_), ColumnValue::Char(_)) => true,
+        (ColumnType::DECIMAL(_, scale), ColumnValue::Decimal(_, s)) => scale == s,
+        (ColumnType::TEXT, ColumnValue::Text(_)) => true,
+        (ColumnType::DATETIME, ColumnValue::DateTime(_)) => true,
+        (ColumnType::TIMESTAMP, ColumnValue::Timestamp(_)) => true,
+        (ColumnType::BOOLEAN, ColumnValue::Boolean(_)) => true,
+        _ => false,
+    }
+}
+
+/// Parses a boolean literal, accepting `true/false`, `1/0`, `t/f` and `yes/no`
+/// in any case.
+fn parse_boolean(value: &str) -> Result<bool, Error> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "t" | "yes" => Ok(true),
+        "false" | "0" | "f" | "no" => Ok(false),
+        _ => Err(Error::Schema(format!("Invalid BOOLEAN: {value}"))),
+    }
+}
+
+/// Parses an integer literal for a column of the given integer type.
+///
+/// # Arguments
+/// * `value` - The literal to parse
+/// * `type_name` - The SQL type used in error messages, e.g. `TINYINT`
+///
+/// # Returns
+/// The parsed value, or an `Error::Schema` telling a malformed literal apart
+/// from one that does not fit the column width
+fn parse_integer<T: TryFrom<i128>>(value: &str, type_name: &str) -> Result<T, Error> {
+    let wide: i128 = value
+        .parse()
+        .map_err(|_| Error::Schema(format!("Invalid {type_name}: {value}")))?;
+    T::try_from(wide)
+        .map_err(|_| Error::Schema(format!("Value {value} out of range for {type_name}")))
+}
+
 /// Builds a row from given schema, columns, and values.
 ///
 /// # Arguments
@@ -114,10 +281,11 @@ impl Row {
 /// # Returns
 /// A new `Row` instance, or an error if:
 /// - Column and value lists have different lengths
+/// - Any column is not in the schema
 /// - Any column is missing a value
 /// - Any value cannot be parsed according to column type
 pub fn build_row(
-    schema: &TableSchema,
+    schema: &Arc<TableSchema>,
     columns: &[String],
     values: &[String],
 ) -> Result<Row, Error> {
@@ -125,86 +293,329 @@ pub fn build_row(
     if columns.len() != values.len() {
         return Err(Error::Schema("Columns and values length mismatch".into()));
     }
+    if let Some(name) = columns
+        .iter()
+        .find(|name| !schema.columns().iter().any(|c| &&c.name == name))
+    {
+        return Err(Error::Semantic(format!(
+            "Unknown column '{name}' in 'field list'"
+        )));
+    }
 
-    let mut row = Row {
-        inner: HashMap::new(),
-    };
+    let mut row_values = Vec::with_capacity(schema.columns().len());
 
-    for col_schema in &schema.columns {
+    for col_schema in schema.columns() {
         // Find value for column, prioritizing provided values over defaults
-        let value = columns
+        let provided = columns
             .iter()
             .position(|c| c == &col_schema.name)
-            .map(|idx| &values[idx])
-            .or_else(|| col_schema.default.as_ref())
-            .ok_or_else(|| {
-                Error::Schema(format!("Missing value for column: {}", col_schema.name))
-            })?;
-
-        // Parse and validate column value
-        let parsed_value = match &col_schema.type_ {
-            ColumnType::INT => ColumnValue::Int(
-                value
-                    .parse()
-                    .map_err(|_| Error::Schema(format!("Invalid INT: {value}")))?,
-            ),
-            ColumnType::SMALLINT => ColumnValue::SmallInt(
-                value
-                    .parse()
-                    .map_err(|_| Error::Schema(format!("Invalid SMALLINT: {value}")))?,
-            ),
-            ColumnType::TINYINT => ColumnValue::TinyInt(
-                value
-                    .parse()
-                    .map_err(|_| Error::Schema(format!("Invalid TINYINT: {value}")))?,
-            ),
-            ColumnType::BIGINT => ColumnValue::BigInt(
-                value
-                    .parse()
-                    .map_err(|_| Error::Schema(format!("Invalid BIGINT: {value}")))?,
-            ),
-            ColumnType::FLOAT => ColumnValue::Float(
-                value
-                    .parse()
-                    .map_err(|_| Error::Schema(format!("Invalid FLOAT: {value}")))?,
-            ),
-            ColumnType::DOUBLE => ColumnValue::Double(
-                value
-                    .parse()
-                    .map_err(|_| Error::Schema(format!("Invalid DOUBLE: {value}")))?,
-            ),
-            ColumnType::VARCHAR(len) => {
-                let mut v = vec![0u8; *len as usize];
-                let bytes = value.as_bytes();
-                v[..bytes.len().min(*len as usize)]
-                    .copy_from_slice(&bytes[..bytes.len().min(*len as usize)]);
-                ColumnValue::VarChar(v)
-            }
-            ColumnType::TEXT => ColumnValue::Text(value.clone().into_bytes()),
-            ColumnType::DATETIME => ColumnValue::DateTime(value.clone().into_bytes()),
-            ColumnType::TIMESTAMP => ColumnValue::Timestamp(value.clone().into_bytes()),
-            ColumnType::BOOLEAN => ColumnValue::Boolean(
-                value
-                    .parse()
-                    .map_err(|_| Error::Schema(format!("Invalid BOOLEAN: {value}")))?,
-            ),
+            .map(|idx| &values[idx]);
+        let value = match provided {
+            Some(value) => parse_value(&col_schema.type_, value)?,
+            None => default_value(col_schema)?,
         };
+        row_values.push(value);
+    }
+
+    Ok(Row::new(schema.clone(), row_values))
+}
+
+/// Parses a literal into a value for a column of the given type.
+///
+/// # Arguments
+/// * `type_` - The column type
+/// * `value` - The literal to parse
+///
+/// # Returns
+/// The parsed value, or an `Error::Schema` if it doesn't fit the column type
+fn parse_value(type_: &ColumnType, value: &str) -> Result<ColumnValue, Error> {
+    let parsed_value = match type_ {
+        ColumnType::INT => ColumnValue::Int(parse_integer(value, "INT")?),
+        ColumnType::SMALLINT => ColumnValue::SmallInt(parse_integer(value, "SMALLINT")?),
+        ColumnType::TINYINT => ColumnValue::TinyInt(parse_integer(value, "TINYINT")?),
+        ColumnType::BIGINT => ColumnValue::BigInt(
+            value
+                .parse()
+                .map_err(|_| Error::Schema(format!("Invalid BIGINT: {value}")))?,
+        ),
+        ColumnType::FLOAT => ColumnValue::Float(
+            value
+                .parse()
+                .map_err(|_| Error::Schema(format!("Invalid FLOAT: {value}")))?,
+        ),
+        ColumnType::DOUBLE => ColumnValue::Double(
+            value
+                .parse()
+                .map_err(|_| Error::Schema(format!("Invalid DOUBLE: {value}")))?,
+        ),
+        ColumnType::VARCHAR(len) => {
+            let mut v = vec![0u8; *len as usize];
+            let bytes = value.as_bytes();
+            v[..bytes.len().min(*len as usize)]
+                .copy_from_slice(&bytes[..bytes.len().min(*len as usize)]);
+            ColumnValue::VarChar(v)
+        }
+        ColumnType::CHAR(len) => {
+            let mut v = vec![b' '; *len as usize];
+            let bytes = value.as_bytes();
+            v[..bytes.len().min(*len as usize)]
+                .copy_from_slice(&bytes[..bytes.len().min(*len as usize)]);
+            ColumnValue::Char(v)
+        }
+        ColumnType::DECIMAL(precision, scale) => {
+            ColumnValue::Decimal(parse_decimal(value, *precision, *scale)?, *scale)
+        }
+        ColumnType::TEXT => ColumnValue::Text(value.as_bytes().to_vec()),
+        ColumnType::DATETIME => ColumnValue::DateTime(
+            datetime::parse_datetime(value)
+                .ok_or_else(|| Error::Schema(format!("Invalid DATETIME: {value}")))?,
+        ),
+        ColumnType::TIMESTAMP => ColumnValue::Timestamp(
+            datetime::parse_datetime(value)
+                .ok_or_else(|| Error::Schema(format!("Invalid TIMESTAMP: {value}")))?,
+        ),
+        ColumnType::BOOLEAN => ColumnValue::Boolean(parse_boolean(value)?),
+    };
+    Ok(parsed_value)
+}
 
-        row.inner.insert(col_schema.name.clone(), parsed_value);
+/// Builds the value of a column that was not given one, from its default.
+///
+/// # Arguments
+/// * `col_schema` - The column schema
+///
+/// # Returns
+/// The default value, NULL for a nullable column without one, or an `Error::Schema`
+/// if the column has none
+pub(super) fn default_value(col_schema: &ColumnSchema) -> Result<ColumnValue, Error> {
+    match &col_schema.default {
+        Some(DefaultValue::Literal(value)) => parse_value(&col_schema.type_, value),
+        Some(DefaultValue::Now) => current_timestamp(&col_schema.type_),
+        None if col_schema.accepts_null() => Ok(ColumnValue::Null),
+        // The key is assigned by the insert, which knows the keys of the table
+        Some(DefaultValue::AutoIncrement) | None => Err(Error::Schema(format!(
+            "Missing value for column: {}",
+            col_schema.name
+        ))),
     }
+}
+
+/// Builds the value of a `DEFAULT CURRENT_TIMESTAMP` column.
+///
+/// # Arguments
+/// * `type_` - The column type, either `DATETIME` or `TIMESTAMP`
+///
+/// # Returns
+/// The current time as seconds since the Unix epoch
+fn current_timestamp(type_: &ColumnType) -> Result<ColumnValue, Error> {
+    let secs = now_secs()?;
+    match type_ {
+        ColumnType::DATETIME => Ok(ColumnValue::DateTime(secs)),
+        ColumnType::TIMESTAMP => Ok(ColumnValue::Timestamp(secs)),
+        _ => Err(Error::Schema(format!(
+            "CURRENT_TIMESTAMP is not a valid default for {type_}"
+        ))),
+    }
+}
+
+/// Returns the current time as a `YYYY-MM-DD HH:MM:SS` literal, the value of `NOW()`.
+///
+/// The literal is parsed like any other value of the target column, so it is stored
+/// exactly in `DATETIME` and `TIMESTAMP` columns and as text in string columns.
+pub fn current_datetime() -> Result<String, Error> {
+    Ok(datetime::format_datetime(now_secs()?))
+}
 
-    Ok(row)
+/// Returns the current time as seconds since the Unix epoch.
+fn now_secs() -> Result<i64, Error> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| Error::Schema(format!("Invalid system time: {e}")))?
+        .as_secs() as i64)
 }
 
 // Optional: Implement additional traits for better usability
 impl std::fmt::Display for Row {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let formatted_columns: Vec<String> = self
-            .inner
+            .schema
+            .columns()
             .iter()
-            .map(|(name, value)| format!("{}: {}", name, value.to_string()))
+            .zip(&self.values)
+            .map(|(column, value)| format!("{}: {}", column.name, value.to_string()))
             .collect();
 
         write!(f, "Row({})", formatted_columns.join(", "))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::encoding;
+
+    #[test]
+    fn test_default_current_timestamp() {
+        let schema = Arc::new(TableSchema::new(vec![
+            ColumnSchema {
+                name: "id".into(),
+                type_: ColumnType::INT,
+                default: None,
+                is_primary: true,
+                is_nullable: false,
+            },
+            ColumnSchema {
+                name: "created_at".into(),
+                type_: ColumnType::TIMESTAMP,
+                default: Some(DefaultValue::Now),
+                is_primary: false,
+                is_nullable: false,
+            },
+        ]));
+        let now = || {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64
+        };
+
+        let before = now();
+        let row = build_row(&schema, &["id".into()], &["1".into()]).unwrap();
+        let after = now();
+
+        let Some(&ColumnValue::Timestamp(secs)) = row.get("created_at") else {
+            panic!("created_at is not a timestamp: {}", row);
+        };
+        assert!(
+            (before..=after).contains(&secs),
+            "{secs} not in {before}..={after}"
+        );
+    }
+
+    #[test]
+    fn test_datetime_round_trip() {
+        let schema = Arc::new(TableSchema::new(vec![ColumnSchema {
+            name: "created_at".into(),
+            type_: ColumnType::DATETIME,
+            default: None,
+            is_primary: false,
+            is_nullable: false,
+        }]));
+
+        let row = build_row(
+            &schema,
+            &["created_at".into()],
+            &["2024-01-02 03:04:05".into()],
+        )
+        .unwrap();
+        let encoded = encoding::encode_row(&schema, &row).unwrap();
+        let decoded = encoding::decode_row(&schema, &encoded).unwrap();
+        assert_eq!(
+            decoded.get_column("created_at").as_deref(),
+            Some("2024-01-02 03:04:05")
+        );
+
+        let err = build_row(&schema, &["created_at".into()], &["tomorrow".into()]).unwrap_err();
+        assert!(matches!(err, Error::Schema(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_to_values() {
+        let schema = crate::storage::table::SCHEMA.clone();
+        let row = build_row(
+            &schema,
+            &["id".into(), "email".into()],
+            &["7".into(), "user7@example.com".into()],
+        )
+        .unwrap();
+
+        let columns: Vec<String> = vec!["email".into(), "users.id".into(), "missing".into()];
+        let per_column: Vec<Option<String>> = columns.iter().map(|c| row.get_column(c)).collect();
+        assert_eq!(row.to_values(&columns), per_column);
+        assert_eq!(row.to_values(&columns)[2], None);
+    }
+
+    #[test]
+    fn test_build_row_unknown_column() {
+        let schema = crate::storage::table::SCHEMA.clone();
+        let columns: Vec<String> = vec!["id".into(), "email".into(), "nope".into()];
+        let values: Vec<String> = vec!["1".into(), "user1@example.com".into(), "x".into()];
+        match build_row(&schema, &columns, &values) {
+            Err(Error::Semantic(msg)) => assert_eq!(msg, "Unknown column 'nope' in 'field list'"),
+            r => panic!("Unexpected result: {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_get_id_range() {
+        let schema = crate::storage::table::SCHEMA.clone();
+        let mut row = build_row(
+            &schema,
+            &["id".into(), "email".into()],
+            &["-1".into(), "user@example.com".into()],
+        )
+        .unwrap();
+        match row.get_id(&schema) {
+            Err(Error::Schema(msg)) => assert_eq!(msg, "Primary key -1 out of range"),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        row.values[0] = ColumnValue::Int(u32::MAX as i64 + 1);
+        assert!(row.get_id(&schema).is_err());
+        row.values[0] = ColumnValue::Int(u32::MAX as i64);
+        assert_eq!(row.get_id(&schema).unwrap(), u32::MAX);
+    }
+
+    #[test]
+    fn test_integer_range() {
+        let schema = Arc::new(TableSchema::new(vec![
+            ColumnSchema {
+                name: "tiny".into(),
+                type_: ColumnType::TINYINT,
+                default: None,
+                is_primary: false,
+                is_nullable: false,
+            },
+            ColumnSchema {
+                name: "small".into(),
+                type_: ColumnType::SMALLINT,
+                default: None,
+                is_primary: false,
+                is_nullable: false,
+            },
+        ]));
+        let columns = vec!["tiny".to_string(), "small".to_string()];
+        let build = |tiny: &str, small: &str| {
+            build_row(&schema, &columns, &[tiny.to_string(), small.to_string()])
+        };
+
+        assert!(build("127", "32767").is_ok());
+        assert!(build("-128", "-32768").is_ok());
+        match build("200", "0") {
+            Err(Error::Schema(msg)) => assert_eq!(msg, "Value 200 out of range for TINYINT"),
+            r => panic!("Unexpected result: {:?}", r.map(|_| ())),
+        }
+        match build("0", "-32769") {
+            Err(Error::Schema(msg)) => assert_eq!(msg, "Value -32769 out of range for SMALLINT"),
+            r => panic!("Unexpected result: {:?}", r.map(|_| ())),
+        }
+        match build("12a", "0") {
+            Err(Error::Schema(msg)) => assert_eq!(msg, "Invalid TINYINT: 12a"),
+            r => panic!("Unexpected result: {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_parse_boolean() {
+        for value in ["true", "TRUE", "1", "t", "T", "yes", "Yes"] {
+            assert!(parse_boolean(value).unwrap(), "{value}");
+        }
+        for value in ["false", "False", "0", "f", "F", "no", "NO"] {
+            assert!(!parse_boolean(value).unwrap(), "{value}");
+        }
+        match parse_boolean("maybe") {
+            Err(Error::Schema(msg)) => assert_eq!(msg, "Invalid BOOLEAN: maybe"),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+}
@@ -1,22 +1,219 @@
 use super::column::ColumnType;
+use super::encoding::null_bitmap_size;
+use bincode::{Decode, Encode};
+use std::fmt;
+use std::sync::OnceLock;
 
-#[derive(Debug, Clone)]
+#[derive(Encode, Decode, Debug, Clone, PartialEq)]
 pub struct TableSchema {
-    pub columns: Vec<ColumnSchema>,
+    columns: Vec<ColumnSchema>,
     pub version: u32,
+    row_size: RowSizeCache,
 }
 
 impl TableSchema {
+    /// Creates a schema at version 0.
+    pub fn new(columns: Vec<ColumnSchema>) -> Self {
+        Self {
+            columns,
+            version: 0,
+            row_size: RowSizeCache::default(),
+        }
+    }
+
+    /// Returns the columns in row order.
+    pub fn columns(&self) -> &[ColumnSchema] {
+        &self.columns
+    }
+
+    /// Returns the columns for changing them, dropping the cached row size so it is
+    /// recomputed from the changed columns.
+    pub fn columns_mut(&mut self) -> &mut Vec<ColumnSchema> {
+        self.row_size = RowSizeCache::default();
+        &mut self.columns
+    }
+
+    /// Returns the encoded size of a row, computed from the columns on first use.
     pub fn get_row_size(&self) -> usize {
-        self.columns.iter().map(|c| c.type_.fixed_size()).sum()
+        *self.row_size.0.get_or_init(|| {
+            let values: usize = self.columns.iter().map(|c| c.type_.fixed_size()).sum();
+            values + null_bitmap_size(self.columns.len())
+        })
+    }
+
+    /// Marks the schema as altered, incrementing its version.
+    pub fn bump_version(&mut self) {
+        self.version += 1;
+    }
+
+    /// Builds the `CREATE TABLE` statement that recreates a table with this schema.
+    ///
+    /// # Arguments
+    /// * `name` - The table name.
+    pub fn to_create_sql(&self, name: &str) -> String {
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| {
+                let mut definition = format!("{} {}", column.name, column.type_);
+                if column.is_primary {
+                    definition.push_str(" PRIMARY KEY");
+                }
+                match &column.default {
+                    Some(DefaultValue::AutoIncrement) => definition.push_str(" AUTO_INCREMENT"),
+                    Some(default) => definition.push_str(&format!(" DEFAULT {}", default)),
+                    None => {}
+                }
+                definition
+            })
+            .collect();
+        format!("CREATE TABLE {} ({});", name, columns.join(", "))
     }
 }
 
-#[derive(Debug, Clone)]
+/// The cached row size of a `TableSchema`.
+///
+/// It is derived from the columns, so it is not stored: it encodes to nothing, decodes
+/// empty and never makes two schemas unequal.
+#[derive(Debug, Clone, Default)]
+struct RowSizeCache(OnceLock<usize>);
+
+impl PartialEq for RowSizeCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Encode for RowSizeCache {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        _: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        Ok(())
+    }
+}
+
+impl<Context> Decode<Context> for RowSizeCache {
+    fn decode<D: bincode::de::Decoder<Context = Context>>(
+        _: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self::default())
+    }
+}
+
+bincode::impl_borrow_decode!(RowSizeCache);
+
+#[derive(Encode, Decode, Debug, Clone, PartialEq)]
 pub struct ColumnSchema {
     pub name: String,
     pub type_: ColumnType,
-    pub default: Option<String>,
+    pub default: Option<DefaultValue>,
     pub is_primary: bool,
     pub is_nullable: bool,
 }
+
+impl ColumnSchema {
+    /// Returns whether the column can hold NULL; a primary key never can.
+    pub fn accepts_null(&self) -> bool {
+        self.is_nullable && !self.is_primary
+    }
+}
+
+/// The value a column takes when an `INSERT` omits it.
+#[derive(Encode, Decode, Debug, Clone, PartialEq)]
+pub enum DefaultValue {
+    /// A constant value, kept as written in the `CREATE TABLE` statement.
+    Literal(String),
+    /// The current time (`CURRENT_TIMESTAMP`), evaluated when the row is built.
+    Now,
+    /// One past the largest key in the table (`AUTO_INCREMENT`), for the primary key.
+    AutoIncrement,
+}
+
+impl fmt::Display for DefaultValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefaultValue::Literal(value) => write!(f, "{}", value),
+            DefaultValue::Now => write!(f, "CURRENT_TIMESTAMP"),
+            DefaultValue::AutoIncrement => write!(f, "AUTO_INCREMENT"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bincode::config;
+
+    fn column(name: &str, type_: ColumnType) -> ColumnSchema {
+        ColumnSchema {
+            name: name.into(),
+            type_,
+            default: None,
+            is_primary: false,
+            is_nullable: true,
+        }
+    }
+
+    #[test]
+    fn test_row_size_is_cached() {
+        let mut schema = TableSchema::new(vec![
+            column("id", ColumnType::INT),
+            column("name", ColumnType::VARCHAR(32)),
+        ]);
+        // Two columns share one byte of NULL bitmap
+        let size = ColumnType::INT.fixed_size() + ColumnType::VARCHAR(32).fixed_size() + 1;
+        assert_eq!(schema.get_row_size(), size);
+
+        // Changing the columns drops the cached size
+        schema
+            .columns_mut()
+            .push(column("flag", ColumnType::BOOLEAN));
+        assert_eq!(
+            schema.get_row_size(),
+            size + ColumnType::BOOLEAN.fixed_size()
+        );
+        assert_eq!(schema.version, 0);
+    }
+
+    #[test]
+    fn test_encoding_skips_row_size() {
+        let schema = TableSchema::new(vec![column("id", ColumnType::INT)]);
+        schema.get_row_size();
+
+        let encoded = bincode::encode_to_vec(&schema, config::standard()).unwrap();
+        let fields = (schema.columns.clone(), schema.version);
+        assert_eq!(
+            encoded,
+            bincode::encode_to_vec(&fields, config::standard()).unwrap()
+        );
+        let (decoded, _): (TableSchema, usize) =
+            bincode::decode_from_slice(&encoded, config::standard()).unwrap();
+        assert_eq!(decoded, schema);
+        assert_eq!(decoded.get_row_size(), schema.get_row_size());
+    }
+
+    #[test]
+    fn test_decode_schema_before_char_and_decimal() {
+        // (id INT PRIMARY KEY, active BOOLEAN DEFAULT true, created DATETIME DEFAULT
+        // CURRENT_TIMESTAMP, note TEXT), as stored before CHAR and DECIMAL were added
+        let stored: &[u8] = &[
+            4, 2, 105, 100, 0, 0, 1, 0, 6, 97, 99, 116, 105, 118, 101, 10, 1, 0, 4, 116, 114, 117,
+            101, 0, 1, 7, 99, 114, 101, 97, 116, 101, 100, 8, 1, 1, 0, 1, 4, 110, 111, 116, 101, 7,
+            0, 0, 1, 0,
+        ];
+        let (decoded, read): (TableSchema, usize) =
+            bincode::decode_from_slice(stored, config::standard()).unwrap();
+        assert_eq!(read, stored.len());
+
+        let mut id = column("id", ColumnType::INT);
+        (id.is_primary, id.is_nullable) = (true, false);
+        let mut active = column("active", ColumnType::BOOLEAN);
+        active.default = Some(DefaultValue::Literal("true".into()));
+        let mut created = column("created", ColumnType::DATETIME);
+        created.default = Some(DefaultValue::Now);
+        let expected =
+            TableSchema::new(vec![id, active, created, column("note", ColumnType::TEXT)]);
+        assert_eq!(decoded, expected);
+    }
+}
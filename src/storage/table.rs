@@ -5,7 +5,7 @@ use super::column::ColumnType;
 use super::cursor;
 use super::encoding;
 use super::row;
-use super::schema::{ColumnSchema, TableSchema};
+use super::schema::{ColumnSchema, DefaultValue, TableSchema};
 use crate::errors::Error;
 use bincode::{config, Decode, Encode};
 use heapless;
@@ -13,19 +13,24 @@ use once_cell::sync::Lazy;
 use std;
 use std::fs::File;
 use std::io::{Read, Write};
-use std::path::PathBuf;
-use std::sync::MutexGuard;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant};
 use tracing::{debug, info, trace, warn};
 
 #[derive(Encode, Decode, Debug)]
 pub struct TablespaceHeader {
+    /// Version of the on-disk table format, see `FORMAT_VERSION`.
+    pub format_version: u32,
     /// Number of actual records in the table.
     pub table_n_recs: u32,
     /// First page number.
     pub page_first: u32,
     /// Number of the root page
     pub root_page_num: u32,
+    /// Size of the encoded table schema that follows the header.
+    pub schema_size: u32,
 }
 
 #[derive(Encode, Decode, Debug)]
@@ -42,10 +47,20 @@ pub struct PageHeader {
     pub page_prev: u32,
     /// Next page number.
     pub page_next: u32,
+    /// CRC-32 of the page body.
+    pub page_checksum: u32,
 }
 
-pub const TABLESPACE_HEADER_SIZE: usize = 16;
-pub const PAGE_HEADER_SIZE: usize = 24;
+/// Version of the on-disk table format.
+///
+/// Version 2 added the format version and page checksums, growing both headers to 32 bytes.
+/// Version 3 stores the table schema between the tablespace header and the first page.
+/// Version 4 stores DATETIME and TIMESTAMP values as 8-byte epoch seconds instead of raw bytes.
+/// Version 5 ends every row with a bitmap of its NULL columns.
+pub const FORMAT_VERSION: u32 = 5;
+
+pub const TABLESPACE_HEADER_SIZE: usize = 32;
+pub const PAGE_HEADER_SIZE: usize = 32;
 
 /// Page size 4 kilobytes because it’s the same size as a page used in
 /// the virtual memory systems of most computer architectures.
@@ -53,8 +68,26 @@ pub const PAGE_SIZE: usize = 4096;
 
 pub const TABLE_MAX_PAGES: usize = 100;
 
-pub static SCHEMA: Lazy<TableSchema> = Lazy::new(|| TableSchema {
-    columns: vec![
+/// Default time to wait for a table lock before giving up.
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Interval between attempts to acquire a contended table lock.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Maximum time to wait for a node held by another thread before giving up.
+const NODE_LOCK_TIMEOUT: Duration = Duration::from_millis(100);
+/// Longest pause between two attempts to lock a busy node.
+const NODE_LOCK_MAX_BACKOFF: Duration = Duration::from_millis(8);
+
+/// Builds the error returned when a table would grow beyond `TABLE_MAX_PAGES`.
+fn page_limit_error() -> Error {
+    Error::ResourceLimit(format!(
+        "Table exceeded maximum page count ({})",
+        TABLE_MAX_PAGES
+    ))
+}
+
+pub static SCHEMA: Lazy<Arc<TableSchema>> = Lazy::new(|| {
+    Arc::new(TableSchema::new(vec![
         ColumnSchema {
             name: "id".into(),
             type_: ColumnType::INT,
@@ -65,7 +98,7 @@ pub static SCHEMA: Lazy<TableSchema> = Lazy::new(|| TableSchema {
         ColumnSchema {
             name: "username".into(),
             type_: ColumnType::VARCHAR(32),
-            default: Some("guest".into()),
+            default: Some(DefaultValue::Literal("guest".into())),
             is_primary: false,
             is_nullable: false,
         },
@@ -73,11 +106,10 @@ pub static SCHEMA: Lazy<TableSchema> = Lazy::new(|| TableSchema {
             name: "email".into(),
             type_: ColumnType::VARCHAR(255),
             default: None,
-            is_primary: true,
+            is_primary: false,
             is_nullable: false,
         },
-    ],
-    version: 0,
+    ]))
 });
 
 pub struct Table {
@@ -86,7 +118,9 @@ pub struct Table {
     pub database: String,
     pub root_page_num: u32,
     pub pager: Pager,
-    pub schema: TableSchema,
+    pub schema: Arc<TableSchema>,
+    /// Whether the table has changes not yet written to disk.
+    dirty: bool,
 }
 
 pub struct Pager {
@@ -100,8 +134,10 @@ impl Pager {
         Pager { pages, row_size }
     }
 
-    pub fn push(&mut self, node: Node) {
-        if let Err(_) = self.pages.push(Arc::new(Mutex::new(node))) {}
+    pub fn push(&mut self, node: Node) -> Result<(), Error> {
+        self.pages
+            .push(Arc::new(Mutex::new(node)))
+            .map_err(|_| page_limit_error())
     }
 
     pub fn try_create(&mut self, page_num: u32) -> Result<(), Error> {
@@ -111,11 +147,39 @@ impl Pager {
             n.set_node_type(btree::NodeType::NodeLeaf);
             n.set_leaf_node_num_cells(0);
             n.set_node_root(self.pages.len() == 0);
-            if let Err(_) = self.pages.push(Arc::new(Mutex::new(n))) {}
+            self.push(n)?;
         }
         Ok(())
     }
 
+    /// Checks that `count` more pages can be allocated before reaching `TABLE_MAX_PAGES`.
+    ///
+    /// # Arguments
+    /// * `count` - The number of pages about to be allocated.
+    ///
+    /// # Returns
+    /// An `Error::ResourceLimit` if the table would exceed its page cap.
+    pub fn ensure_free_pages(&self, count: usize) -> Result<(), Error> {
+        if self.pages.len() + count > TABLE_MAX_PAGES {
+            return Err(page_limit_error());
+        }
+        Ok(())
+    }
+
+    /// Returns the number of levels from the given page down to the leaves.
+    pub fn depth(&self, page_num: u32) -> Result<usize, Error> {
+        let mut depth = 1;
+        let mut page_num = page_num;
+        loop {
+            let node = self.get(page_num)?;
+            if node.get_node_type()? == NodeType::NodeLeaf {
+                return Ok(depth);
+            }
+            page_num = node.internal_node_child(0)?;
+            depth += 1;
+        }
+    }
+
     pub fn get(&self, page_num: u32) -> Result<MutexGuard<btree::Node>, Error> {
         let node_arc = match self.pages.get(page_num as usize) {
             Some(p) => p,
@@ -126,9 +190,28 @@ impl Pager {
             }
         };
 
-        node_arc
-            .try_lock()
-            .map_err(|_| Error::LockTable("Failed to lock the node".to_string()))
+        // Wait for a node held by another thread, backing off between attempts, so
+        // concurrent access serializes instead of failing on the first collision.
+        let deadline = Instant::now() + NODE_LOCK_TIMEOUT;
+        let mut backoff = Duration::from_micros(50);
+        loop {
+            match node_arc.try_lock() {
+                Ok(guard) => return Ok(guard),
+                Err(TryLockError::Poisoned(_)) => {
+                    return Err(Error::LockTable(format!(
+                        "Page {} is poisoned after a prior panic",
+                        page_num
+                    )))
+                }
+                Err(TryLockError::WouldBlock) if Instant::now() >= deadline => {
+                    return Err(Error::LockTable("Failed to lock the node".to_string()))
+                }
+                Err(TryLockError::WouldBlock) => {
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(NODE_LOCK_MAX_BACKOFF);
+                }
+            }
+        }
     }
 
     pub fn get_or_create(&mut self, page_num: u32) -> Result<MutexGuard<btree::Node>, Error> {
@@ -170,19 +253,55 @@ impl Pager {
     }
 }
 
+impl Drop for Table {
+    /// Flushes unsaved changes on a best-effort basis.
+    fn drop(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        if let Err(e) = self.flush() {
+            warn!(database = %self.database, table = %self.name, "Failed to flush table on drop: {}", e);
+        }
+    }
+}
+
 impl Table {
+    /// Writes the table to its file.
+    ///
+    /// A complete copy is written next to the table file and renamed over it, so a
+    /// failure part way through leaves the previous contents intact.
     pub fn flush(&mut self) -> Result<(), Error> {
+        let tmp_path = self.path.with_extension("tbd.tmp");
+        if let Err(e) = self.write_file(&tmp_path) {
+            let _ = std::fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+        std::fs::rename(&tmp_path, &self.path)?;
+        info!("Flushed {} pages.", self.pager.len());
+        self.mark_clean();
+
+        Ok(())
+    }
+
+    /// Writes the tablespace header, schema and every page to `path`, syncing it to disk.
+    fn write_file(&self, path: &Path) -> Result<(), Error> {
         let mut file = std::fs::OpenOptions::new()
             .write(true)
             .create(true)
-            .open(&self.path)?;
+            .truncate(true)
+            .open(path)?;
 
+        let schema = bincode::encode_to_vec(self.schema.as_ref(), config::standard())
+            .map_err(|e| Error::Encoding(format!("Failed to encode schema. {}", e)))?;
         let tablespace_header: [u8; TABLESPACE_HEADER_SIZE] = encode_header(&TablespaceHeader {
+            format_version: FORMAT_VERSION,
             page_first: 0,
             table_n_recs: self.pager.table_n_recs()?,
             root_page_num: self.root_page_num,
+            schema_size: schema.len() as u32,
         })?;
         file.write_all(&tablespace_header)?;
+        file.write_all(&schema)?;
 
         for i in 0..self.pager.len() {
             let page = match self.pager.get(i as u32) {
@@ -197,20 +316,129 @@ impl Table {
                 page_garbage: 0,
                 page_prev: 0,
                 page_next: 0,
+                page_checksum: encoding::crc32(page.as_slice()),
             })?;
             file.write_all(&page_header)?;
             file.write_all(&page.as_slice())?;
         }
-        info!("Flushed {} pages.", self.pager.len());
-
+        file.sync_all()?;
         Ok(())
     }
 
+    /// Returns the number of pages allocated by the table.
+    pub fn page_count(&self) -> usize {
+        self.pager.len()
+    }
+
+    /// Returns the number of rows in the table, summed from the leaf cell counts
+    /// without decoding any row.
+    pub fn row_count(&self) -> Result<u32, Error> {
+        self.pager.table_n_recs()
+    }
+
+    /// Returns whether the table has changes not yet written to disk.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Marks the table as modified, so it is flushed when dropped.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Marks the table as in sync with its file, so dropping it doesn't flush.
+    pub fn mark_clean(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Checks whether a row with the given primary key exists, without inserting it.
+    ///
+    /// Descends from the root the same way `Cursor::find` does, but only takes shared
+    /// access, then compares the key of the leaf cell it lands on.
+    ///
+    /// # Arguments
+    /// * `key` - The primary key to look up.
+    ///
+    /// # Returns
+    /// A `Result` containing whether the key is present, or an `Error`.
+    pub fn contains_key(&self, key: u32) -> Result<bool, Error> {
+        let mut page_num = self.root_page_num;
+        loop {
+            let node = self.pager.get(page_num)?;
+            if node.get_node_type()? == NodeType::NodeInternal {
+                page_num = node.internal_node_find(key)?;
+                continue;
+            }
+            let cell_num = node.leaf_node_find(key)?;
+            return Ok(cell_num < node.leaf_node_num_cells()?
+                && node.leaf_node_key(cell_num as usize)? == key);
+        }
+    }
+
+    /// Returns the largest key of the table, following the rightmost children.
+    ///
+    /// # Returns
+    /// A `Result` containing the key, `None` for an empty table, or an `Error`.
+    pub fn max_key(&self) -> Result<Option<u32>, Error> {
+        let mut page_num = self.root_page_num;
+        loop {
+            let node = self.pager.get(page_num)?;
+            if node.get_node_type()? == NodeType::NodeInternal {
+                page_num = node.internal_node_right_child()?;
+                continue;
+            }
+            return match node.leaf_node_num_cells()? {
+                0 => Ok(None),
+                n => Ok(Some(node.leaf_node_key(n as usize - 1)?)),
+            };
+        }
+    }
+
+    /// Returns every key of the table in order, without decoding any row.
+    ///
+    /// Descends to the leftmost leaf and follows the leaf sibling chain, so the result
+    /// also reflects whether that chain is intact.
+    ///
+    /// # Errors
+    /// Returns an `Error::Storage` if a page cannot be accessed, or if the chain visits
+    /// more leaves than the table has pages.
+    pub fn keys(&self) -> Result<Vec<u32>, Error> {
+        let mut page_num = self.root_page_num;
+        loop {
+            let node = self.pager.get(page_num)?;
+            if node.get_node_type()? == NodeType::NodeLeaf {
+                break;
+            }
+            page_num = node.internal_node_child(0)?;
+        }
+
+        let mut keys = Vec::new();
+        for _ in 0..self.pager.len() {
+            let leaf = self.pager.get(page_num)?;
+            for i in 0..leaf.leaf_node_num_cells()? as usize {
+                keys.push(leaf.leaf_node_key(i)?);
+            }
+            // 0 marks the last leaf, the root page never follows another leaf
+            page_num = leaf.leaf_node_next_leaf()?;
+            if page_num == 0 {
+                return Ok(keys);
+            }
+        }
+        Err(err!(
+            Storage,
+            "Leaf sibling chain of table '{}' has a cycle",
+            self.name
+        ))
+    }
+
     /// Builds a B-tree representation as columns and rows for all pages.
     ///
-    /// Iterates over pages 0, 1, and 2 (or all available pages up to 3), collecting data from each.
-    /// Returns a tuple of (columns, rows) where:
-    /// - `columns` is a `Vec<String>` of column headers: "Page", "Index", "Key".
+    /// Pages are visited in tree order, starting from the root and descending into
+    /// internal node children (see [`Table::walk_btree`]).
+    /// Returns a tuple of (total, columns, rows) where:
+    /// - `total` is the number of pages in the pager.
+    /// - `columns` is a `Vec<String>` of column headers: "Type", "Page", "Index", "Key", ...
+    ///   "Capacity" shows how full each node is as occupied/maximum cells, e.g. `7/13`.
     /// - `rows` is a `Vec<Vec<String>>` of row data, with each row representing a cell from a page.
     ///
     /// # Errors
@@ -229,35 +457,53 @@ impl Table {
         ];
 
         let mut rows = Vec::new();
-        for page_num in 0..total {
-            let node = self.pager.get(page_num as u32)?;
-            let num_cells = node.leaf_node_num_cells()?;
-
-            for i in 0..num_cells {
-                let (key, capacity, child) = if node.get_node_type()? == NodeType::NodeInternal {
-                    (
-                        node.internal_node_key(i as u32)?,
-                        node.internal_node_num_keys()?,
-                        node.internal_node_right_child()?,
-                    )
-                } else {
+        for (page_num, _) in self.walk_btree()? {
+            let node = self.pager.get(page_num)?;
+            let node_type = node.get_node_type()?;
+            let parent = node.node_parent()?;
+            let is_root = node.is_node_root()?;
+
+            // Internal nodes list one row per key (with its left child) followed by
+            // the right child; leaves list one row per cell and have no children.
+            let (capacity, cells) = match node_type {
+                NodeType::NodeInternal => {
+                    let num_keys = node.internal_node_num_keys()?;
+                    let mut cells = (0..num_keys)
+                        .map(|i| {
+                            Ok((
+                                node.internal_node_key(i)?.to_string(),
+                                node.internal_node_child(i)?.to_string(),
+                            ))
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    cells.push((
+                        "-".to_string(),
+                        node.internal_node_right_child()?.to_string(),
+                    ));
                     (
-                        node.leaf_node_key(i as usize)?,
-                        node.leaf_node_num_cells()?,
-                        node.internal_node_right_child()?,
+                        format!("{}/{}", num_keys, btree::INTERNAL_NODE_MAX_CELLS),
+                        cells,
                     )
-                };
+                }
+                NodeType::NodeLeaf => {
+                    let num_cells = node.leaf_node_num_cells()?;
+                    let cells = (0..num_cells as usize)
+                        .map(|i| Ok((node.leaf_node_key(i)?.to_string(), "-".to_string())))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    (format!("{}/{}", num_cells, node.max_cells()), cells)
+                }
+            };
 
-                let parent = node.node_parent()?;
+            for (i, (key, child)) in cells.into_iter().enumerate() {
                 let row = vec![
-                    node.get_node_type()?.to_string(),
+                    node_type.to_string(),
                     page_num.to_string(),    // Page number
                     i.to_string(),           // Cell index
-                    format!("{:?}", key),    // Key value (debug-formatted)
-                    format!("{:?}", parent), // Key value (debug-formatted)
-                    format!("{:?}", node.is_node_root()?),
-                    format!("{:?}", capacity),
-                    format!("{:?}", child),
+                    key,                     // Key value
+                    format!("{:?}", parent), // Parent page number
+                    format!("{:?}", is_root),
+                    capacity.clone(),
+                    child,
                 ];
                 rows.push(row);
             }
@@ -265,9 +511,124 @@ impl Table {
 
         Ok((total, columns, rows))
     }
+
+    /// Renders the B-tree as an indented outline, one line per node.
+    ///
+    /// Children are indented below their internal node, e.g.:
+    /// ```text
+    /// Internal page 0: keys [7]
+    ///   Leaf page 2: 7 cells [1..7]
+    ///   Leaf page 1: 7 cells [8..14]
+    /// ```
+    pub fn build_btree_outline(&self) -> Result<Vec<String>, Error> {
+        let mut lines = Vec::new();
+        for (page_num, depth) in self.walk_btree()? {
+            let node = self.pager.get(page_num)?;
+            let line = match node.get_node_type()? {
+                NodeType::NodeInternal => {
+                    let keys = (0..node.internal_node_num_keys()?)
+                        .map(|i| node.internal_node_key(i).map(|k| k.to_string()))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    format!("Internal page {}: keys [{}]", page_num, keys.join(", "))
+                }
+                NodeType::NodeLeaf => {
+                    let num_cells = node.leaf_node_num_cells()?;
+                    if num_cells == 0 {
+                        format!("Leaf page {}: empty", page_num)
+                    } else {
+                        format!(
+                            "Leaf page {}: {} cells [{}..{}]",
+                            page_num,
+                            num_cells,
+                            node.leaf_node_key(0)?,
+                            node.leaf_node_key(num_cells as usize - 1)?
+                        )
+                    }
+                }
+            };
+            lines.push(format!("{}{}", "  ".repeat(depth), line));
+        }
+        Ok(lines)
+    }
+
+    /// Walks the B-tree depth-first from the root page.
+    ///
+    /// Returns `(page_num, depth)` pairs in visiting order; children of an internal node
+    /// are visited left to right, ending with its right child.
+    pub fn walk_btree(&self) -> Result<Vec<(u32, usize)>, Error> {
+        let mut pages = Vec::new();
+        let mut stack = vec![(self.root_page_num, 0)];
+
+        while let Some((page_num, depth)) = stack.pop() {
+            if pages.len() >= self.pager.len() {
+                return Err(Error::Storage(format!(
+                    "B-tree of '{}' has a cycle at page {}.",
+                    self.name, page_num
+                )));
+            }
+            pages.push((page_num, depth));
+
+            let node = self.pager.get(page_num)?;
+            if node.get_node_type()? == NodeType::NodeInternal {
+                let num_keys = node.internal_node_num_keys()?;
+                for i in (0..=num_keys).rev() {
+                    stack.push((node.internal_node_child(i)?, depth + 1));
+                }
+            }
+        }
+        Ok(pages)
+    }
 }
 
-pub fn insert_row(table: &mut Table, row: &row::Row) -> Result<(), Error> {
+/// Acquires the lock of a table, waiting at most `timeout` for it to be released.
+///
+/// # Arguments
+/// * `table` - The table to lock, wrapped in an `Arc<Mutex<Table>>`.
+/// * `name` - The table name, used in error messages.
+/// * `timeout` - The maximum time to wait for the lock.
+///
+/// # Returns
+/// A `Result` containing the lock guard or an `Error::LockTable` if the lock is
+/// still held by someone else when the deadline passes.
+///
+/// A lock poisoned by a panic of its previous holder is recovered if the table had no
+/// unsaved changes, since it then still matches its file. Otherwise the panic may have
+/// left it half-modified, and locking fails with a distinct error.
+pub fn lock_with_timeout<'a>(
+    table: &'a Arc<Mutex<Table>>,
+    name: &str,
+    timeout: Duration,
+) -> Result<MutexGuard<'a, Table>, Error> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match table.try_lock() {
+            Ok(guard) => return Ok(guard),
+            Err(TryLockError::Poisoned(e)) => {
+                if e.get_ref().is_dirty() {
+                    return Err(err!(
+                        LockTable,
+                        "Table '{}' is poisoned after a prior panic",
+                        name
+                    ));
+                }
+                warn!(
+                    table = name,
+                    "Recovering the lock of a table after a prior panic"
+                );
+                table.clear_poison();
+                return Ok(e.into_inner());
+            }
+            Err(TryLockError::WouldBlock) => {
+                if Instant::now() >= deadline {
+                    return Err(err!(LockTable, "Timed out acquiring lock on '{}'", name));
+                }
+                thread::sleep(LOCK_RETRY_INTERVAL);
+            }
+        }
+    }
+}
+
+pub fn insert_row(table: &mut Table, row: &row::Row) -> Result<u32, Error> {
     let row_size = table.schema.get_row_size();
     let row_id = row.get_id(&table.schema)?;
     let row_bin = encoding::encode_row(&table.schema, row)?;
@@ -282,7 +643,7 @@ pub fn insert_row(table: &mut Table, row: &row::Row) -> Result<(), Error> {
     }
     let mut cursor = cursor::Cursor::find(table, row_id)?;
 
-    let mut node = cursor.table.pager.get(cursor.page_num)?;
+    let node = cursor.table.pager.get(cursor.page_num)?;
     let num_cells = node.leaf_node_num_cells()?;
 
     if cursor.cell_num < num_cells {
@@ -300,26 +661,69 @@ pub fn insert_row(table: &mut Table, row: &row::Row) -> Result<(), Error> {
             "Node full. Splitting a leaf node..."
         );
         drop(node);
+        // A split allocates a page per level plus a new root in the worst case; refuse
+        // up front instead of leaving the tree half-split.
+        let depth = cursor.table.pager.depth(cursor.table.root_page_num)?;
+        cursor.table.pager.ensure_free_pages(depth + 1)?;
+        cursor.table.mark_dirty();
         leaf_node_split_and_insert(&mut cursor, row_id, row_bin.clone())?;
-        return Ok(());
+        return Ok(row_id);
     }
 
-    if cursor.cell_num < num_cells {
-        // Make room for new cell
-        for i in (cursor.cell_num + 1..=num_cells).rev() {
-            let prev: Vec<u8>;
-            {
-                prev = node.leaf_node_cell(i as usize - 1)?.to_vec();
-            }
-            node.leaf_node_cell_mut(i as usize)?.copy_from_slice(&prev);
-        }
+    // The pager guard borrows the table, so release it to mark the table dirty
+    drop(node);
+    cursor.table.mark_dirty();
+    let mut node = cursor.table.pager.get(cursor.page_num)?;
+    let shifted = leaf_node_insert(&mut node, cursor.cell_num, row_id, &row_bin)?;
+    trace!(page_num = cursor.page_num, shifted, "Inserted a cell");
+
+    Ok(row_id)
+}
+
+/// Inserts a cell into a leaf node that has room for it.
+///
+/// Cells `[cell_num, num_cells)` are shifted one position to the right, into
+/// `[cell_num + 1, num_cells + 1)`, to make room; appending after the last cell (the
+/// common case for ascending keys) shifts nothing.
+///
+/// Fails with `Error::Storage` if the node is full or `cell_num` is past the last cell,
+/// so no cell beyond `max_cells` is ever written.
+///
+/// # Returns
+/// The number of cells shifted.
+pub fn leaf_node_insert(
+    node: &mut Node,
+    cell_num: u32,
+    key: u32,
+    value: &[u8],
+) -> Result<u32, Error> {
+    let num_cells = node.leaf_node_num_cells()?;
+    if num_cells as usize >= node.max_cells() {
+        return Err(Error::Storage(format!(
+            "Leaf node is full ({} cells)",
+            num_cells
+        )));
     }
+    if cell_num > num_cells {
+        return Err(Error::Storage(format!(
+            "Cell {} is past the end of a leaf node with {} cells",
+            cell_num, num_cells
+        )));
+    }
+
+    // Make room for the new cell, moving the last cell first
+    for i in (cell_num..num_cells).rev() {
+        let cell = node.leaf_node_cell(i as usize)?.to_vec();
+        node.leaf_node_cell_mut(i as usize + 1)?
+            .copy_from_slice(&cell);
+    }
+    let shifted = num_cells - cell_num;
 
     node.set_leaf_node_num_cells(num_cells + 1);
-    node.set_leaf_node_key(cursor.cell_num as usize, row_id)?;
-    node.set_leaf_node_value(cursor.cell_num as usize, row_bin.as_slice())?;
+    node.set_leaf_node_key(cell_num as usize, key)?;
+    node.set_leaf_node_value(cell_num as usize, value)?;
 
-    Ok(())
+    Ok(shifted)
 }
 
 /// Create a new node and move half the cells over.
@@ -434,113 +838,108 @@ pub fn internal_node_split_and_insert(
         child_page_num, "Splitting internal node..."
     );
 
-    let old_page_num = parent_page_num;
-
-    // Precompute all values that require immutable borrows
-    let (old_max, child_max, splitting_root, old_node_parent, old_num_keys, right_child_page_num) = {
-        let old_node = cursor.table.pager.get(old_page_num)?;
-        let child = cursor.table.pager.get(child_page_num)?;
+    let (old_max, splitting_root) = {
+        let old_node = cursor.table.pager.get(parent_page_num)?;
         (
             cursor.table.pager.get_node_max_key(&old_node)?,
-            cursor.table.pager.get_node_max_key(&child)?,
             old_node.is_node_root()?,
-            old_node.node_parent()?,
-            old_node.internal_node_num_keys()?,
-            old_node.internal_node_right_child()?,
         )
     };
+    let child_max = {
+        let child = cursor.table.pager.get(child_page_num)?;
+        cursor.table.pager.get_node_max_key(&child)?
+    };
     let new_page_num = cursor.table.pager.get_unused_page_num() as u32;
 
-    // Initialize the parent node
-    let parent_id = {
-        if splitting_root {
-            create_new_root(cursor, new_page_num)?;
-            cursor.table.root_page_num
-        } else {
-            let mut new_node = cursor.table.pager.get(new_page_num)?;
-            initialize_internal_node(&mut new_node)?;
-            old_node_parent
-        }
+    // When splitting the root, its contents move to a new left child and the root
+    // page becomes their parent, so the node to split is that left child.
+    let old_page_num = if splitting_root {
+        create_new_root(cursor, new_page_num)?;
+        let root = cursor.table.pager.get(cursor.table.root_page_num)?;
+        root.internal_node_child(0)?
+    } else {
+        let mut new_node = cursor.table.pager.get_or_create(new_page_num)?;
+        initialize_internal_node(&mut new_node)?;
+        parent_page_num
     };
 
-    // Split the old node and move keys/children
-    {
+    // Move the right child and the upper half of the children to the new node.
+    let right_child_page_num = {
         let mut old_node = cursor.table.pager.get(old_page_num)?;
-        let mut current_num_keys = old_num_keys;
-
-        // Move the right child to the new node
-        {
-            let mut cur = cursor.table.pager.get(right_child_page_num)?;
-            cur.set_node_parent(new_page_num);
-        }
+        let right_child_page_num = old_node.internal_node_right_child()?;
         old_node.set_internal_node_right_child(btree::INVALID_PAGE_NUM);
-
-        // Collect children to move to the new node
-        let mut children_to_move = Vec::new();
-        for i in (btree::INTERNAL_NODE_MAX_CELLS / 2 + 1..btree::INTERNAL_NODE_MAX_CELLS).rev() {
+        right_child_page_num
+    };
+    move_internal_node_child(cursor, right_child_page_num, new_page_num)?;
+    for i in (btree::INTERNAL_NODE_MAX_CELLS / 2 + 1..btree::INTERNAL_NODE_MAX_CELLS).rev() {
+        let cur_page_num = {
+            let mut old_node = cursor.table.pager.get(old_page_num)?;
             let cur_page_num = old_node.internal_node_child(i as u32)?;
-            children_to_move.push(cur_page_num);
-            current_num_keys -= 1;
-            old_node.set_internal_node_num_keys(current_num_keys);
-        }
-
-        // Update the old node's right child
-        let new_right_child = old_node.internal_node_child(current_num_keys - 1)?;
-        old_node
-            .internal_node_right_child_mut()?
-            .copy_from_slice(&new_right_child.to_le_bytes());
-        current_num_keys -= 1;
-        old_node.set_internal_node_num_keys(current_num_keys);
-
-        // Drop old_node borrow before mutable operations
-        drop(old_node);
-
-        // Perform insertions into the new node
-        for cur_page_num in children_to_move {
-            internal_node_insert(cursor, new_page_num, cur_page_num)?;
-            let mut cur = cursor.table.pager.get(cur_page_num)?;
-            cur.set_node_parent(new_page_num);
-        }
+            old_node.set_internal_node_num_keys(i as u32);
+            cur_page_num
+        };
+        move_internal_node_child(cursor, cur_page_num, new_page_num)?;
     }
 
-    // Compute max_after_split and determine destination
+    // The child before the moved ones becomes the right child of the old node.
     let max_after_split = {
-        let old_node = cursor.table.pager.get(old_page_num)?;
+        let mut old_node = cursor.table.pager.get(old_page_num)?;
+        let num_keys = old_node.internal_node_num_keys()?;
+        let new_right_child = old_node.internal_node_child(num_keys - 1)?;
+        old_node.set_internal_node_right_child(new_right_child);
+        old_node.set_internal_node_num_keys(num_keys - 1);
         cursor.table.pager.get_node_max_key(&old_node)?
     };
+
     let destination_page_num = if child_max < max_after_split {
         old_page_num
     } else {
         new_page_num
     };
+    move_internal_node_child(cursor, child_page_num, destination_page_num)?;
 
-    // Insert the child
-    internal_node_insert(cursor, destination_page_num, child_page_num)?;
-    {
-        let mut child = cursor.table.pager.get(child_page_num)?;
-        child.set_node_parent(destination_page_num);
-    }
-
-    // Update parent key and handle root splitting
-    {
+    let (old_node_parent, new_old_max) = {
         let old_node = cursor.table.pager.get(old_page_num)?;
-        let mut parent = cursor.table.pager.get(parent_id)?;
-        parent
-            .update_internal_node_key(old_max, cursor.table.pager.get_node_max_key(&old_node)?)?;
-    }
-    if splitting_root {
-        let parent_page_num = {
-            let old_node = cursor.table.pager.get(old_page_num)?;
-            old_node.node_parent()?
-        };
-        internal_node_insert(cursor, parent_page_num, new_page_num)?;
-        let mut new_node = cursor.table.pager.get(new_page_num)?;
-        new_node.set_node_parent(parent_page_num);
+        (
+            old_node.node_parent()?,
+            cursor.table.pager.get_node_max_key(&old_node)?,
+        )
+    };
+    cursor
+        .table
+        .pager
+        .get(old_node_parent)?
+        .update_internal_node_key(old_max, new_old_max)?;
+
+    // A new root already points to the new node as its right child.
+    if !splitting_root {
+        // Set before inserting, so a split of the parent can move the new node again.
+        cursor
+            .table
+            .pager
+            .get(new_page_num)?
+            .set_node_parent(old_node_parent);
+        internal_node_insert(cursor, old_node_parent, new_page_num)?;
     }
 
     Ok(())
 }
 
+/// Inserts `child_page_num` into the internal node `parent_page_num` and makes that
+/// node its parent.
+fn move_internal_node_child(
+    cursor: &mut cursor::Cursor,
+    child_page_num: u32,
+    parent_page_num: u32,
+) -> Result<(), Error> {
+    cursor
+        .table
+        .pager
+        .get(child_page_num)?
+        .set_node_parent(parent_page_num);
+    internal_node_insert(cursor, parent_page_num, child_page_num)
+}
+
 // Add a new child/key pair to parent that corresponds to child
 // Because we store the rightmost child pointer separately from the rest of the child/key pairs, we have to handle
 // things differently if the new child is going to become the rightmost child.
@@ -554,58 +953,43 @@ pub fn internal_node_insert(
 ) -> Result<(), Error> {
     debug!(parent_page_num, child_page_num, "Inserting internal node");
 
+    let child_max_key = {
+        let child = cursor.table.pager.get(child_page_num)?;
+        cursor.table.pager.get_node_max_key(&child)?
+    };
     let mut parent = cursor.table.pager.get(parent_page_num)?;
-    let child = cursor.table.pager.get(child_page_num)?;
-    let child_max_key: u32 = cursor.table.pager.get_node_max_key(&child)?;
-
-    // The index where the new cell (child/key pair) should be inserted depends on the maximum key in the new child.
-    let index = parent.internal_node_find_child(child_max_key)?;
-
     let original_num_keys = parent.internal_node_num_keys()?;
-    parent.set_internal_node_num_keys(original_num_keys + 1);
 
     if original_num_keys >= btree::INTERNAL_NODE_MAX_CELLS as u32 {
         drop(parent);
-        drop(child);
-        internal_node_split_and_insert(cursor, parent_page_num, child_page_num)?;
-        return Ok(());
+        return internal_node_split_and_insert(cursor, parent_page_num, child_page_num);
     }
 
-    let p2 = parent.clone(); // XXX: bullshit
-    let right_child_page_num = p2.internal_node_right_child()?;
+    let right_child_page_num = parent.internal_node_right_child()?;
     // An internal node with a right child of INVALID_PAGE_NUM is empty
     if right_child_page_num == btree::INVALID_PAGE_NUM {
         parent.set_internal_node_right_child(child_page_num);
         return Ok(());
     }
-    let right_child = cursor.table.pager.get(right_child_page_num)?;
+    let right_child_max_key = {
+        let right_child = cursor.table.pager.get(right_child_page_num)?;
+        cursor.table.pager.get_node_max_key(&right_child)?
+    };
 
-    // If we are already at the max number of cells for a node, we cannot increment
-    // before splitting. Incrementing without inserting a new key/child pair
-    // and immediately calling internal_node_split_and_insert has the effect
-    // of creating a new key at (max_cells + 1) with an uninitialized value.
+    // The index where the new cell (child/key pair) should be inserted depends on the maximum key in the new child.
+    let index = parent.internal_node_find_child(child_max_key)?;
     parent.set_internal_node_num_keys(original_num_keys + 1);
 
-    if child_max_key > cursor.table.pager.get_node_max_key(&right_child)? {
+    if child_max_key > right_child_max_key {
         trace!("Replace right child");
-        //
-        parent
-            .internal_node_child_mut(original_num_keys)?
-            .copy_from_slice(&right_child_page_num.to_le_bytes());
-        parent.set_internal_node_key(
-            original_num_keys,
-            cursor.table.pager.get_node_max_key(&right_child)?,
-        )?;
-        parent
-            .internal_node_right_child_mut()?
-            .copy_from_slice(&child_page_num.to_le_bytes());
+        parent.set_internal_node_child(original_num_keys, right_child_page_num)?;
+        parent.set_internal_node_key(original_num_keys, right_child_max_key)?;
+        parent.set_internal_node_right_child(child_page_num);
     } else {
         trace!("Make room for the new cell");
-        let source_parent = parent.clone();
         for i in (index + 1..=original_num_keys).rev() {
-            let destination = parent.internal_node_cell_mut(i)?;
-            let source = source_parent.internal_node_cell(i - 1)?;
-            destination.copy_from_slice(source);
+            let source = parent.internal_node_cell(i - 1)?.to_vec();
+            parent.internal_node_cell_mut(i)?.copy_from_slice(&source);
         }
         parent.set_internal_node_child(index, child_page_num)?;
         parent.set_internal_node_key(index, child_max_key)?;
@@ -633,15 +1017,20 @@ pub fn create_new_root(
     // New root node points to two children
     //
     debug!(right_child_page_num, "Creating a new root");
-    let left_child_page_num = cursor.table.pager.get_unused_page_num();
-
+    // The right child is created first, so the left child doesn't take its page number
     cursor.table.pager.try_create(right_child_page_num)?;
+    let left_child_page_num = cursor.table.pager.get_unused_page_num();
     cursor.table.pager.try_create(left_child_page_num as u32)?;
 
     let mut root = cursor.table.pager.get(cursor.table.root_page_num)?;
     let mut right_child = cursor.table.pager.get(right_child_page_num)?;
     let mut left_child = cursor.table.pager.get(left_child_page_num as u32)?;
 
+    // An internal root is split into an internal right child, filled by the caller
+    if root.get_node_type()? == NodeType::NodeInternal {
+        initialize_internal_node(&mut right_child)?;
+    }
+
     // The old root is copied to the left child so we can reuse the root page
     left_child.data.copy_from_slice(&root.data);
     left_child.set_node_root(false);
@@ -661,6 +1050,22 @@ pub fn create_new_root(
     left_child.set_node_parent(cursor.table.root_page_num);
     right_child.set_node_parent(cursor.table.root_page_num);
 
+    // The children of an internal root now belong to the left child
+    let grandchildren = match left_child.get_node_type()? {
+        NodeType::NodeInternal => (0..=left_child.internal_node_num_keys()?)
+            .map(|i| left_child.internal_node_child(i))
+            .collect::<Result<Vec<_>, _>>()?,
+        NodeType::NodeLeaf => Vec::new(),
+    };
+    drop((root, right_child, left_child));
+    for page_num in grandchildren {
+        cursor
+            .table
+            .pager
+            .get(page_num)?
+            .set_node_parent(left_child_page_num as u32);
+    }
+
     Ok(())
 }
 
@@ -685,32 +1090,308 @@ pub fn initialize_internal_node(node: &mut Node) -> Result<(), Error> {
     Ok(())
 }
 
-pub fn select_rows(table: &mut Table) -> Result<std::vec::Vec<row::Row>, Error> {
+/// Reads the rows of a table in key order.
+///
+/// # Arguments
+/// * `table` - The table to scan.
+/// * `max_rows` - Stop the scan after this many rows; `None` reads the whole table.
+pub fn select_rows(
+    table: &mut Table,
+    max_rows: Option<usize>,
+) -> Result<std::vec::Vec<row::Row>, Error> {
     let mut rows = std::vec::Vec::new();
-    let row_size = table.schema.get_row_size();
+    if max_rows == Some(0) {
+        return Ok(rows);
+    }
+    for_each_row(table, |row| {
+        rows.push(row);
+        Ok(max_rows.is_none_or(|max| rows.len() < max))
+    })?;
+    Ok(rows)
+}
+
+/// Decodes the rows of a table one at a time, in key order, without buffering them.
+///
+/// # Arguments
+/// * `table` - The table to scan.
+/// * `f` - Called with each row; returning `false` stops the scan.
+pub fn for_each_row<F>(table: &mut Table, mut f: F) -> Result<(), Error>
+where
+    F: FnMut(row::Row) -> Result<bool, Error>,
+{
+    let schema = table.schema.clone();
+    let mut buf = vec![0; schema.get_row_size()];
 
     let mut cursor = cursor::Cursor::start(table)?;
     while !cursor.end_of_table {
-        let mut buf = vec![];
-        buf.resize(row_size, 0);
         cursor.read_value(&mut buf)?;
-
-        rows.push(encoding::decode_row(&SCHEMA, &buf)?);
+        if !f(encoding::decode_row(&schema, &buf)?)? {
+            break;
+        }
         cursor.advance()?;
     }
-    Ok(rows)
+    Ok(())
 }
 
-pub fn load_table(database: &String, name: &String) -> Result<Table, Error> {
-    let path = PathBuf::from(format!("data/{}/{}.tbd", database, name));
+/// Looks up a row by its primary key.
+///
+/// # Returns
+/// A `Result` containing the row, `None` if no row has the key, or an `Error`.
+pub fn find_row(table: &mut Table, key: u32) -> Result<Option<row::Row>, Error> {
+    let row_size = table.schema.get_row_size();
+    let cursor = cursor::Cursor::find(table, key)?;
+    let (page_num, cell_num) = (cursor.page_num, cursor.cell_num as usize);
+
+    let node = table.pager.get(page_num)?;
+    if cell_num >= node.leaf_node_num_cells()? as usize || node.leaf_node_key(cell_num)? != key {
+        return Ok(None);
+    }
+    let buf = node.leaf_node_value(cell_num)?[..row_size].to_vec();
+    Ok(Some(encoding::decode_row(&table.schema, &buf)?))
+}
+
+/// Deletes the rows with the given primary keys.
+///
+/// Each cell is removed from its leaf and the cells after it shift left. When it held
+/// the max key of the leaf, the separator above the leaf is lowered to the new max,
+/// so every separator keeps the max key of its child. A leaf is never left empty: the
+/// keys whose removal would empty a leaf are put aside, and once every other key is
+/// deleted the tree is rebuilt a single time from the remaining rows.
+///
+/// # Returns
+/// A `Result` containing the number of deleted rows, or an `Error`.
+pub fn delete_rows(table: &mut Table, keys: &[u32]) -> Result<u64, Error> {
+    let mut deleted = 0;
+    let mut last_cells = Vec::new();
+    for &key in keys {
+        match delete_cell(table, key)? {
+            CellDeletion::Missing => {}
+            CellDeletion::Deleted => deleted += 1,
+            CellDeletion::LastCell => last_cells.push(key),
+        }
+    }
+    if !last_cells.is_empty() {
+        let mut rows = select_rows(table, None)?;
+        let schema = table.schema.clone();
+        let last_cells: std::collections::HashSet<u32> = last_cells.into_iter().collect();
+        rows.retain(|row| {
+            row.get_id(&schema)
+                .map_or(true, |id| !last_cells.contains(&id))
+        });
+        table.pager = bulk_load(&schema, &rows)?;
+        table.root_page_num = 0;
+        deleted += last_cells.len() as u64;
+    }
+    Ok(deleted)
+}
+
+/// The outcome of removing a single cell from its leaf.
+enum CellDeletion {
+    Missing,
+    Deleted,
+    /// The key is the only cell of a non-root leaf, which is left untouched.
+    LastCell,
+}
+
+/// Removes the cell with the given key from its leaf, unless it is the last one.
+fn delete_cell(table: &mut Table, key: u32) -> Result<CellDeletion, Error> {
+    let cursor = cursor::Cursor::find(table, key)?;
+    let (page_num, cell_num) = (cursor.page_num, cursor.cell_num as usize);
+
+    let (num_cells, is_root) = {
+        let node = table.pager.get(page_num)?;
+        let num_cells = node.leaf_node_num_cells()? as usize;
+        if cell_num >= num_cells || node.leaf_node_key(cell_num)? != key {
+            return Ok(CellDeletion::Missing);
+        }
+        (num_cells, node.is_node_root()?)
+    };
+    table.mark_dirty();
+
+    if num_cells == 1 && !is_root {
+        return Ok(CellDeletion::LastCell);
+    }
+
+    let new_max = {
+        let mut node = table.pager.get(page_num)?;
+        for i in cell_num..num_cells - 1 {
+            let cell = node.leaf_node_cell(i + 1)?.to_vec();
+            node.leaf_node_cell_mut(i)?.copy_from_slice(&cell);
+        }
+        node.set_leaf_node_num_cells(num_cells as u32 - 1);
+        match cell_num == num_cells - 1 && !is_root {
+            true => Some(node.leaf_node_key(num_cells - 2)?),
+            false => None,
+        }
+    };
+    if let Some(new_max) = new_max {
+        update_max_key(table, page_num, new_max)?;
+    }
+    Ok(CellDeletion::Deleted)
+}
+
+/// Sets the separator of the node at `page_num` to its new max key.
+///
+/// A right child has no separator in its parent; its max key is the max key of the
+/// parent, so the update goes on with the parent, up to the root.
+fn update_max_key(table: &mut Table, mut page_num: u32, new_max: u32) -> Result<(), Error> {
+    loop {
+        let parent_page_num = {
+            let node = table.pager.get(page_num)?;
+            if node.is_node_root()? {
+                return Ok(());
+            }
+            node.node_parent()?
+        };
+        let mut parent = table.pager.get(parent_page_num)?;
+        let num_keys = parent.internal_node_num_keys()?;
+        match (0..num_keys).find(|&i| parent.internal_node_child(i).ok() == Some(page_num)) {
+            Some(index) => return parent.set_internal_node_key(index, new_max),
+            None => page_num = parent_page_num,
+        }
+    }
+}
+
+/// Discards all rows of a table, keeping its schema and file.
+///
+/// The pager is reinitialized with a single empty root leaf at page 0.
+pub fn truncate_table(table: &mut Table) -> Result<(), Error> {
+    let mut pager = Pager::new(table.schema.get_row_size() as u32);
+    pager.try_create(0)?;
+    table.pager = pager;
+    table.root_page_num = 0;
+    table.mark_dirty();
+    Ok(())
+}
+
+/// Rewrites a table into a densely packed B-tree.
+///
+/// Rows are read in key order and bulk loaded into fresh pages: every leaf but the
+/// last is filled to capacity and internal nodes take as many children as they hold.
+/// The new tree replaces the table file through `Table::flush`; if that fails, the
+/// in-memory tree is restored and the old file is left untouched.
+///
+/// # Returns
+/// A `Result` containing the number of pages before and after the rewrite, or an `Error`.
+pub fn optimize_table(table: &mut Table) -> Result<(usize, usize), Error> {
+    let rows = select_rows(table, None)?;
+    let pages_before = table.pager.len();
+    let pager = bulk_load(&table.schema, &rows)?;
+    let pages_after = pager.len();
+
+    let old_pager = std::mem::replace(&mut table.pager, pager);
+    let old_root_page_num = std::mem::replace(&mut table.root_page_num, 0);
+    if let Err(e) = table.flush() {
+        table.pager = old_pager;
+        table.root_page_num = old_root_page_num;
+        return Err(e);
+    }
+    info!(pages_before, pages_after, "Optimized table {}", table.name);
+    Ok((pages_before, pages_after))
+}
+
+/// Builds a pager holding the given rows, which must be sorted by primary key.
+///
+/// The root stays at page 0; leaves follow in key order and are linked through their
+/// next-leaf pointers, then each internal level is appended above them.
+fn bulk_load(schema: &TableSchema, rows: &[row::Row]) -> Result<Pager, Error> {
+    let mut pager = Pager::new(schema.get_row_size() as u32);
+    pager.try_create(0)?;
+    let max_cells = pager.get(0)?.max_cells();
+    if rows.len() <= max_cells {
+        let mut root = pager.get(0)?;
+        for (i, row) in rows.iter().enumerate() {
+            let value = encoding::encode_row(schema, row)?;
+            leaf_node_insert(&mut root, i as u32, row.get_id(schema)?, &value)?;
+        }
+        drop(root);
+        return Ok(pager);
+    }
+
+    // (page number, max key) of every node on the level being built.
+    let mut level = Vec::new();
+    for chunk in rows.chunks(max_cells) {
+        let page_num = pager.len() as u32;
+        let mut node = pager.get_or_create(page_num)?;
+        initialize_leaf_node(&mut node)?;
+        let mut max_key = 0;
+        for (i, row) in chunk.iter().enumerate() {
+            max_key = row.get_id(schema)?;
+            let value = encoding::encode_row(schema, row)?;
+            leaf_node_insert(&mut node, i as u32, max_key, &value)?;
+        }
+        drop(node);
+        level.push((page_num, max_key));
+    }
+    for pair in level.windows(2) {
+        pager.get(pair[0].0)?.set_leaf_node_next_leaf(pair[1].0);
+    }
+
+    let fanout = btree::INTERNAL_NODE_MAX_CELLS + 1;
+    while level.len() > fanout {
+        // Spread children evenly so no internal node is left with a single child.
+        let groups = level.len().div_ceil(fanout);
+        let mut parents = Vec::new();
+        let mut start = 0;
+        for i in 0..groups {
+            let end = level.len() * (i + 1) / groups;
+            let page_num = pager.len() as u32;
+            pager.try_create(page_num)?;
+            link_internal_node(&pager, page_num, &level[start..end])?;
+            parents.push((page_num, level[end - 1].1));
+            start = end;
+        }
+        level = parents;
+    }
+    link_internal_node(&pager, 0, &level)?;
+    pager.get(0)?.set_node_root(true);
+    Ok(pager)
+}
+
+/// Initializes `page_num` as an internal node over `children` and points them at it.
+fn link_internal_node(pager: &Pager, page_num: u32, children: &[(u32, u32)]) -> Result<(), Error> {
+    let mut node = pager.get(page_num)?;
+    initialize_internal_node(&mut node)?;
+    let Some(((right_child, _), rest)) = children.split_last() else {
+        return Err(Error::Storage("Internal node needs a child".into()));
+    };
+    node.set_internal_node_num_keys(rest.len() as u32);
+    for (i, (child, key)) in rest.iter().enumerate() {
+        node.set_internal_node_child(i as u32, *child)?;
+        node.set_internal_node_key(i as u32, *key)?;
+    }
+    node.set_internal_node_right_child(*right_child);
+    drop(node);
+    for (child, _) in children {
+        pager.get(*child)?.set_node_parent(page_num);
+    }
+    Ok(())
+}
+
+/// Loads a table from its tablespace file in the database directory `dir`.
+pub fn load_table(dir: &Path, database: &String, name: &String) -> Result<Table, Error> {
+    let path = table_path(dir, name);
 
-    let row_size = SCHEMA.get_row_size();
-    let mut pager = Pager::new(row_size as u32);
     let mut file = std::fs::File::open(&path)?;
 
     let mut tablespace_header_buf = [0u8; TABLESPACE_HEADER_SIZE];
     file.read_exact(&mut tablespace_header_buf)?;
     let tablespace_header: TablespaceHeader = decode_header(&tablespace_header_buf)?;
+    if tablespace_header.format_version != FORMAT_VERSION {
+        return Err(Error::Storage(format!(
+            "Table '{}.{}' has unsupported format version {} (expected {})",
+            database, name, tablespace_header.format_version, FORMAT_VERSION
+        )));
+    }
+
+    let mut schema_buf = vec![0u8; tablespace_header.schema_size as usize];
+    file.read_exact(&mut schema_buf)?;
+    let (schema, _): (TableSchema, usize) =
+        bincode::decode_from_slice(&schema_buf, config::standard())
+            .map_err(|e| Error::Encoding(format!("Failed to decode schema. {}", e)))?;
+
+    let row_size = schema.get_row_size();
+    let mut pager = Pager::new(row_size as u32);
 
     loop {
         let mut page_header_buf = [0u8; PAGE_HEADER_SIZE];
@@ -726,8 +1407,14 @@ pub fn load_table(database: &String, name: &String) -> Result<Table, Error> {
         if read == 0 {
             break;
         }
+        if encoding::crc32(&page_buf) != page_header.page_checksum {
+            return Err(Error::Storage(format!(
+                "Page {} checksum mismatch",
+                pager.len()
+            )));
+        }
         let node = Node::new(&page_buf, row_size);
-        pager.push(node);
+        pager.push(node)?;
     }
 
     debug!(
@@ -743,18 +1430,45 @@ pub fn load_table(database: &String, name: &String) -> Result<Table, Error> {
         database: database.clone(),
         root_page_num: tablespace_header.root_page_num,
         pager,
-        schema: SCHEMA.clone(),
+        schema: Arc::new(schema),
+        dirty: false,
     };
     Ok(table)
 }
 
-pub fn create_table(database: &String, name: &String) -> Result<Table, Error> {
+/// Creates a new, empty table with the given schema.
+///
+/// The B-Tree is keyed by a `u32`, so the schema must have exactly one `INT` primary key
+/// column.
+pub fn create_table(
+    dir: &Path,
+    database: &String,
+    name: &String,
+    schema: Arc<TableSchema>,
+) -> Result<Table, Error> {
+    let mut primary = schema.columns().iter().filter(|c| c.is_primary);
+    match (primary.next(), primary.next()) {
+        (None, _) => return Err(Error::Schema("Table must have a primary key".into())),
+        (Some(_), Some(_)) => {
+            return Err(Error::Schema(
+                "Composite primary keys are not supported".into(),
+            ))
+        }
+        (Some(column), None) if !matches!(column.type_, ColumnType::INT) => {
+            return Err(Error::Schema(format!(
+                "Primary key column '{}' must be INT",
+                column.name
+            )))
+        }
+        (Some(_), None) => {}
+    }
+
     let root_page_num = 0;
-    let row_size = SCHEMA.get_row_size();
+    let row_size = schema.get_row_size();
     let mut pager = Pager::new(row_size as u32);
     pager.try_create(0)?;
 
-    let path = PathBuf::from(format!("data/{}/{}.tbd", database, name));
+    let path = table_path(dir, name);
     if path.exists() {
         return Err(Error::Storage(format!(
             "Table '{}.{}' already exists",
@@ -769,13 +1483,15 @@ pub fn create_table(database: &String, name: &String) -> Result<Table, Error> {
         path,
         root_page_num,
         pager,
-        schema: SCHEMA.clone(),
+        schema,
+        // The root page is only written on the first flush.
+        dirty: true,
     };
     Ok(table)
 }
 
-pub fn drop_table(database: &String, name: &String) -> Result<(), Error> {
-    let path = PathBuf::from(format!("data/{}/{}.tbd", database, name));
+pub fn drop_table(dir: &Path, database: &String, name: &String) -> Result<(), Error> {
+    let path = table_path(dir, name);
     if !path.exists() {
         return Err(Error::Storage(format!(
             "Unknown table '{}.{}'",
@@ -786,20 +1502,90 @@ pub fn drop_table(database: &String, name: &String) -> Result<(), Error> {
     Ok(())
 }
 
-pub fn show_tables(database: &String) -> Result<std::vec::Vec<String>, Error> {
-    let path = PathBuf::from(format!("data/{}", database));
-    std::fs::create_dir_all(&path)?;
+/// Renames a table and moves its tablespace file to match the new name.
+///
+/// Fails with `Error::Schema` if a table file with the new name already exists.
+pub fn rename_table(table: &mut Table, new_name: &String) -> Result<(), Error> {
+    let path = table.path.with_file_name(format!("{}.tbd", new_name));
+    if path.exists() {
+        return Err(Error::Schema(format!(
+            "Table '{}.{}' already exists",
+            table.database, new_name
+        )));
+    }
+    std::fs::rename(&table.path, &path)?;
+    table.name = new_name.clone();
+    table.path = path;
+    Ok(())
+}
+
+/// Renames a column of a table.
+///
+/// Rows are encoded positionally in schema order, so only the schema changes; the
+/// stored pages are left untouched and the new name is persisted on the next flush.
+pub fn rename_column(table: &mut Table, name: &str, new_name: &str) -> Result<(), Error> {
+    if table.schema.columns().iter().any(|c| c.name == new_name) {
+        return Err(Error::Schema(format!(
+            "Duplicate column name '{}' in '{}'",
+            new_name, table.name
+        )));
+    }
+    let column = Arc::make_mut(&mut table.schema)
+        .columns_mut()
+        .iter_mut()
+        .find(|c| c.name == name)
+        .ok_or_else(|| Error::Schema(format!("Unknown column '{}' in '{}'", name, table.name)))?;
+    column.name = new_name.to_string();
+    Arc::make_mut(&mut table.schema).bump_version();
+    table.mark_dirty();
+    Ok(())
+}
+
+/// Reads the tablespace header of an on-disk table without loading its pages.
+///
+/// Returns the header together with the number of pages stored in the file.
+pub fn peek_table(
+    dir: &Path,
+    database: &String,
+    name: &String,
+) -> Result<(TablespaceHeader, usize), Error> {
+    let path = table_path(dir, name);
+    let mut file = std::fs::File::open(&path)
+        .map_err(|e| Error::Storage(format!("Cannot open table '{}.{}'. {}", database, name, e)))?;
+
+    let mut tablespace_header_buf = [0u8; TABLESPACE_HEADER_SIZE];
+    file.read_exact(&mut tablespace_header_buf)?;
+    let tablespace_header: TablespaceHeader = decode_header(&tablespace_header_buf)?;
+
+    let file_size = file.metadata()?.len() as usize;
+    let pages = file_size
+        .saturating_sub(TABLESPACE_HEADER_SIZE + tablespace_header.schema_size as usize)
+        / (PAGE_HEADER_SIZE + PAGE_SIZE);
+    Ok((tablespace_header, pages))
+}
+
+pub fn show_tables(dir: &Path) -> Result<std::vec::Vec<String>, Error> {
+    std::fs::create_dir_all(dir)?;
     let mut tables = std::vec::Vec::new();
-    for entry in std::fs::read_dir(path)? {
+    for entry in std::fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
-        if let Some(f) = path.file_name() {
-            tables.push(f.to_string_lossy().to_string());
+        if path.extension() != Some("tbd".as_ref()) {
+            continue;
+        }
+        if let Some(stem) = path.file_stem() {
+            tables.push(stem.to_string_lossy().to_string());
         }
     }
     Ok(tables)
 }
 
+/// Returns the path of the tablespace file of table `name` in the database
+/// directory `dir`.
+fn table_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.tbd", name))
+}
+
 fn decode_header<T: Decode<()>>(bytes: &[u8]) -> Result<T, Error> {
     let (decoded, _): (T, usize) = bincode::decode_from_slice(&bytes, config::standard())
         .map_err(|e| Error::Encoding(format!("Failed to encode header. {}", e)))?;
@@ -824,3 +1610,524 @@ fn encode_header<T: Encode, const N: usize>(header: &T) -> Result<[u8; N], Error
     header[..encoded.len()].copy_from_slice(&encoded);
     Ok(header)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::storage;
+    use tempfile::TempDir;
+
+    fn user_row(id: u32) -> row::Row {
+        storage::build_row(
+            &SCHEMA,
+            &["id".into(), "email".into()],
+            &[id.to_string(), format!("user{}@example.com", id)],
+        )
+        .unwrap()
+    }
+
+    /// Creates a `users` table in a fresh test database holding a row for each id.
+    fn users_table(ids: impl IntoIterator<Item = u32>) -> (TempDir, Database, Table) {
+        let (dir, db) = Database::open_test().unwrap();
+        let mut table =
+            create_table(db.path(), &db.name, &"users".to_string(), SCHEMA.clone()).unwrap();
+        for id in ids {
+            insert_row(&mut table, &user_row(id)).unwrap();
+        }
+        (dir, db, table)
+    }
+
+    #[test]
+    fn test_flush_on_drop() {
+        let (_dir, db, table) = users_table([1]);
+        let name = "users".to_string();
+        assert!(table.is_dirty());
+        drop(table);
+
+        let (header, pages) = peek_table(db.path(), &db.name, &name).unwrap();
+        assert_eq!((header.table_n_recs, pages), (1, 1));
+
+        let table = load_table(db.path(), &db.name, &name).unwrap();
+        assert!(!table.is_dirty());
+    }
+
+    #[test]
+    fn test_duplicate_insert_stays_clean() {
+        let (_dir, _db, mut table) = users_table([1]);
+        table.flush().unwrap();
+        assert!(!table.is_dirty());
+
+        let err = insert_row(&mut table, &user_row(1)).unwrap_err();
+        assert!(matches!(err, Error::Storage(ref msg) if msg == "Duplicate key"));
+        assert!(!table.is_dirty());
+    }
+
+    #[test]
+    fn test_page_checksum_mismatch() {
+        let (_dir, db, mut table) = users_table([]);
+        let name = "users".to_string();
+        table.flush().unwrap();
+        let path = table.path.clone();
+        drop(table);
+        assert!(load_table(db.path(), &db.name, &name).is_ok());
+
+        // Page 0 starts after the tablespace header and the serialized schema
+        let (header, _) = peek_table(db.path(), &db.name, &name).unwrap();
+        let page_start = TABLESPACE_HEADER_SIZE + header.schema_size as usize;
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[page_start + PAGE_HEADER_SIZE + 100] ^= 0xFF;
+        std::fs::write(&path, bytes).unwrap();
+
+        match load_table(db.path(), &db.name, &name) {
+            Err(Error::Storage(msg)) => assert_eq!(msg, "Page 0 checksum mismatch"),
+            Err(e) => panic!("Unexpected error: {}", e),
+            Ok(_) => panic!("Corrupted page was loaded"),
+        }
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(encoding::crc32(b""), 0);
+        assert_eq!(encoding::crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_leaf_node_insert_shifts() {
+        let row_size = SCHEMA.get_row_size();
+        let value = vec![0u8; row_size];
+        let mut ascending = Node::new(&[0u8; PAGE_SIZE], row_size);
+        let mut descending = Node::new(&[0u8; PAGE_SIZE], row_size);
+        initialize_leaf_node(&mut ascending).unwrap();
+        initialize_leaf_node(&mut descending).unwrap();
+
+        let max_cells = ascending.max_cells() as u32;
+        let (mut ascending_shifts, mut descending_shifts) = (0, 0);
+        for i in 0..max_cells {
+            ascending_shifts += leaf_node_insert(&mut ascending, i, i, &value).unwrap();
+            let cell_num = descending.leaf_node_find(max_cells - i).unwrap();
+            descending_shifts +=
+                leaf_node_insert(&mut descending, cell_num, max_cells - i, &value).unwrap();
+        }
+
+        assert_eq!(ascending_shifts, 0);
+        assert_eq!(descending_shifts, max_cells * (max_cells - 1) / 2);
+        for i in 0..max_cells {
+            assert_eq!(ascending.leaf_node_key(i as usize).unwrap(), i);
+            assert_eq!(descending.leaf_node_key(i as usize).unwrap(), i + 1);
+        }
+    }
+
+    #[test]
+    fn test_leaf_node_insert_into_nearly_full_leaf() {
+        let row_size = SCHEMA.get_row_size();
+        let mut node = Node::new(&[0u8; PAGE_SIZE], row_size);
+        initialize_leaf_node(&mut node).unwrap();
+        let max_cells = node.max_cells() as u32;
+
+        // Even keys leave a gap in the middle for the last free cell
+        for i in 0..max_cells - 1 {
+            let value = vec![i as u8; row_size];
+            leaf_node_insert(&mut node, i, i * 2, &value).unwrap();
+        }
+        let middle = max_cells / 2;
+        let cell_num = node.leaf_node_find(middle * 2 - 1).unwrap();
+        assert_eq!(cell_num, middle);
+        let shifted =
+            leaf_node_insert(&mut node, cell_num, middle * 2 - 1, &vec![0xFF; row_size]).unwrap();
+        assert_eq!(shifted, max_cells - 1 - middle);
+
+        assert_eq!(node.leaf_node_num_cells().unwrap(), max_cells);
+        for i in 0..max_cells {
+            let (key, fill) = match i.cmp(&middle) {
+                std::cmp::Ordering::Less => (i * 2, i as u8),
+                std::cmp::Ordering::Equal => (middle * 2 - 1, 0xFF),
+                std::cmp::Ordering::Greater => ((i - 1) * 2, (i - 1) as u8),
+            };
+            assert_eq!(node.leaf_node_key(i as usize).unwrap(), key);
+            assert!(node
+                .leaf_node_value(i as usize)
+                .unwrap()
+                .iter()
+                .all(|b| *b == fill));
+        }
+
+        // A full leaf must be split by the caller instead
+        let err = leaf_node_insert(&mut node, 0, 1, &vec![0; row_size]).unwrap_err();
+        assert!(matches!(err, Error::Storage(_)), "{}", err);
+
+        let mut node = Node::new(&[0u8; PAGE_SIZE], row_size);
+        initialize_leaf_node(&mut node).unwrap();
+        assert!(leaf_node_insert(&mut node, 1, 1, &vec![0; row_size]).is_err());
+    }
+
+    #[test]
+    fn test_insert_ascending_keys() {
+        let (_dir, _db, mut table) = users_table(1..=30);
+
+        let ids: Vec<String> = select_rows(&mut table, None)
+            .unwrap()
+            .iter()
+            .map(|row| row.get_column("id").unwrap())
+            .collect();
+        let expected: Vec<String> = (1..=30).map(|id| id.to_string()).collect();
+        assert_eq!(ids, expected);
+
+        let ids: Vec<String> = select_rows(&mut table, Some(5))
+            .unwrap()
+            .iter()
+            .map(|row| row.get_column("id").unwrap())
+            .collect();
+        assert_eq!(ids, expected[..5]);
+    }
+
+    #[test]
+    fn test_insert_random_keys() {
+        let (_dir, db) = Database::open_test().unwrap();
+        // Small rows, so several hundred keys split internal nodes without hitting
+        // the page limit.
+        let schema = Arc::new(TableSchema::new(vec![
+            SCHEMA.columns()[0].clone(),
+            ColumnSchema {
+                name: "name".into(),
+                type_: ColumnType::VARCHAR(100),
+                default: None,
+                is_primary: false,
+                is_nullable: false,
+            },
+        ]));
+        let mut table =
+            create_table(db.path(), &db.name, &"users".to_string(), schema.clone()).unwrap();
+
+        // A fixed permutation of 1..=500, from a linear congruential generator
+        let mut ids: Vec<u32> = (1..=500).collect();
+        let mut seed: u64 = 42;
+        for i in (1..ids.len()).rev() {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ids.swap(i, (seed >> 33) as usize % (i + 1));
+        }
+        for &id in &ids {
+            let row = storage::build_row(
+                &schema,
+                &["id".into(), "name".into()],
+                &[id.to_string(), format!("user{}", id)],
+            )
+            .unwrap();
+            insert_row(&mut table, &row).unwrap();
+        }
+        assert!(table.pager.depth(table.root_page_num).unwrap() > 2);
+
+        let found: Vec<u32> = select_rows(&mut table, None)
+            .unwrap()
+            .iter()
+            .map(|row| row.get_id(&schema).unwrap())
+            .collect();
+        assert_eq!(found, (1..=500).collect::<Vec<_>>());
+        for id in [1, 250, 500] {
+            assert!(table.contains_key(id).unwrap(), "key {}", id);
+        }
+    }
+
+    #[test]
+    fn test_create_table_requires_primary_key() {
+        let (_dir, db) = Database::open_test().unwrap();
+        let column = |name: &str, type_: ColumnType, is_primary: bool| ColumnSchema {
+            name: name.into(),
+            type_,
+            default: None,
+            is_primary,
+            is_nullable: true,
+        };
+        let schema = |columns| Arc::new(TableSchema::new(columns));
+
+        let err = create_table(
+            db.path(),
+            &db.name,
+            &"t".into(),
+            schema(vec![column("a", ColumnType::INT, false)]),
+        )
+        .err()
+        .expect("table without a primary key was created");
+        assert_eq!(
+            err.to_string(),
+            "[5000] Schema Error: Table must have a primary key"
+        );
+
+        let err = create_table(
+            db.path(),
+            &db.name,
+            &"t".into(),
+            schema(vec![column("a", ColumnType::VARCHAR(8), true)]),
+        )
+        .err()
+        .expect("table with a VARCHAR primary key was created");
+        assert!(matches!(err, Error::Schema(_)), "{}", err);
+
+        let err = create_table(
+            db.path(),
+            &db.name,
+            &"t".into(),
+            schema(vec![
+                column("a", ColumnType::INT, true),
+                column("b", ColumnType::INT, true),
+            ]),
+        )
+        .err()
+        .expect("table with a composite primary key was created");
+        assert_eq!(
+            err.to_string(),
+            "[5000] Schema Error: Composite primary keys are not supported"
+        );
+        assert!(!db.path().join("t.tbd").exists());
+
+        create_table(
+            db.path(),
+            &db.name,
+            &"t".into(),
+            schema(vec![column("a", ColumnType::INT, true)]),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_get_waits_for_busy_node() {
+        let mut pager = Pager::new(SCHEMA.get_row_size() as u32);
+        pager.try_create(0).unwrap();
+        let pager = &pager;
+
+        thread::scope(|scope| {
+            let guard = pager.get(0).unwrap();
+            let waiter = scope.spawn(move || pager.get(0).map(|_| ()));
+            thread::sleep(Duration::from_millis(20));
+            drop(guard);
+            assert!(waiter.join().unwrap().is_ok());
+        });
+
+        // A node that is never released still fails once the wait runs out
+        let _guard = pager.get(0).unwrap();
+        thread::scope(|scope| {
+            let err = scope.spawn(|| pager.get(0).map(|_| ())).join().unwrap();
+            assert!(matches!(err, Err(Error::LockTable(_))));
+        });
+    }
+
+    #[test]
+    fn test_page_limit() {
+        let mut pager = Pager::new(SCHEMA.get_row_size() as u32);
+        for page_num in 0..TABLE_MAX_PAGES as u32 {
+            pager.try_create(page_num).unwrap();
+        }
+        assert_eq!(pager.len(), TABLE_MAX_PAGES);
+        let err = pager.try_create(TABLE_MAX_PAGES as u32).unwrap_err();
+        assert!(matches!(err, Error::ResourceLimit(_)), "{}", err);
+
+        let (_dir, _db, mut table) = users_table([]);
+        // Fill the page cap so the root leaf cannot split
+        for page_num in 1..TABLE_MAX_PAGES as u32 {
+            table.pager.try_create(page_num).unwrap();
+        }
+
+        let max_cells = table.pager.get(0).unwrap().max_cells() as u32;
+        let mut result = Ok(0);
+        let mut id = 0;
+        while result.is_ok() {
+            id += 1;
+            result = insert_row(&mut table, &user_row(id));
+        }
+        assert_eq!(id, max_cells + 1);
+        assert!(matches!(result, Err(Error::ResourceLimit(_))));
+        assert_eq!(table.pager.table_n_recs().unwrap(), max_cells);
+        assert_eq!(
+            select_rows(&mut table, None).unwrap().len(),
+            max_cells as usize
+        );
+    }
+
+    #[test]
+    fn test_optimize_deep_tree() {
+        let (_dir, db, mut table) = users_table([]);
+        let name = "users".to_string();
+        let rows: Vec<row::Row> = (1..=200).map(user_row).collect();
+        // Two levels of internal nodes above the packed leaves.
+        table.pager = bulk_load(&SCHEMA, &rows).unwrap();
+        assert_eq!(table.pager.depth(0).unwrap(), 3);
+        optimize_table(&mut table).unwrap();
+
+        let ids = |rows: &[row::Row]| {
+            rows.iter()
+                .map(|r| r.get_id(&SCHEMA).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&select_rows(&mut table, None).unwrap()), ids(&rows));
+        for id in [1, 77, 200] {
+            let row = find_row(&mut table, id).unwrap().unwrap();
+            assert_eq!(row.get_id(&SCHEMA).unwrap(), id);
+        }
+        drop(table);
+
+        let mut table = load_table(db.path(), &db.name, &name).unwrap();
+        assert_eq!(table.pager.table_n_recs().unwrap(), 200);
+        assert_eq!(select_rows(&mut table, None).unwrap().len(), 200);
+    }
+
+    #[test]
+    fn test_contains_key() {
+        let (_dir, _db, mut table) = users_table([]);
+        assert!(!table.contains_key(1).unwrap());
+        // Even keys only, enough of them to split the root.
+        for id in (2..=60).step_by(2) {
+            insert_row(&mut table, &user_row(id)).unwrap();
+        }
+        assert_eq!(
+            table.pager.get(0).unwrap().get_node_type().unwrap(),
+            NodeType::NodeInternal
+        );
+
+        for id in [2, 30, 60] {
+            assert!(table.contains_key(id).unwrap(), "key {}", id);
+        }
+        for id in [0, 1, 31, 61] {
+            assert!(!table.contains_key(id).unwrap(), "key {}", id);
+        }
+    }
+
+    #[test]
+    fn test_find_separator_keys() {
+        let (_dir, _db, mut table) = users_table([]);
+        let insert = |table: &mut Table, id: u32| {
+            insert_row(table, &user_row(id)).unwrap();
+        };
+        let separators = |table: &Table| {
+            let root = table.pager.get(table.root_page_num).unwrap();
+            (0..root.internal_node_num_keys().unwrap())
+                .map(|i| root.internal_node_key(i).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let find = |table: &mut Table, key: u32| {
+            let cursor = cursor::Cursor::find(table, key).unwrap();
+            let leaf = cursor.table.pager.get(cursor.page_num).unwrap();
+            leaf.leaf_node_key(cursor.cell_num as usize).unwrap()
+        };
+
+        // Gaps of three keys, so inserts can land right next to each separator.
+        let mut ids: Vec<u32> = (1..=20).map(|i| i * 4).collect();
+        for &id in &ids {
+            insert(&mut table, id);
+        }
+        for round in 1..=3 {
+            let separators = separators(&table);
+            assert!(!separators.is_empty());
+            for &separator in &separators {
+                assert_eq!(find(&mut table, separator), separator);
+                // Fill the gaps on both sides of the separator until they run out
+                for id in [separator - round, separator + round] {
+                    if id % 4 != 0 && !ids.contains(&id) {
+                        insert(&mut table, id);
+                        ids.push(id);
+                    }
+                }
+            }
+            for &id in &ids {
+                assert_eq!(find(&mut table, id), id, "round {}", round);
+            }
+        }
+
+        ids.sort();
+        let found: Vec<u32> = select_rows(&mut table, None)
+            .unwrap()
+            .iter()
+            .map(|row| row.get_id(&SCHEMA).unwrap())
+            .collect();
+        assert_eq!(found, ids);
+    }
+
+    #[test]
+    fn test_keys() {
+        let (_dir, _db, mut table) = users_table([]);
+        assert!(table.keys().unwrap().is_empty());
+        assert_eq!(table.max_key().unwrap(), None);
+
+        let ids = [
+            17, 3, 29, 8, 1, 22, 12, 30, 5, 26, 14, 9, 19, 2, 27, 11, 24, 6, 20, 15,
+        ];
+        for id in ids {
+            insert_row(&mut table, &user_row(id)).unwrap();
+        }
+        assert!(table.page_count() > 2);
+
+        let mut expected = ids.to_vec();
+        expected.sort();
+        assert_eq!(table.keys().unwrap(), expected);
+        assert_eq!(table.max_key().unwrap(), Some(30));
+    }
+
+    #[test]
+    fn test_cursor_seek_edges() {
+        let (_dir, _db, mut table) = users_table([]);
+        {
+            let cursor = cursor::Cursor::end(&mut table).unwrap();
+            assert!(cursor.end_of_table);
+            assert!(cursor::Cursor::seek_last(&mut table).unwrap().end_of_table);
+        }
+        for id in 1..=30 {
+            insert_row(&mut table, &user_row(id)).unwrap();
+        }
+        assert!(table.pager.depth(table.root_page_num).unwrap() > 1);
+
+        let key_at = |table: &Table, page_num: u32, cell_num: u32| {
+            table
+                .pager
+                .get(page_num)
+                .unwrap()
+                .leaf_node_key(cell_num as usize)
+                .unwrap()
+        };
+        let (page_num, cell_num) = {
+            let cursor = cursor::Cursor::seek_first(&mut table).unwrap();
+            (cursor.page_num, cursor.cell_num)
+        };
+        assert_eq!(key_at(&table, page_num, cell_num), 1);
+
+        let (page_num, cell_num) = {
+            let cursor = cursor::Cursor::seek_last(&mut table).unwrap();
+            assert!(!cursor.end_of_table);
+            (cursor.page_num, cursor.cell_num)
+        };
+        assert_eq!(key_at(&table, page_num, cell_num), 30);
+
+        let cursor = cursor::Cursor::end(&mut table).unwrap();
+        assert!(cursor.end_of_table);
+        assert_eq!(cursor.page_num, page_num);
+        assert_eq!(cursor.cell_num, cell_num + 1);
+        let leaf = cursor.table.pager.get(cursor.page_num).unwrap();
+        assert_eq!(leaf.get_node_type().unwrap(), NodeType::NodeLeaf);
+        assert_eq!(leaf.leaf_node_next_leaf().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_flush_failure_keeps_file() {
+        let (_dir, db, mut table) = users_table(1..=20);
+        let name = "users".to_string();
+        table.flush().unwrap();
+        let saved = std::fs::read(&table.path).unwrap();
+        assert!(table.pager.len() > 1);
+
+        // Hold a page so the write fails after the temporary file was created.
+        table.mark_dirty();
+        let last_page = Arc::clone(table.pager.pages.last().unwrap());
+        let guard = last_page.lock().unwrap();
+        let err = table.flush().unwrap_err();
+        assert!(matches!(err, Error::LockTable(_)), "{}", err);
+        drop(guard);
+
+        assert_eq!(std::fs::read(&table.path).unwrap(), saved);
+        assert!(!table.path.with_extension("tbd.tmp").exists());
+        assert!(table.is_dirty());
+        table.flush().unwrap();
+        drop(table);
+        let table = load_table(db.path(), &db.name, &name).unwrap();
+        assert_eq!(table.pager.table_n_recs().unwrap(), 20);
+    }
+}
@@ -0,0 +1,267 @@
+use std::process::Command;
+use tempfile::TempDir;
+
+fn mysqlite(dir: &TempDir, sql: &str, flags: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_mysqlite"))
+        .current_dir(dir.path())
+        .args(flags)
+        .args(["--execute", sql])
+        .output()
+        .expect("Failed to run mysqlite")
+}
+
+#[test]
+fn test_execute_statements() {
+    let dir = TempDir::new().unwrap();
+    let output = mysqlite(
+        &dir,
+        "CREATE TABLE users (id INT PRIMARY KEY, username VARCHAR(32), email VARCHAR(255)); \
+         INSERT INTO users (id, username, email) VALUES (1, 'alice', 'alice@example.com'); \
+         SELECT * FROM users;",
+        &[],
+    );
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("alice@example.com"));
+    assert!(stdout.contains("1 row in set"));
+}
+
+#[test]
+fn test_execute_failing_statement() {
+    let dir = TempDir::new().unwrap();
+    let output = mysqlite(&dir, "SELECT * FROM missing; SELECT 1;", &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!String::from_utf8_lossy(&output.stderr).is_empty());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("1 row in set"));
+}
+
+#[test]
+fn test_execute_batch() {
+    let dir = TempDir::new().unwrap();
+    let output = mysqlite(
+        &dir,
+        "CREATE TABLE users (id INT PRIMARY KEY, username VARCHAR(32)); \
+         INSERT INTO users (id, username) VALUES (1, 'alice'); \
+         INSERT INTO users (id, username) VALUES (2, 'bob'); \
+         SELECT id, username FROM users;",
+        &["--batch"],
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "id\tusername\n1\talice\n2\tbob\n"
+    );
+}
+
+#[test]
+fn test_init_file() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("init.sql"),
+        "CREATE TABLE settings (id INT PRIMARY KEY, name VARCHAR(16));\n\
+         INSERT INTO settings (id, name) VALUES (1, 'theme');\n",
+    )
+    .unwrap();
+    let output = mysqlite(
+        &dir,
+        "SELECT name FROM settings;",
+        &["--batch", "--init-file", "init.sql"],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "name\ntheme\n");
+}
+
+#[test]
+fn test_init_file_errors() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("init.sql"), "SELECT * FROM missing;").unwrap();
+
+    // By default the error is printed and startup goes on
+    let output = mysqlite(&dir, "SELECT 1;", &["--init-file", "init.sql"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("init.sql"));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1 row in set"));
+
+    let output = mysqlite(
+        &dir,
+        "SELECT 1;",
+        &["--init-file", "init.sql", "--abort-on-init-error"],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.matches("missing").count(), 1, "{}", stderr);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("1 row in set"));
+}
+
+/// A REPL running on a pseudo-terminal provided by `script`, with its cursor position
+/// queries answered, since there is no real terminal to do it.
+#[cfg(unix)]
+struct Repl {
+    child: std::process::Child,
+    stdin: std::sync::Arc<std::sync::Mutex<std::process::ChildStdin>>,
+    output: std::sync::Arc<std::sync::Mutex<Vec<u8>>>,
+}
+
+#[cfg(unix)]
+impl Repl {
+    /// Starts the REPL in `dir`, with `dir` as its home directory, or returns `None`
+    /// if `script` is not available.
+    fn spawn(dir: &TempDir, flags: &[&str]) -> Option<Repl> {
+        use std::io::{Read, Write};
+        use std::process::Stdio;
+        use std::sync::{Arc, Mutex};
+
+        if Command::new("script").arg("--version").output().is_err() {
+            eprintln!("Skipping: `script` is not available");
+            return None;
+        }
+        let mut child = Command::new("script")
+            .current_dir(dir.path())
+            .env("HOME", dir.path())
+            .args(["-qfec"])
+            .arg(format!(
+                "stty rows 24 cols 80; exec {} {}",
+                env!("CARGO_BIN_EXE_mysqlite"),
+                flags.join(" ")
+            ))
+            .arg("/dev/null")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to run script");
+
+        let stdin = Arc::new(Mutex::new(child.stdin.take().unwrap()));
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let mut stdout = child.stdout.take().unwrap();
+        let (reply, seen) = (stdin.clone(), output.clone());
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 4096];
+            while let Ok(n) = stdout.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                let mut seen = seen.lock().unwrap();
+                let before = seen.windows(4).filter(|w| w == b"\x1b[6n").count();
+                seen.extend_from_slice(&buf[..n]);
+                let after = seen.windows(4).filter(|w| w == b"\x1b[6n").count();
+                for _ in before..after {
+                    reply.lock().unwrap().write_all(b"\x1b[1;1R").unwrap();
+                }
+            }
+        });
+        let repl = Repl {
+            child,
+            stdin,
+            output,
+        };
+        repl.wait_for("mysqlite> ", 1);
+        Some(repl)
+    }
+
+    /// Waits until `text` was printed `count` times.
+    fn wait_for(&self, text: &str, count: usize) {
+        use std::time::{Duration, Instant};
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while String::from_utf8_lossy(&self.output.lock().unwrap())
+            .matches(text)
+            .count()
+            < count
+        {
+            assert!(
+                Instant::now() < deadline,
+                "Timed out waiting for {:?}",
+                text
+            );
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    /// Types the statements one by one, waiting for each to succeed.
+    fn execute(&self, statements: &[&str]) {
+        use std::io::Write;
+
+        for (i, statement) in statements.iter().enumerate() {
+            let line = format!("{}\r", statement);
+            self.stdin
+                .lock()
+                .unwrap()
+                .write_all(line.as_bytes())
+                .unwrap();
+            // Every result ends with the execution time
+            self.wait_for(" sec)", i + 1);
+        }
+    }
+
+    /// Sends SIGTERM to the REPL itself, which `exec` made the direct child of `script`.
+    fn terminate(&self) {
+        let pid = String::from_utf8(
+            Command::new("pgrep")
+                .args(["-P", &self.child.id().to_string()])
+                .output()
+                .unwrap()
+                .stdout,
+        )
+        .unwrap();
+        assert!(Command::new("kill")
+            .args(["-TERM", pid.trim()])
+            .status()
+            .unwrap()
+            .success());
+    }
+
+    /// Waits for the REPL to exit, returning whether it succeeded.
+    fn wait(mut self) -> bool {
+        self.child.wait().unwrap().success()
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_sigterm_flushes_repl() {
+    let dir = TempDir::new().unwrap();
+    let Some(repl) = Repl::spawn(&dir, &[]) else {
+        return;
+    };
+    repl.execute(&[
+        "CREATE TABLE t (id INT PRIMARY KEY);",
+        "INSERT INTO t (id) VALUES (1);",
+    ]);
+    repl.terminate();
+    assert!(repl.wait());
+
+    let output = mysqlite(&dir, "SELECT * FROM t;", &["--batch"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "id\n1\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_no_history() {
+    let history = |dir: &TempDir| std::fs::read_to_string(dir.path().join(".mysqlite_history"));
+    let run = |dir: &TempDir, flags: &[&str]| {
+        let Some(repl) = Repl::spawn(dir, flags) else {
+            return false;
+        };
+        repl.execute(&["SELECT 1;"]);
+        repl.terminate();
+        assert!(repl.wait());
+        true
+    };
+
+    let dir = TempDir::new().unwrap();
+    if !run(&dir, &[]) {
+        return;
+    }
+    assert_eq!(history(&dir).unwrap(), "SELECT 1;\n");
+
+    // An existing history file is left as is
+    run(&dir, &["--no-history"]);
+    assert_eq!(history(&dir).unwrap(), "SELECT 1;\n");
+
+    let dir = TempDir::new().unwrap();
+    run(&dir, &["--no-history"]);
+    assert!(history(&dir).is_err());
+}
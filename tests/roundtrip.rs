@@ -0,0 +1,42 @@
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Rows inserted by the round trip, enough to split the table into several pages.
+const ROWS: u32 = 30;
+
+fn mysqlite(dir: &TempDir, sql: &str) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_mysqlite"))
+        .current_dir(dir.path())
+        .args(["--batch", "--execute", sql])
+        .output()
+        .expect("Failed to run mysqlite");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// Writes a table in one process and reads it back in another, so every row has
+/// to survive the flush to disk and the reload of the table file.
+#[test]
+fn test_persistence_roundtrip() {
+    let dir = TempDir::new().unwrap();
+    let mut sql = String::from(
+        "CREATE TABLE users (id INT PRIMARY KEY, username VARCHAR(32), email VARCHAR(255));",
+    );
+    // Insert out of order so the reloaded scan order comes from the btree
+    for id in (1..=ROWS).rev() {
+        sql.push_str(&format!(
+            "INSERT INTO users (id, username, email) VALUES ({id}, 'user{id}', 'user{id}@example.com');"
+        ));
+    }
+    mysqlite(&dir, &sql);
+
+    let mut expected = String::from("id\tusername\temail\n");
+    for id in 1..=ROWS {
+        expected.push_str(&format!("{id}\tuser{id}\tuser{id}@example.com\n"));
+    }
+    assert_eq!(mysqlite(&dir, "SELECT * FROM users ORDER BY id;"), expected);
+}